@@ -0,0 +1,57 @@
+//! Circuit cost of a one-block hash for each SHA-512 backend.
+//!
+//! Only the `table16` backend exists in this crate; further backends should be added
+//! here as extra bench functions so their costs can be compared side by side.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use sha512_halo2::sha512::{blocks_from_bytes, Sha512, Table16Chip, Table16Config};
+
+#[derive(Default)]
+struct Table16OneBlock {}
+
+impl Circuit<Fr> for Table16OneBlock {
+    type Config = Table16Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let blocks = blocks_from_bytes(b"abc");
+        Sha512::hash_blocks(table16_chip, layouter.namespace(|| "'abc'"), &blocks)?;
+
+        Ok(())
+    }
+}
+
+fn table16(c: &mut Criterion) {
+    let circuit = Table16OneBlock::default();
+    let k = Table16Config::minimum_k(1);
+    println!("table16: k = {}, rows = {}", k, Table16Config::minimum_rows(1));
+
+    c.bench_function("table16 one-block MockProver synthesis", |b| {
+        b.iter(|| MockProver::<Fr>::run(k, &circuit, vec![]).expect("synthesis should not fail"))
+    });
+}
+
+criterion_group! {
+    name = backends;
+    config = Criterion::default().sample_size(10);
+    targets = table16
+}
+criterion_main!(backends);
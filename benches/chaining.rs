@@ -0,0 +1,106 @@
+//! Cost of chaining the state through a 64-block message.
+//!
+//! A counting global allocator reports how many heap allocations one MockProver
+//! synthesis of the message makes, alongside the time criterion measures. `State` only
+//! holds cell handles, so chaining it between blocks moves it rather than allocating.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use sha512_halo2::sha512::{
+    blocks_from_bytes, Sha512Instructions, State, Table16Chip, Table16Config, BLOCK_SIZE,
+};
+
+/// Counts every allocation made through the system allocator.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The number of padded blocks in the benchmarked message.
+const BLOCKS: usize = 64;
+
+#[derive(Default)]
+struct Chained {}
+
+impl Circuit<Fr> for Chained {
+    type Config = Table16Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        // Leaves exactly enough room in the last block for the padding and length.
+        let message = vec![0x61; BLOCKS * BLOCK_SIZE * 8 - 17];
+        let blocks = blocks_from_bytes(&message);
+        assert_eq!(blocks.len(), BLOCKS);
+
+        // The state is updated in place between blocks.
+        let mut state = table16_chip.initialization_vector(&mut layouter)?;
+        for block in blocks {
+            let compressed = table16_chip.compress_block(&mut layouter, &state, block)?;
+            state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+        }
+        table16_chip.digest_cells(&mut layouter, &state)?;
+
+        Ok(())
+    }
+}
+
+fn chaining(c: &mut Criterion) {
+    let circuit = Chained::default();
+    let k = Table16Config::minimum_k(BLOCKS);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    MockProver::<Fr>::run(k, &circuit, vec![]).expect("synthesis should not fail");
+    println!(
+        "{} blocks: k = {}, {} allocations, State is {} bytes",
+        BLOCKS,
+        k,
+        ALLOCATIONS.load(Ordering::Relaxed) - before,
+        std::mem::size_of::<State>(),
+    );
+
+    c.bench_function("table16 64-block MockProver synthesis", |b| {
+        b.iter(|| MockProver::<Fr>::run(k, &circuit, vec![]).expect("synthesis should not fail"))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = chaining
+}
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Cost of key generation for a multi-block circuit.
+//!
+//! Key generation synthesizes [`Circuit::without_witnesses`], whose message is
+//! `Value::unknown()`. The witness closures of the chip only run on known values, so
+//! this measures the layout and the fixed columns rather than the hash itself.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::{
+    halo2curves::bn256::Bn256,
+    plonk::{keygen_pk, keygen_vk, Circuit},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use sha512_halo2::sha512::{circuits::PreimageCircuit, Table16Config};
+
+/// The length of the message, which pads to four blocks.
+const MESSAGE_BYTES: usize = 4 * 128 - 17;
+
+fn keygen(c: &mut Criterion) {
+    let circuit = PreimageCircuit::new(&[0x61; MESSAGE_BYTES]).without_witnesses();
+    let k = Table16Config::minimum_k(circuit.num_blocks());
+    println!("{} blocks: k = {}", circuit.num_blocks(), k);
+
+    let rng = XorShiftRng::seed_from_u64(0x5eed);
+    let params = ParamsKZG::<Bn256>::setup(k, rng);
+
+    c.bench_function("keygen_vk of a four-block preimage circuit", |b| {
+        b.iter(|| keygen_vk(&params, &circuit).expect("keygen_vk should not fail"))
+    });
+    c.bench_function("keygen_pk of a four-block preimage circuit", |b| {
+        b.iter(|| {
+            let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+            keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail")
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = keygen
+}
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+//! Cost of the message schedule on its own, apart from the compression rounds.
+//!
+//! Loading the spread table dominates a MockProver run, so a circuit that only loads it
+//! is measured next to one that also expands `SCHEDULES` blocks; the difference is the
+//! cost of the schedules.
+//!
+//! The schedule of a block takes 1504 rows, a quarter of the 6000 of a one-block hash:
+//!
+//! - `W_0`: 6 rows, to decompose it for σ_0 in `W_16`;
+//! - `W_1..W_14`: 13 × 14 rows, to decompose each word and compute its σ_0;
+//! - `W_14..W_65`: 51 × 22 rows, to decompose each word and compute its σ_0 and σ_1;
+//! - `W_65..W_78`: 13 × 14 rows, to decompose each word and compute its σ_1;
+//! - `W_78..W_80`: 2 × 6 rows, to decompose the last words.
+//!
+//! Each word is decomposed once, and each σ term is computed once, where it is first
+//! needed, then copied into the words that add it.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use sha512_halo2::sha512::{blocks_from_bytes, Table16Chip, Table16Config};
+
+/// The number of blocks expanded in the measured circuit.
+const SCHEDULES: usize = 8;
+
+struct ScheduleOnly {
+    schedules: usize,
+}
+
+impl Circuit<Fr> for ScheduleOnly {
+    type Config = Table16Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            schedules: self.schedules,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let block = blocks_from_bytes(b"abc")[0];
+        for _ in 0..self.schedules {
+            table16_chip.message_schedule(&mut layouter, block)?;
+        }
+        Ok(())
+    }
+}
+
+fn message_schedule(c: &mut Criterion) {
+    // The schedules fit beside the table in the rows of a one-block hash.
+    let k = Table16Config::minimum_k(1);
+
+    for schedules in [0, SCHEDULES] {
+        let circuit = ScheduleOnly { schedules };
+        c.bench_function(&format!("{} message schedules, MockProver", schedules), |b| {
+            b.iter(|| MockProver::<Fr>::run(k, &circuit, vec![]).expect("synthesis should not fail"))
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = message_schedule
+}
+criterion_main!(benches);
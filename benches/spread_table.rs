@@ -0,0 +1,67 @@
+//! Cost of loading the spread table repeatedly, as a test suite or a batch prover
+//! does once per circuit.
+//!
+//! The table rows are computed on the first load and shared by later ones, so the
+//! first MockProver synthesis is timed on its own before criterion measures batches of
+//! warm loads.
+
+use std::time::Instant;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use sha512_halo2::sha512::{Table16Chip, Table16Config};
+
+/// The number of loads in each measured batch.
+const LOADS: usize = 50;
+
+/// The smallest `k` that fits the 2^16 rows of the table and the blinding rows.
+const K: u32 = 17;
+
+#[derive(Default)]
+struct LoadOnly {}
+
+impl Circuit<Fr> for LoadOnly {
+    type Config = Table16Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        Table16Chip::load(config, &mut layouter)
+    }
+}
+
+fn spread_table(c: &mut Criterion) {
+    let circuit = LoadOnly::default();
+
+    let start = Instant::now();
+    MockProver::<Fr>::run(K, &circuit, vec![]).expect("synthesis should not fail");
+    println!("first load, computing the table: {:?}", start.elapsed());
+
+    c.bench_function("spread table, 50 loads", |b| {
+        b.iter(|| {
+            for _ in 0..LOADS {
+                MockProver::<Fr>::run(K, &circuit, vec![]).expect("synthesis should not fail");
+            }
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = spread_table
+}
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+//! Key generation, proving and verification shared by the proving examples.
+
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit},
+    poly::{
+        commitment::ParamsProver,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand::rngs::OsRng;
+
+/// Sets up parameters for `2^k` rows, generates the keys of `circuit`, proves it with
+/// `public` as its only instance column and verifies the proof.
+pub fn prove_and_verify<C: Circuit<Fr>>(k: u32, circuit: C, public: &[Fr]) {
+    let instances: &[&[Fr]] = &[public];
+
+    let params = ParamsKZG::<Bn256>::setup(k, OsRng);
+    let vk = keygen_vk(&params, &circuit.without_witnesses()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &circuit.without_witnesses())
+        .expect("keygen_pk should not fail");
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        _,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        C,
+    >(&params, &pk, &[circuit], &[instances], OsRng, &mut transcript)
+    .expect("proof generation should not fail");
+    let proof = transcript.finalize();
+    println!("Proof size: {} bytes", proof.len());
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        SingleStrategy<'_, Bn256>,
+    >(
+        params.verifier_params(),
+        pk.get_vk(),
+        strategy,
+        &[instances],
+        &mut transcript,
+    )
+    .expect("proof verification should not fail");
+    println!("Proof verified");
+}
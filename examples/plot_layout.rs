@@ -0,0 +1,29 @@
+//! Renders the layout of the single-block hash circuit to a PNG image, to see how the
+//! regions of the chip fill the rows counted by `Table16Config::minimum_rows`.
+//!
+//! Run with `cargo run --example plot_layout --features dev [output.png]`.
+
+use halo2_proofs::{dev::CircuitLayout, halo2curves::bn256::Fr};
+use plotters::prelude::*;
+
+use sha512_halo2::sha512::{blocks_from_bytes, circuits::SingleBlockHashCircuit, Table16Config};
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "sha512-layout.png".to_string());
+
+    let circuit = SingleBlockHashCircuit::new(blocks_from_bytes(b"abc")[0]);
+    let k = Table16Config::minimum_k(1);
+
+    let root = BitMapBackend::new(&path, (1024, 3480)).into_drawing_area();
+    root.fill(&WHITE).unwrap();
+    let root = root
+        .titled(&format!("SHA-512 of one block, k = {}", k), ("sans-serif", 60))
+        .unwrap();
+
+    CircuitLayout::default()
+        .render::<Fr, _, _>(k, &circuit, &root)
+        .expect("the layout should render");
+    println!("Wrote the layout of a one-block hash with k = {} to {}", k, path);
+}
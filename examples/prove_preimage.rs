@@ -0,0 +1,38 @@
+//! Proves knowledge of a SHA-512 preimage of a committed digest with the KZG backend,
+//! keeping the length of the message private as well.
+//!
+//! The message is a private witness of at most `MAX_BLOCKS * 128 - 17` bytes. Its digest
+//! is packed into three field elements of an instance column, so the verifier only
+//! learns the digest.
+//!
+//! Run with `cargo run --release --example prove_preimage [message]`.
+
+mod common;
+
+use halo2_proofs::halo2curves::bn256::Fr;
+use sha2::Digest;
+
+use sha512_halo2::sha512::circuits::{packed_digest_instance, BoundedPreimageCircuit};
+
+/// The number of blocks the padded message may span.
+const MAX_BLOCKS: usize = 2;
+type PreimageCircuit = BoundedPreimageCircuit<MAX_BLOCKS>;
+
+fn main() {
+    let message = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "abc".to_string())
+        .into_bytes();
+    let capacity = MAX_BLOCKS * 128 - 17;
+    assert!(message.len() <= capacity, "the message must be at most {} bytes", capacity);
+
+    // The public input: the digest packed into three field elements.
+    let expected = sha2::Sha512::digest(&message);
+    let public: Vec<Fr> = packed_digest_instance(expected.as_slice().try_into().unwrap());
+
+    let circuit = PreimageCircuit::new(&message);
+    let k = PreimageCircuit::minimum_k();
+    println!("Proving a preimage of {:x} with k = {}", expected, k);
+
+    common::prove_and_verify(k, circuit, &public);
+}
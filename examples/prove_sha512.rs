@@ -0,0 +1,34 @@
+//! Proves knowledge of a SHA-512 preimage of a public digest with the KZG backend.
+//!
+//! The message is a private witness. The eight 64-bit words of its digest are copied
+//! into an instance column, so the verifier only learns the digest.
+//!
+//! Run with `cargo run --release --example prove_sha512 [message]`.
+
+mod common;
+
+use halo2_proofs::halo2curves::bn256::Fr;
+use sha2::Digest;
+
+use sha512_halo2::sha512::{
+    circuits::{digest_instance, PreimageCircuit},
+    Table16Config, DIGEST_SIZE,
+};
+
+fn main() {
+    let message = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "abc".to_string())
+        .into_bytes();
+
+    // The public input: the digest as eight big-endian 64-bit words.
+    let expected = sha2::Sha512::digest(&message);
+    let public: Vec<Fr> = digest_instance(expected.as_slice().try_into().unwrap());
+    assert_eq!(public.len(), DIGEST_SIZE);
+
+    let circuit = PreimageCircuit::new(&message);
+    let k = Table16Config::minimum_k(circuit.num_blocks());
+    println!("Proving a preimage of {:x} with k = {}", expected, k);
+
+    common::prove_and_verify(k, circuit, &public);
+}
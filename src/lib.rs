@@ -1 +1,7 @@
-pub mod sha512;
\ No newline at end of file
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod reference;
+#[cfg(feature = "std")]
+pub mod sha512;
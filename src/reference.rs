@@ -0,0 +1,321 @@
+//! The pure computations behind the circuit: the SHA-512 constants, a software
+//! reference implementation, and the bit conversions used to fill witnesses.
+//!
+//! Nothing here depends on halo2 or on `std`, so this module also builds with
+//! `default-features = false` on targets that only provide `alloc`.
+
+use alloc::vec::Vec;
+
+/// The size of a SHA-512 block, in 64-bit words.
+pub const BLOCK_SIZE: usize = 16;
+/// The size of a SHA-512 digest, in 64-bit words.
+pub const DIGEST_SIZE: usize = 8;
+
+/// The number of rounds of the SHA-512 compression function.
+pub const ROUNDS: usize = 80;
+/// The size of the SHA-512 state, in 64-bit words.
+pub const STATE: usize = 8;
+
+/// The round constants `K_0..K_79`.
+#[allow(clippy::unreadable_literal)]
+pub const ROUND_CONSTANTS: [u64; ROUNDS] = [
+            0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc, 0x3956c25bf348b538, 
+            0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118, 0xd807aa98a3030242, 0x12835b0145706fbe, 
+            0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2, 0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 
+            0xc19bf174cf692694, 0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65, 
+            0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5, 0x983e5152ee66dfab, 
+            0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4, 0xc6e00bf33da88fc2, 0xd5a79147930aa725, 
+            0x06ca6351e003826f, 0x142929670a0e6e70, 0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 
+            0x53380d139d95b3df, 0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b, 
+            0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30, 0xd192e819d6ef5218, 
+            0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8, 0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 
+            0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8, 0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 
+            0x682e6ff3d6b2b8a3, 0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec, 
+            0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b, 0xca273eceea26619c, 
+            0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178, 0x06f067aa72176fba, 0x0a637dc5a2c898a6, 
+            0x113f9804bef90dae, 0x1b710b35131c471b, 0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 
+            0x431d67c49c100d4c, 0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+/// The initial hash value `H^(0)`.
+pub const IV: [u64; STATE] = [
+    0x6a09e667f3bcc908, 
+    0xbb67ae8584caa73b, 
+    0x3c6ef372fe94f82b, 
+    0xa54ff53a5f1d36f1, 
+    0x510e527fade682d1, 
+    0x9b05688c2b3e6c1f, 
+    0x1f83d9abfb41bd6b, 
+    0x5be0cd19137e2179,
+];
+
+/// The initial hash value of SHA-384, from FIPS 180-4 section 5.3.4.
+pub const SHA384_IV: [u64; STATE] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
+/// Fails to compile any instantiation of [`i2lebsp`] or [`lebs2ip`] with more bits
+/// than a `u128` holds, rather than panicking when it is called.
+struct MaxBits<const NUM_BITS: usize>;
+
+impl<const NUM_BITS: usize> MaxBits<NUM_BITS> {
+    const CHECK: () = assert!(NUM_BITS <= 128, "a u128 holds at most 128 bits");
+}
+
+/// The sequence of bits representing a u64 in little-endian order.
+///
+/// `NUM_BITS` must be at most 128, which is checked at compile time. In debug builds,
+/// panics if `int` does not fit in `NUM_BITS` bits, as its high bits would be dropped.
+pub fn i2lebsp<const NUM_BITS: usize>(int: u128) -> [bool; NUM_BITS] {
+    /// Takes in an FnMut closure and returns a constant-length array with elements of
+    /// type `Output`.
+    fn gen_const_array<Output: Copy + Default, const LEN: usize>(
+        closure: impl FnMut(usize) -> Output,
+    ) -> [Output; LEN] {
+        gen_const_array_with_default(Default::default(), closure)
+    }
+
+    fn gen_const_array_with_default<Output: Copy, const LEN: usize>(
+        default_value: Output,
+        closure: impl FnMut(usize) -> Output,
+    ) -> [Output; LEN] {
+        let mut ret: [Output; LEN] = [default_value; LEN];
+        for (bit, val) in ret.iter_mut().zip((0..LEN).map(closure)) {
+            *bit = val;
+        }
+        ret
+    }
+
+    let () = MaxBits::<NUM_BITS>::CHECK;
+    debug_assert!(
+        NUM_BITS == 128 || int >> NUM_BITS == 0,
+        "{:#x} does not fit in {} bits",
+        int,
+        NUM_BITS
+    );
+    gen_const_array(|mask: usize| (int & (1 << mask)) != 0)
+}
+
+/// Returns the integer representation of a little-endian bit-array.
+/// `K` must be at most 128, which is checked at compile time.
+pub fn lebs2ip<const K: usize>(bits: &[bool; K]) -> u128 {
+    let () = MaxBits::<K>::CHECK;
+    bits.iter()
+        .enumerate()
+        .fold(0u128, |acc, (i, b)| acc + if *b { 1 << i } else { 0 })
+}
+
+/// Software SHA-512 message schedule: expands `block` into the words `W_0..W_79` using
+/// `σ_0` and `σ_1`.
+pub fn reference_message_schedule(block: [u64; BLOCK_SIZE]) -> [u64; ROUNDS] {
+    let mut w = [0u64; ROUNDS];
+    w[..BLOCK_SIZE].copy_from_slice(&block);
+    for i in BLOCK_SIZE..ROUNDS {
+        let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+        let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+    w
+}
+
+/// Software SHA-512 compression of `block` from `state`, applying only the first
+/// `rounds` rounds and no feed-forward.
+pub fn reference_compress_rounds(
+    state: [u64; STATE],
+    block: [u64; BLOCK_SIZE],
+    rounds: usize,
+) -> [u64; STATE] {
+    assert!(rounds <= ROUNDS);
+
+    let w = reference_message_schedule(block);
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for i in 0..rounds {
+        let sigma_1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ (!e & g);
+        let h_prime = h
+            .wrapping_add(sigma_1)
+            .wrapping_add(ch)
+            .wrapping_add(ROUND_CONSTANTS[i])
+            .wrapping_add(w[i]);
+        let sigma_0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(h_prime);
+        d = c;
+        c = b;
+        b = a;
+        a = h_prime.wrapping_add(sigma_0).wrapping_add(maj);
+    }
+    [a, b, c, d, e, f, g, h]
+}
+
+/// Software SHA-512 compression function: all rounds of `block` from `state`, followed
+/// by the feed-forward of `state`.
+pub fn reference_compress(state: [u64; STATE], block: [u64; BLOCK_SIZE]) -> [u64; STATE] {
+    let compressed = reference_compress_rounds(state, block, ROUNDS);
+    let mut next = state;
+    for (word, compressed) in next.iter_mut().zip(compressed) {
+        *word = word.wrapping_add(compressed);
+    }
+    next
+}
+
+/// Applies SHA-512 padding to `msg` and packs the result into big-endian 64-bit words.
+///
+/// The message is followed by a single `0x80` byte, zeros, and the message length in
+/// bits as a 128-bit big-endian integer. A message of 112 bytes or more modulo 128 does
+/// not leave room for the length, and spills into an extra block.
+pub fn pad_message(msg: &[u8]) -> Vec<[u64; BLOCK_SIZE]> {
+    let bit_len = (msg.len() as u128) * 8;
+
+    let mut padded = msg.to_vec();
+    padded.push(0x80);
+    while padded.len() % (BLOCK_SIZE * 8) != (BLOCK_SIZE - 2) * 8 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&bit_len.to_be_bytes());
+
+    padded
+        .chunks_exact(BLOCK_SIZE * 8)
+        .map(|chunk| {
+            let mut block = [0u64; BLOCK_SIZE];
+            for (word, bytes) in block.iter_mut().zip(chunk.chunks_exact(8)) {
+                *word = u64::from_be_bytes(bytes.try_into().expect("bytes.len() == 8"));
+            }
+            block
+        })
+        .collect()
+}
+
+/// Software SHA-512 of `msg`, used to fold constant messages and as an oracle in tests.
+pub fn reference_sha512(msg: &[u8]) -> [u8; 64] {
+    let mut state = IV;
+    for block in pad_message(msg) {
+        state = reference_compress(state, block);
+    }
+
+    let mut digest = [0u8; 64];
+    for (bytes, word) in digest.chunks_exact_mut(8).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// Derives the initial hash value of SHA-512/t, as specified in FIPS 180-4, section
+/// 5.3.6: the SHA-512 digest of the ASCII string `"SHA-512/t"`, computed from [`IV`] with
+/// each word XORed with `0xa5a5a5a5a5a5a5a5`.
+///
+/// # Panics
+///
+/// Panics unless `t` is between 1 and 511 and not 384, which has its own IV,
+/// [`SHA384_IV`].
+pub fn sha512_t_iv(t: u32) -> [u64; STATE] {
+    assert!(
+        (1..512).contains(&t) && t != 384,
+        "SHA-512/t is defined for 0 < t < 512 and t != 384, got {}",
+        t
+    );
+
+    let mut state = IV.map(|word| word ^ 0xa5a5_a5a5_a5a5_a5a5);
+    for block in pad_message(alloc::format!("SHA-512/{}", t).as_bytes()) {
+        state = reference_compress(state, block);
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{i2lebsp, lebs2ip, reference_compress, reference_sha512, sha512_t_iv, IV};
+    use hex_literal::hex;
+
+    #[test]
+    fn sha512_t_ivs() {
+        // FIPS 180-4, sections 5.3.6.1 and 5.3.6.2.
+        assert_eq!(
+            sha512_t_iv(224),
+            [
+                0x8c3d37c819544da2, 0x73e1996689dcd4d6, 0x1dfab7ae32ff9c82, 0x679dd514582f9fcf,
+                0x0f6d2b697bd44da8, 0x77e36f7304c48942, 0x3f9d85a86a1d36c8, 0x1112e6ad91d692a1,
+            ]
+        );
+        assert_eq!(
+            sha512_t_iv(256),
+            [
+                0x22312194fc2bf72c, 0x9f555fa3c84c64c2, 0x2393b86b6f53b151, 0x963877195940eabd,
+                0x96283ee2a88effe3, 0xbe5e1e2553863992, 0x2b0199fc2c85b8aa, 0x0eb72ddc81c52ca2,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "SHA-512/t is defined")]
+    fn sha512_t_iv_rejects_384() {
+        sha512_t_iv(384);
+    }
+
+    #[test]
+    fn reference_sha512_nist() {
+        // FIPS 180-2, Appendix C.1 and C.2.
+        assert_eq!(
+            reference_sha512(b"abc"),
+            hex!("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f")
+        );
+        assert_eq!(
+            reference_sha512(b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu"),
+            hex!("8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909")
+        );
+    }
+
+    #[test]
+    fn reference_compress_abc() {
+        let mut block = [0; 16];
+        block[0] = 0x6162638000000000;
+        block[15] = 24;
+
+        let digest = reference_compress(IV, block);
+        assert_eq!(digest[0], 0xddaf35a193617aba);
+        assert_eq!(digest[7], 0x2a9ac94fa54ca49f);
+    }
+
+    #[test]
+    fn lebs2ip_i2lebsp_round_trip() {
+        fn round_trip<const NUM_BITS: usize>(int: u128) {
+            let bits = i2lebsp::<NUM_BITS>(int);
+            assert_eq!(lebs2ip(&bits), int, "{} bits", NUM_BITS);
+        }
+
+        for int in [0, 1, 0x8000_0000, 0xdead_beef, u32::MAX as u128] {
+            round_trip::<32>(int);
+        }
+        for int in [0, 1, 1 << 63, 0x6a09_e667_f3bc_c908, u64::MAX as u128] {
+            round_trip::<64>(int);
+        }
+        for int in [0, 1, 1 << 127, u64::MAX as u128 + 1, u128::MAX] {
+            round_trip::<128>(int);
+        }
+
+        // The top bit is the last of the sequence.
+        assert!(i2lebsp::<32>(0x8000_0000)[31]);
+        assert_eq!(i2lebsp::<64>(1 << 63)[..63], [false; 63]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "does not fit in 32 bits")]
+    fn i2lebsp_rejects_wide_int() {
+        i2lebsp::<32>(1 << 32);
+    }
+}
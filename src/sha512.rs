@@ -4,18 +4,287 @@ use std::fmt;
 
 use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::{
-    circuit::{Chip, Layouter},
-    plonk::Error,
+    circuit::{Chip, Layouter, Value},
+    plonk::{Advice, Column, Error},
 };
 
+#[cfg(not(feature = "table16"))]
+compile_error!("a SHA-512 backend must be selected; enable the `table16` feature");
+
+#[cfg(feature = "table16")]
 mod table16;
 
-pub use table16::{BlockWord, Table16Chip, Table16Config,IV};
+#[cfg(feature = "table16")]
+pub use table16::{
+    AssignedBits, BlockWord, Endianness, RoundWordSpread, Sha512Hasher, Sha512Params,
+    Sha512Variant, State, Table16Chip, Table16Config, VariantDigest, Visibility, WordParams, IV,
+};
+
+#[cfg(feature = "table16")]
+pub use table16::circuits;
+#[cfg(feature = "table16")]
+pub use table16::spread_table;
+#[cfg(all(feature = "table16", feature = "trace"))]
+pub use table16::trace;
+#[cfg(all(feature = "table16", feature = "cost_model"))]
+pub use table16::CostModel;
+
+/// The chip of the selected SHA-512 backend.
+#[cfg(feature = "table16")]
+pub type Sha512Chip = Table16Chip;
+/// The configuration of the selected SHA-512 backend.
+#[cfg(feature = "table16")]
+pub type Sha512Config = Table16Config;
+
+pub use crate::reference::{BLOCK_SIZE, DIGEST_SIZE};
+
+/// The errors of the high-level SHA-512 APIs, such as [`Sha512::hash_blocks`] and
+/// [`digest_hex`].
+///
+/// It converts from and into [`plonk::Error`](Error), so `?` works both inside these
+/// APIs and in a [`Circuit::synthesize`](halo2_proofs::plonk::Circuit::synthesize) that
+/// calls them. Variants other than [`Sha512Error::Synthesis`] become
+/// [`Error::Synthesis`].
+#[derive(Debug)]
+pub enum Sha512Error {
+    /// An error from the proving system while laying out the circuit.
+    Synthesis(Error),
+    /// The number of padded blocks given, which cannot be hashed.
+    BadBlockCount(usize),
+    /// A digest word is not known, so the digest has no value outside the circuit.
+    UnknownDigestValue,
+    /// A word did not split into pieces of the lengths `var` expects.
+    DecompositionMismatch {
+        /// The decomposed variable, such as `AbcdVar`.
+        var: &'static str,
+    },
+    /// The wrong number of extra advice columns was given to configure the chip.
+    WrongColumnCount {
+        /// The number of columns the chip needs.
+        expected: usize,
+        /// The number of columns given.
+        given: usize,
+    },
+    /// The same advice column was given more than once to configure the chip.
+    DuplicateColumn(Column<Advice>),
+    /// A message is too long to be padded into the blocks available for it.
+    MessageTooLong {
+        /// The length of the message in bytes.
+        len: usize,
+        /// The longest message whose padding fits in the available blocks.
+        max: usize,
+    },
+    /// The field is too small for the gates of the chip, whose constants would wrap.
+    FieldTooSmall {
+        /// The bit length of the modulus of the field.
+        num_bits: u32,
+        /// The shortest bit length the gates hold in.
+        required: u32,
+    },
+    /// A digest was requested with the given number of words, rather than 1 to 8.
+    BadOutputWords(usize),
+    /// A chaining value was given with the given number of words, rather than 8.
+    BadIvLength(usize),
+    /// A domain tag is too long to be hashed with its length in one block.
+    DomainTooLong {
+        /// The length of the tag in bytes.
+        len: usize,
+        /// The longest tag that fits.
+        max: usize,
+    },
+    /// The chip was given a spread table other than the one it was configured to look
+    /// up into.
+    SpreadTableMismatch,
+    /// A round constant was requested for a round past the last one.
+    RoundOutOfRange {
+        /// The requested round, counted from 0.
+        round: usize,
+        /// The number of rounds, and of round constants.
+        rounds: usize,
+    },
+    /// [`circuits::self_test`] did not give the known digest, for the given reason.
+    #[cfg(feature = "self-test")]
+    SelfTestFailed(String),
+}
+
+impl fmt::Display for Sha512Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sha512Error::Synthesis(err) => write!(f, "synthesis failed: {}", err),
+            Sha512Error::BadBlockCount(count) => write!(f, "cannot hash {} padded blocks", count),
+            Sha512Error::UnknownDigestValue => write!(f, "the digest value is unknown"),
+            Sha512Error::DecompositionMismatch { var } => {
+                write!(f, "{} does not match its decomposition", var)
+            }
+            Sha512Error::WrongColumnCount { expected, given } => write!(
+                f,
+                "Table16Chip requires {} extra advice columns, {} were given",
+                expected, given
+            ),
+            Sha512Error::DuplicateColumn(column) => write!(
+                f,
+                "Table16Chip requires distinct advice columns, {:?} was given twice",
+                column
+            ),
+            Sha512Error::MessageTooLong { len, max } => write!(
+                f,
+                "a message of {} bytes does not fit in the padded blocks, at most {} do",
+                len, max
+            ),
+            Sha512Error::FieldTooSmall { num_bits, required } => write!(
+                f,
+                "the gates need a field of at least {} bits, the field has {}",
+                required, num_bits
+            ),
+            Sha512Error::BadOutputWords(count) => {
+                write!(f, "a digest has 1 to 8 words, {} were requested", count)
+            }
+            Sha512Error::BadIvLength(len) => {
+                write!(f, "a chaining value has 8 words, {} were given", len)
+            }
+            Sha512Error::DomainTooLong { len, max } => write!(
+                f,
+                "a domain tag of {} bytes does not fit in the tag block, at most {} do",
+                len, max
+            ),
+            Sha512Error::SpreadTableMismatch => {
+                write!(f, "the chip does not look up into the given spread table")
+            }
+            Sha512Error::RoundOutOfRange { round, rounds } => write!(
+                f,
+                "there is no round constant for round {}, there are {} rounds",
+                round, rounds
+            ),
+            #[cfg(feature = "self-test")]
+            Sha512Error::SelfTestFailed(reason) => write!(f, "the self-test failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Sha512Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Sha512Error::Synthesis(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for Sha512Error {
+    fn from(err: Error) -> Self {
+        Sha512Error::Synthesis(err)
+    }
+}
+
+impl From<Sha512Error> for Error {
+    fn from(err: Sha512Error) -> Self {
+        match err {
+            Sha512Error::Synthesis(err) => err,
+            _ => Error::Synthesis,
+        }
+    }
+}
+
+/// Applies SHA-512 padding to `msg` and packs the result into big-endian 64-bit
+/// [`BlockWord`]s, ready to be fed to the message schedule.
+///
+/// The message is followed by a single `0x80` byte, zeros, and the message length in
+/// bits as a 128-bit big-endian integer. A message of 112 bytes or more modulo 128 does
+/// not leave room for the length, and spills into an extra block.
+#[cfg(feature = "table16")]
+pub fn blocks_from_bytes(msg: &[u8]) -> Vec<[BlockWord; BLOCK_SIZE]> {
+    crate::reference::pad_message(msg)
+        .into_iter()
+        .map(|block| block.map(|word| BlockWord(Value::known(word))))
+        .collect()
+}
 
-/// The size of a SHA-512 block, in 64-bit words.
-pub const BLOCK_SIZE: usize = 16;
-/// The size of a SHA-512 block, in 64-bit words.
-const DIGEST_SIZE: usize = 8;
+/// One 1024-bit block of input to the message schedule, as sixteen big-endian words.
+#[cfg(feature = "table16")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Block(pub [BlockWord; BLOCK_SIZE]);
+
+#[cfg(feature = "table16")]
+impl Block {
+    /// The longest message that [`Block::try_from`] pads into a single block: the
+    /// padding takes at least a `0x80` byte and the 16-byte length.
+    pub const MAX_MESSAGE_BYTES: usize = BLOCK_SIZE * 8 - 17;
+}
+
+/// Packs 128 bytes into a block as they are, without padding.
+#[cfg(feature = "table16")]
+impl From<[u8; BLOCK_SIZE * 8]> for Block {
+    fn from(bytes: [u8; BLOCK_SIZE * 8]) -> Self {
+        let mut block = [BlockWord::default(); BLOCK_SIZE];
+        for (word, bytes) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+            let bytes = bytes.try_into().expect("chunks of 8 bytes");
+            *word = BlockWord(Value::known(u64::from_be_bytes(bytes)));
+        }
+        Block(block)
+    }
+}
+
+#[cfg(feature = "table16")]
+impl From<Block> for [BlockWord; BLOCK_SIZE] {
+    fn from(block: Block) -> Self {
+        block.0
+    }
+}
+
+/// Pads a message of at most [`Block::MAX_MESSAGE_BYTES`] bytes into a single block, as
+/// [`blocks_from_bytes`] does.
+#[cfg(feature = "table16")]
+impl TryFrom<&[u8]> for Block {
+    type Error = Sha512Error;
+
+    fn try_from(msg: &[u8]) -> Result<Self, Self::Error> {
+        if msg.len() > Block::MAX_MESSAGE_BYTES {
+            return Err(Sha512Error::MessageTooLong {
+                len: msg.len(),
+                max: Block::MAX_MESSAGE_BYTES,
+            });
+        }
+        Ok(Block(blocks_from_bytes(msg)[0]))
+    }
+}
+
+/// Returns the canonical lowercase hex encoding of `digest`, each word written
+/// big-endian.
+///
+/// # Errors
+///
+/// Returns [`Sha512Error::UnknownDigestValue`] if any word is unknown.
+#[cfg(feature = "table16")]
+pub fn digest_hex(digest: &[BlockWord; DIGEST_SIZE]) -> Result<String, Sha512Error> {
+    let mut hex = String::with_capacity(DIGEST_SIZE * 16);
+    for word in digest.iter() {
+        let mut known = None;
+        word.0.map(|word| known = Some(word));
+        let word = known.ok_or(Sha512Error::UnknownDigestValue)?;
+        hex.push_str(&format!("{:016x}", word));
+    }
+    Ok(hex)
+}
+
+/// Parses the 128 hex digits of a digest, as returned by [`digest_hex`], into known
+/// [`BlockWord`]s. Upper and lower case digits are accepted; any other input gives
+/// `None`.
+#[cfg(feature = "table16")]
+pub fn digest_from_hex(hex: &str) -> Option<[BlockWord; DIGEST_SIZE]> {
+    if hex.len() != DIGEST_SIZE * 16 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut digest = [BlockWord::default(); DIGEST_SIZE];
+    for (word, digits) in digest.iter_mut().zip(hex.as_bytes().chunks_exact(16)) {
+        let digits = std::str::from_utf8(digits).ok()?;
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return None;
+        }
+        *word = BlockWord(Value::known(u64::from_str_radix(digits, 16).ok()?));
+    }
+    Some(digest)
+}
 
 /// The set of circuit instructions required to use the [`Sha512`] gadget.
 pub trait Sha512Instructions<F: FieldExt>: Chip<F> {
@@ -36,7 +305,8 @@ pub trait Sha512Instructions<F: FieldExt>: Chip<F> {
     ) -> Result<Self::State, Error>;
 
     /// Starting from the given initialized state, processes a block of input and returns the
-    /// final state.
+    /// final state. The feed-forward is not added; see
+    /// [`Sha512Instructions::add_feed_forward`].
     fn compress(
         &self,
         layouter: &mut impl Layouter<F>,
@@ -44,6 +314,22 @@ pub trait Sha512Instructions<F: FieldExt>: Chip<F> {
         input: [Self::BlockWord; BLOCK_SIZE],
     ) -> Result<Self::State, Error>;
 
+    /// Adds the state `prev` a block was compressed from to the `compressed` state, word
+    /// by word modulo $2^{64}$, giving the chaining value after that block.
+    ///
+    /// The default returns `compressed` unchanged, which is right for a chip whose
+    /// [`Sha512Instructions::compress`] already adds the feed-forward, as chips written
+    /// before this method was added do. A chip whose `compress` leaves it out must
+    /// override this method.
+    fn add_feed_forward(
+        &self,
+        _layouter: &mut impl Layouter<F>,
+        _prev: &Self::State,
+        compressed: &Self::State,
+    ) -> Result<Self::State, Error> {
+        Ok(compressed.clone())
+    }
+
     /// Converts the given state into a message digest.
     fn digest(
         &self,
@@ -52,10 +338,146 @@ pub trait Sha512Instructions<F: FieldExt>: Chip<F> {
     ) -> Result<[Self::BlockWord; DIGEST_SIZE], Error>;
 }
 
+/// A SHA-2 gadget hashing messages of assigned words, so that code generic over the
+/// hash, such as a Merkle tree, can be written once and instantiated with SHA-512 or
+/// another member of the family. Every block of the family holds [`BLOCK_SIZE`] words.
+///
+/// [`ShaGadget::digest`] shares its name with [`Sha512Instructions::digest`], so call
+/// it as `ShaGadget::digest(&chip, ..)` where both traits are in scope.
+pub trait ShaGadget<F: FieldExt>: Chip<F> {
+    /// The number of bytes of a digest.
+    const DIGEST_BYTES: usize;
+    /// The number of bytes of a message block.
+    const BLOCK_BYTES: usize;
+
+    /// An assigned word of a message or a digest.
+    type Word: Clone + fmt::Debug;
+    /// The chaining value between blocks.
+    type State: Clone + fmt::Debug;
+
+    /// Assigns the padding of a message of `message_bytes` bytes as constant words.
+    fn padding(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        message_bytes: usize,
+    ) -> Result<Vec<Self::Word>, Error>;
+
+    /// Hashes `words`, a padded message of whole blocks, from the IV and returns the
+    /// chaining value after the last block. The words are copy-constrained into the
+    /// hash.
+    fn hash_blocks(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        words: &[Self::Word],
+    ) -> Result<Self::State, Error>;
+
+    /// Returns the digest words of `state`.
+    fn digest(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        state: &Self::State,
+    ) -> Result<Vec<Self::Word>, Error>;
+
+    /// Pads `message` and returns the words of its digest.
+    fn hash(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        message: &[Self::Word],
+    ) -> Result<Vec<Self::Word>, Error> {
+        let message_bytes = message.len() * Self::BLOCK_BYTES / BLOCK_SIZE;
+        let mut words = message.to_vec();
+        words.extend(self.padding(layouter, message_bytes)?);
+        let state = self.hash_blocks(layouter, &words)?;
+        ShaGadget::digest(self, layouter, &state)
+    }
+}
+
 /// The output of a SHA-512 circuit invocation.
 #[derive(Debug)]
 pub struct Sha512Digest<BlockWord>(pub [BlockWord; DIGEST_SIZE]);
 
+/// The digest returned by [`Sha512::digest`], [`Sha512::finalize`] and
+/// [`Sha512::hash_blocks`] with the table16 chip.
+#[cfg(feature = "table16")]
+pub type Digest = Sha512Digest<BlockWord>;
+
+#[cfg(feature = "table16")]
+impl Sha512Digest<BlockWord> {
+    /// Returns the words of the digest.
+    pub fn as_words(&self) -> &[BlockWord; DIGEST_SIZE] {
+        &self.0
+    }
+
+    /// Returns the 64 bytes of the digest, each word written big-endian.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::UnknownDigestValue`] if any word is unknown.
+    pub fn to_bytes(&self) -> Result<[u8; DIGEST_SIZE * 8], Sha512Error> {
+        let mut bytes = [0u8; DIGEST_SIZE * 8];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(self.0.iter()) {
+            let mut known = None;
+            word.0.map(|word| known = Some(word));
+            let word = known.ok_or(Sha512Error::UnknownDigestValue)?;
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Returns the lowercase hex encoding of the digest, as [`digest_hex`] does.
+    pub fn to_hex(&self) -> Result<String, Sha512Error> {
+        digest_hex(&self.0)
+    }
+}
+
+/// Two digests are equal if all their words are known and equal. A digest with an
+/// unknown word equals no digest, not even itself.
+#[cfg(feature = "table16")]
+impl PartialEq for Sha512Digest<BlockWord> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.to_bytes(), other.to_bytes()) {
+            (Ok(lhs), Ok(rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "table16")]
+impl From<[BlockWord; DIGEST_SIZE]> for Sha512Digest<BlockWord> {
+    fn from(words: [BlockWord; DIGEST_SIZE]) -> Self {
+        Sha512Digest(words)
+    }
+}
+
+#[cfg(feature = "table16")]
+impl From<Sha512Digest<BlockWord>> for [BlockWord; DIGEST_SIZE] {
+    fn from(digest: Sha512Digest<BlockWord>) -> Self {
+        digest.0
+    }
+}
+
+/// Serializes a known digest as the 128 hex digits of [`digest_hex`].
+#[cfg(all(feature = "table16", feature = "serde"))]
+impl serde::Serialize for Sha512Digest<BlockWord> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hex = digest_hex(&self.0).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&hex)
+    }
+}
+
+/// Deserializes a digest from the 128 hex digits accepted by [`digest_from_hex`].
+#[cfg(all(feature = "table16", feature = "serde"))]
+impl<'de> serde::Deserialize<'de> for Sha512Digest<BlockWord> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, Unexpected};
+
+        let hex = String::deserialize(deserializer)?;
+        digest_from_hex(&hex)
+            .map(Sha512Digest)
+            .ok_or_else(|| D::Error::invalid_value(Unexpected::Str(&hex), &"128 hex digits"))
+    }
+}
+
 /// A gadget that constrains a SHA-512 invocation. It supports input at a granularity of
 /// 64 bits.
 #[derive(Debug)]
@@ -98,24 +520,27 @@ impl<F: FieldExt, Sha512Chip: Sha512Instructions<F>> Sha512<F, Sha512Chip> {
         }
 
         // Process the now-full current block.
-        self.state = self.chip.compress(
+        let initialized = self.chip.initialization(&mut layouter, &self.state)?;
+        let compressed = self.chip.compress(
             &mut layouter,
-            &self.state,
+            &initialized,
             self.cur_block[..]
                 .try_into()
                 .expect("cur_block.len() == BLOCK_SIZE"),
         )?;
+        self.state = self.chip.add_feed_forward(&mut layouter, &self.state, &compressed)?;
         self.cur_block.clear();
 
         // Process any additional full blocks.
         let mut chunks_iter = data.chunks_exact(BLOCK_SIZE);
         for chunk in &mut chunks_iter {
-            self.state = self.chip.initialization(&mut layouter, &self.state)?;
-            self.state = self.chip.compress(
+            let initialized = self.chip.initialization(&mut layouter, &self.state)?;
+            let compressed = self.chip.compress(
                 &mut layouter,
-                &self.state,
+                &initialized,
                 chunk.try_into().expect("chunk.len() == BLOCK_SIZE"),
             )?;
+            self.state = self.chip.add_feed_forward(&mut layouter, &self.state, &compressed)?;
         }
 
         // Cache the remaining partial block, if any.
@@ -134,14 +559,15 @@ impl<F: FieldExt, Sha512Chip: Sha512Instructions<F>> Sha512<F, Sha512Chip> {
         if !self.cur_block.is_empty() {
             let padding = vec![Sha512Chip::BlockWord::default(); BLOCK_SIZE - self.cur_block.len()];
             self.cur_block.extend_from_slice(&padding);
-            self.state = self.chip.initialization(&mut layouter, &self.state)?;
-            self.state = self.chip.compress(
+            let initialized = self.chip.initialization(&mut layouter, &self.state)?;
+            let compressed = self.chip.compress(
                 &mut layouter,
-                &self.state,
+                &initialized,
                 self.cur_block[..]
                     .try_into()
                     .expect("cur_block.len() == BLOCK_SIZE"),
             )?;
+            self.state = self.chip.add_feed_forward(&mut layouter, &self.state, &compressed)?;
         }
         self.chip
             .digest(&mut layouter, &self.state)
@@ -159,4 +585,53 @@ impl<F: FieldExt, Sha512Chip: Sha512Instructions<F>> Sha512<F, Sha512Chip> {
         hasher.update(layouter.namespace(|| "update"), data)?;
         hasher.finalize(layouter.namespace(|| "finalize"))
     }
+
+    /// Convenience function to compute the hash of already padded blocks, such as those
+    /// returned by [`blocks_from_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::BadBlockCount`] if `blocks` is empty: every padded message
+    /// has at least one block, even the empty message.
+    pub fn hash_blocks(
+        chip: Sha512Chip,
+        layouter: impl Layouter<F>,
+        blocks: &[[Sha512Chip::BlockWord; BLOCK_SIZE]],
+    ) -> Result<Sha512Digest<Sha512Chip::BlockWord>, Sha512Error> {
+        if blocks.is_empty() {
+            return Err(Sha512Error::BadBlockCount(0));
+        }
+        let data: Vec<_> = blocks.iter().flatten().copied().collect();
+        Ok(Self::digest(chip, layouter, &data)?)
+    }
+
+    /// Like [`Sha512::hash_blocks`], but takes the blocks from an iterator and
+    /// compresses each one as it is yielded, keeping only the running state. A message
+    /// of any length is hashed without holding all of its blocks in memory.
+    ///
+    /// The blocks must already be padded: the gadget cannot tell the last block from
+    /// the others, so the iterator must end with the block holding the `0x80` byte and
+    /// the message length, as [`blocks_from_bytes`] or [`Block::try_from`] pad it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::BadBlockCount`] if `blocks` yields no block.
+    pub fn hash_stream(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        blocks: impl IntoIterator<Item = [Sha512Chip::BlockWord; BLOCK_SIZE]>,
+    ) -> Result<Sha512Digest<Sha512Chip::BlockWord>, Sha512Error> {
+        let mut blocks = blocks.into_iter();
+        let first = blocks.next().ok_or(Sha512Error::BadBlockCount(0))?;
+
+        let state = chip.initialization_vector(&mut layouter)?;
+        let compressed = chip.compress(&mut layouter, &state, first)?;
+        let mut state = chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+        for block in blocks {
+            let initialized = chip.initialization(&mut layouter, &state)?;
+            let compressed = chip.compress(&mut layouter, &initialized, block)?;
+            state = chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+        }
+        Ok(chip.digest(&mut layouter, &state).map(Sha512Digest)?)
+    }
 }
\ No newline at end of file
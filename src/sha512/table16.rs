@@ -1,68 +1,206 @@
 use std::convert::TryInto;
 use std::marker::PhantomData;
 
-use super::Sha512Instructions;
+use super::{Sha512Error, Sha512Instructions};
 use halo2_proofs::{
-    circuit::{AssignedCell, Chip, Layouter, Region, Value},
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Cell, Chip, Layouter, Region, Value},
     halo2curves::bn256,
-    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error},
+    plonk::{
+        Advice, Any, Assigned, Column, ConstraintSystem, Error, Instance, Selector, VirtualCells,
+    },
+    poly::Rotation,
 };
 
+pub mod circuits;
 mod compression;
+mod dynamic_length;
+mod field_element;
 mod gates;
+mod hasher;
+mod merkle;
 mod message_schedule;
-mod spread_table;
+pub mod spread_table;
 mod util;
+mod word_params;
 
 use compression::*;
+pub use compression::{RoundWordSpread, State};
+#[cfg(feature = "trace")]
+pub use compression::trace;
+use dynamic_length::*;
+use field_element::*;
 use gates::*;
+pub use hasher::Sha512Hasher;
+use merkle::*;
 use message_schedule::*;
 use spread_table::*;
 use util::*;
+pub use word_params::{Sha512Params, WordParams};
 
-const ROUNDS: usize = 80;
-const STATE: usize = 8;
-
-#[allow(clippy::unreadable_literal)]
-pub(crate) const ROUND_CONSTANTS: [u64; ROUNDS] = [
-            0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc, 0x3956c25bf348b538, 
-            0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118, 0xd807aa98a3030242, 0x12835b0145706fbe, 
-            0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2, 0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 
-            0xc19bf174cf692694, 0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65, 
-            0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5, 0x983e5152ee66dfab, 
-            0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4, 0xc6e00bf33da88fc2, 0xd5a79147930aa725, 
-            0x06ca6351e003826f, 0x142929670a0e6e70, 0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 
-            0x53380d139d95b3df, 0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b, 
-            0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30, 0xd192e819d6ef5218, 
-            0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8, 0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 
-            0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8, 0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 
-            0x682e6ff3d6b2b8a3, 0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec, 
-            0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b, 0xca273eceea26619c, 
-            0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178, 0x06f067aa72176fba, 0x0a637dc5a2c898a6, 
-            0x113f9804bef90dae, 0x1b710b35131c471b, 0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 
-            0x431d67c49c100d4c, 0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
-];
-
-pub const IV: [u64; STATE] = [
-    0x6a09e667f3bcc908, 
-    0xbb67ae8584caa73b, 
-    0x3c6ef372fe94f82b, 
-    0xa54ff53a5f1d36f1, 
-    0x510e527fade682d1, 
-    0x9b05688c2b3e6c1f, 
-    0x1f83d9abfb41bd6b, 
-    0x5be0cd19137e2179,
-];
-
-#[derive(Clone, Copy, Debug, Default)]
+use crate::reference::{sha512_t_iv, ROUNDS, ROUND_CONSTANTS, SHA384_IV, STATE};
+pub use crate::reference::IV;
+
+/// The number of advice columns of this chip besides the message schedule and the
+/// spread lookup inputs.
+const EXTRA_COLUMNS: usize = 6;
+
+/// Whether `Debug` output hides witness values, as with the `secrets` feature unless
+/// `debug-witness` is also enabled. Cells of field elements, which are halo2 types, still
+/// show their values.
+const REDACT_WITNESSES: bool = cfg!(all(feature = "secrets", not(feature = "debug-witness")));
+
+/// Stands in for a witness value in `Debug` output.
+struct Redacted;
+
+impl std::fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Returns `value`, or [`Redacted`] if [`REDACT_WITNESSES`] is set.
+fn witness_debug(value: &dyn std::fmt::Debug) -> &dyn std::fmt::Debug {
+    if REDACT_WITNESSES {
+        &Redacted
+    } else {
+        value
+    }
+}
+
+#[derive(Clone, Copy, Default)]
 /// A word in a `Table16` message block.
 // TODO: Make the internals of this struct private.
 pub struct BlockWord(pub Value<u64>);
 
+impl std::fmt::Debug for BlockWord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BlockWord").field(witness_debug(&self.0)).finish()
+    }
+}
+
+/// Serializes a known word as its 16 lowercase hex digits. An unknown word cannot be
+/// serialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for BlockWord {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut known = None;
+        self.0.map(|word| known = Some(word));
+        match known {
+            Some(word) => serializer.serialize_str(&format!("{:016x}", word)),
+            None => Err(serde::ser::Error::custom("the word value is unknown")),
+        }
+    }
+}
+
+/// Deserializes a known word from 16 hex digits, in upper or lower case.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BlockWord {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, Unexpected};
+
+        let hex = String::deserialize(deserializer)?;
+        if hex.len() != 16 || !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(D::Error::invalid_value(Unexpected::Str(&hex), &"16 hex digits"));
+        }
+        let word = u64::from_str_radix(&hex, 16).map_err(D::Error::custom)?;
+        Ok(BlockWord(Value::known(word)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The byte order of a digest returned by [`Table16Chip::digest_bytes`].
+pub enum Endianness {
+    /// Each word is written most significant byte first, as in the SHA-512
+    /// specification and its test vectors.
+    Big,
+    /// Each word is written least significant byte first. The words themselves stay in
+    /// order.
+    Little,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Whether a message word given to [`Table16Chip::hash_partially_public`] is known to the
+/// verifier.
+pub enum Visibility {
+    /// The word is a constant of the circuit, fixed at key generation.
+    Public,
+    /// The word is a private witness.
+    Private,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A member of the SHA-512 family. All of them run the same compression function and
+/// differ only in their IV and in how much of the final state makes up the digest.
+pub enum Sha512Variant {
+    /// SHA-512, with a 512-bit digest.
+    Sha512,
+    /// SHA-384, with a 384-bit digest.
+    Sha384,
+    /// SHA-512/256, with a 256-bit digest.
+    Sha512_256,
+    /// SHA-512/224, with a 224-bit digest.
+    Sha512_224,
+}
+
+impl Sha512Variant {
+    /// The initial hash value of this variant.
+    pub fn iv(self) -> [u64; STATE] {
+        match self {
+            Sha512Variant::Sha512 => IV,
+            Sha512Variant::Sha384 => SHA384_IV,
+            Sha512Variant::Sha512_256 => sha512_t_iv(256),
+            Sha512Variant::Sha512_224 => sha512_t_iv(224),
+        }
+    }
+
+    /// The length of the digest, in bits.
+    pub fn output_bits(self) -> usize {
+        match self {
+            Sha512Variant::Sha512 => 512,
+            Sha512Variant::Sha384 => 384,
+            Sha512Variant::Sha512_256 => 256,
+            Sha512Variant::Sha512_224 => 224,
+        }
+    }
+
+    /// The number of 64-bit words of the final state that hold the digest. For
+    /// SHA-512/224 the last of them holds only 32 bits of it, in its upper half.
+    pub fn output_words(self) -> usize {
+        (self.output_bits() + 63) / 64
+    }
+}
+
+/// The assigned digest of a [`Sha512Variant`], as returned by
+/// [`Table16Chip::hash_variant`].
 #[derive(Clone, Debug)]
-/// Little-endian bits (up to 64 bits)
+pub struct VariantDigest {
+    /// The words of the final state that are whole words of the digest.
+    pub words: Vec<AssignedBits<64>>,
+    /// For SHA-512/224, the upper 32-bit half of the fourth word of the final state,
+    /// which ends the digest; `None` for the other variants.
+    pub last_half: Option<AssignedBits<32>>,
+}
+
+impl VariantDigest {
+    /// The cells of the digest in order, the whole words and then the last half, for
+    /// instance to constrain to rows of an instance column.
+    pub fn cells(&self) -> Vec<Cell> {
+        let words = self.words.iter().map(|word| word.cell());
+        words.chain(self.last_half.iter().map(|half| half.cell())).collect()
+    }
+}
+
+#[derive(Clone)]
+/// Little-endian bits (up to 128 bits)
 pub struct Bits<const LEN: usize>([bool; LEN]);
 
+impl<const LEN: usize> std::fmt::Debug for Bits<LEN> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Bits").field(witness_debug(&self.0)).finish()
+    }
+}
+
 impl<const LEN: usize> Bits<LEN> {
     fn spread<const SPREAD: usize>(&self) -> [bool; SPREAD] {
         spread_bits(self.0)
@@ -91,8 +229,7 @@ impl<const LEN: usize> From<&Bits<LEN>> for [bool; LEN] {
 
 impl<const LEN: usize> From<&Bits<LEN>> for Assigned<bn256::Fr> {
     fn from(bits: &Bits<LEN>) -> Assigned<bn256::Fr> {
-        assert!(LEN <= 64);
-        bn256::Fr::from(lebs2ip(&bits.0) as u64).into()
+        bn256::Fr::from_u128(lebs2ip(&bits.0)).into()
     }
 }
 impl From<&Bits<16>> for u16 {
@@ -124,6 +261,16 @@ impl From<u64> for Bits<64> {
     fn from(int: u64) -> Bits<64> {
         Bits(i2lebsp::<64>(int.into()))
     }
+}
+impl From<&Bits<128>> for u128 {
+    fn from(bits: &Bits<128>) -> u128 {
+        lebs2ip(&bits.0)
+    }
+}
+impl From<u128> for Bits<128> {
+    fn from(int: u128) -> Bits<128> {
+        Bits(i2lebsp::<128>(int))
+    }
 }    
 
 #[derive(Clone, Debug)]
@@ -270,8 +417,51 @@ impl AssignedBits<64> {
         .map(AssignedBits)
     }
 }
+/// A whole spread word, such as [`RoundWordSpread::value`], in a single cell. The
+/// gates keep spread words as their two 64-bit halves, which are what the spread table
+/// range-checks, so this is only used to hold or compare such a value.
+#[allow(dead_code)]
+impl AssignedBits<128> {
+    fn value_u128(&self) -> Value<u128> {
+        self.value().map(|v| v.into())
+    }
+    fn assign<A, AR>(
+        region: &mut Region<'_, bn256::Fr>,
+        annotation: A,
+        column: impl Into<Column<Any>>,
+        offset: usize,
+        value: Value<u128>,
+    ) -> Result<Self, Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let column: Column<Any> = column.into();
+        let value: Value<Bits<128>> = value.map(|v| v.into());
+        match column.column_type() {
+            Any::Advice(_) => {
+                region.assign_advice(annotation, column.try_into().unwrap(), offset, || {
+                    value.clone()
+                })
+            }
+            Any::Fixed => {
+                region.assign_fixed(annotation, column.try_into().unwrap(), offset, || {
+                    value.clone()
+                })
+            }
+            _ => panic!("Cannot assign to instance column"),
+        }
+        .map(AssignedBits)
+    }
+}
+
 
 
+// The chip is configured over bn256, whose modulus is 254 bits long.
+const _: () = assert!(
+    <bn256::Fr as halo2_proofs::halo2curves::group::ff::PrimeField>::NUM_BITS
+        > Table16Config::MAX_CONSTRAINT_BITS
+);
 
 /// Configuration for a [`Table16Chip`].
 #[derive(Clone, Debug)]
@@ -279,7 +469,213 @@ pub struct Table16Config {
     lookup: SpreadTableConfig,
     message_schedule: MessageScheduleConfig,
     compression: CompressionConfig,
+    merkle: MerkleConfig,
+    field_element: FieldElementConfig,
+    dynamic_length: DynamicLengthConfig,
 }
+impl Table16Config {
+    /// The degree of the constraint system configured by [`Table16Chip::configure`].
+    ///
+    /// The 3-bit range checks of the decomposition gates are products of eight linear
+    /// factors, and their selector adds one. The additions modulo $2^{64}$ and the other
+    /// linear checks are degree 2 with their selector. The extended domain grows with the
+    /// degree, so a gate of higher degree is a regression.
+    pub const MAX_DEGREE: usize = 9;
+
+    /// The bit length of the values the gates of this chip sum in an honest assignment.
+    ///
+    /// The spread form of a 64-bit word is 128 bits wide, and the gates weight its
+    /// pieces by up to $2^{126}$. A Σ or Maj gate sums three spread words, so no
+    /// constraint reaches $2^{130}$. The gates are generic over the field, but only hold
+    /// in a field whose modulus is above that, as otherwise their constants wrap.
+    pub const MAX_CONSTRAINT_BITS: u32 = 130;
+
+    /// Checks that the gates of this chip do not wrap in the field `F`; see
+    /// [`Self::check_field_bits`].
+    pub fn check_field<F: FieldExt>() -> Result<(), Sha512Error> {
+        Self::check_field_bits(F::NUM_BITS)
+    }
+
+    /// Checks that the gates of this chip do not wrap in a field whose modulus is
+    /// `num_bits` bits long.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::FieldTooSmall`] unless `num_bits` is above
+    /// [`Self::MAX_CONSTRAINT_BITS`].
+    pub fn check_field_bits(num_bits: u32) -> Result<(), Sha512Error> {
+        if num_bits <= Self::MAX_CONSTRAINT_BITS {
+            return Err(Sha512Error::FieldTooSmall {
+                num_bits,
+                required: Self::MAX_CONSTRAINT_BITS + 1,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the spread table of this chip and the advice columns looked up into it,
+    /// for gadgets that build on [`SpreadVar`](spread_table::SpreadVar).
+    ///
+    /// The inputs are looked up on every row. Empty rows look up `(0, 0, 0)`, which is in
+    /// the table, but each row a gadget assigns must hold a full `(tag, dense, spread)`
+    /// triple, as [`SpreadVar::with_lookup`](spread_table::SpreadVar::with_lookup)
+    /// writes. The table is loaded by [`Table16Chip::load`].
+    pub fn spread_table(&self) -> &SpreadTableConfig {
+        &self.lookup
+    }
+
+    /// Recomputes the digest of the state whose words `A` to `H` are in rows `0..8` of
+    /// `instance`, without compressing anything, as a verifier does for a state made
+    /// public by another proof. See [`State::from_instance_words`].
+    ///
+    /// The spread table must have been loaded with [`Table16Chip::load`].
+    pub fn synthesize_digest_only(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        instance: Column<Instance>,
+    ) -> Result<[BlockWord; super::DIGEST_SIZE], Error> {
+        let chip = Table16Chip::construct(self.clone());
+        let state = State::from_instance_words(&chip, layouter, instance)?;
+        chip.digest(layouter, &state)
+    }
+
+    /// Returns the number of advice rows the [`Sha512`](super::Sha512) gadget lays out to
+    /// hash a padded message of `num_blocks` blocks, which is 6000 for one block.
+    ///
+    /// Every region of this chip shares the `a_3` column, so the floor planner places
+    /// them one after another, in the order they are assigned, whatever else the circuit
+    /// contains. Each region has a fixed height:
+    ///
+    /// - the IV: 34 rows;
+    /// - the state a block is compressed from: 34 rows;
+    /// - the message schedule of a block: 1504 rows;
+    /// - the range check on the input words of a block: 64 rows;
+    /// - the 80 compression rounds of a block, whose last round writes the new `A` and
+    ///   `E` into the digest rows: 4320 + 6 rows;
+    /// - the feed-forward of a block: 32 rows;
+    /// - the digest: 6 rows.
+    ///
+    /// That is five regions per block, and one each for the IV and the digest. They are
+    /// not merged into one region per block: since they already abut, a merged region
+    /// would take the same rows, and the subregions address their rows from the start of
+    /// their own region. The region boundaries only cost the floor planner its
+    /// bookkeeping.
+    pub fn hash_rows(num_blocks: usize) -> usize {
+        // The IV, then the state each block is compressed from.
+        let initial_rows = (num_blocks + 1) * SUBREGION_INITIAL_ROWS;
+        let block_rows = SCHEDULE_ROWS
+            + INPUT_RANGE_ROWS
+            + SUBREGION_MAIN_ROWS
+            + SUBREGION_DIGEST_ROWS
+            + SUBREGION_FEED_FORWARD_ROWS;
+
+        initial_rows + num_blocks * block_rows + SUBREGION_DIGEST_ROWS
+    }
+
+    /// Returns the number of rows needed to load the spread table and hash a padded
+    /// message of `num_blocks` blocks with the [`Sha512`](super::Sha512) gadget, not
+    /// counting the rows halo2 reserves for blinding.
+    pub fn minimum_rows(num_blocks: usize) -> usize {
+        Self::hash_rows(num_blocks).max(1 << 16)
+    }
+
+    /// Returns the smallest `k` such that $2^k$ rows fit [`Self::minimum_rows`] for
+    /// `num_blocks` blocks, together with the blinding rows of this chip.
+    pub fn minimum_k(num_blocks: usize) -> u32 {
+        let mut meta = ConstraintSystem::<bn256::Fr>::default();
+        Table16Chip::configure(&mut meta);
+        let rows = Self::minimum_rows(num_blocks) + meta.minimum_rows();
+
+        usize::BITS - (rows - 1).leading_zeros()
+    }
+
+    /// Returns the footprint of the constraint system configured by
+    /// [`Table16Chip::configure`], for sizing this chip in a larger circuit.
+    #[cfg(feature = "cost_model")]
+    pub fn cost_model() -> CostModel {
+        let mut meta = ConstraintSystem::<bn256::Fr>::default();
+        Table16Chip::configure(&mut meta);
+        CostModel::from(&meta)
+    }
+}
+
+/// The footprint of a constraint system, as returned by [`Table16Config::cost_model`].
+///
+/// Selectors are counted before halo2 combines them into fixed columns at keygen, so
+/// `fixed_columns` only counts the columns the chip allocates itself.
+#[cfg(feature = "cost_model")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostModel {
+    /// The number of custom gates.
+    pub gates: usize,
+    /// The number of polynomial constraints in all gates.
+    pub constraints: usize,
+    /// The number of lookup arguments.
+    pub lookups: usize,
+    /// The number of advice columns: the message schedule and extra columns shared by
+    /// every region, and the inputs of the spread table lookup.
+    pub advice_columns: usize,
+    /// The number of fixed columns the chip allocates: the three columns of the spread
+    /// table and the column its constants are copied from.
+    pub fixed_columns: usize,
+    /// The number of instance columns, which is zero as the chip exposes nothing by
+    /// itself; the circuit using it adds its own.
+    pub instance_columns: usize,
+    /// The number of selectors, each of which switches one or more gates on at the rows
+    /// it is enabled on.
+    pub selectors: usize,
+    /// The smallest rotation at which any column is queried, relative to the row a gate
+    /// is enabled on.
+    pub min_rotation: i32,
+    /// The largest rotation at which any column is queried, relative to the row a gate
+    /// is enabled on.
+    pub max_rotation: i32,
+    /// The degree of the constraint system, including lookups.
+    pub degree: usize,
+}
+
+#[cfg(feature = "cost_model")]
+impl From<&ConstraintSystem<bn256::Fr>> for CostModel {
+    fn from(meta: &ConstraintSystem<bn256::Fr>) -> Self {
+        let rotations: Vec<i32> = meta
+            .advice_queries()
+            .iter()
+            .map(|(_, rotation)| rotation.0)
+            .chain(meta.fixed_queries().iter().map(|(_, rotation)| rotation.0))
+            .chain(meta.instance_queries().iter().map(|(_, rotation)| rotation.0))
+            .collect();
+
+        CostModel {
+            gates: meta.gates().len(),
+            constraints: meta.gates().iter().map(|gate| gate.polynomials().len()).sum(),
+            lookups: meta.lookups().len(),
+            advice_columns: meta.num_advice_columns(),
+            fixed_columns: meta.num_fixed_columns(),
+            instance_columns: meta.num_instance_columns(),
+            selectors: meta.num_selectors(),
+            min_rotation: rotations.iter().copied().min().unwrap_or(0),
+            max_rotation: rotations.iter().copied().max().unwrap_or(0),
+            degree: meta.degree(),
+        }
+    }
+}
+
+#[cfg(feature = "cost_model")]
+impl std::fmt::Display for CostModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "gates: {} ({} constraints)", self.gates, self.constraints)?;
+        writeln!(f, "lookups: {}", self.lookups)?;
+        writeln!(
+            f,
+            "columns: {} advice, {} fixed, {} instance",
+            self.advice_columns, self.fixed_columns, self.instance_columns
+        )?;
+        writeln!(f, "selectors: {}", self.selectors)?;
+        writeln!(f, "rotations: {}..={}", self.min_rotation, self.max_rotation)?;
+        write!(f, "degree: {}", self.degree)
+    }
+}
+
 /// A chip that implements SHA-512 with a maximum lookup table size of $2^16$.
 #[derive(Clone, Debug)]
 pub struct Table16Chip {
@@ -302,6 +698,9 @@ impl Chip<bn256::Fr> for Table16Chip {
 
 
 impl Table16Chip {
+    /// The number of rows used by one compression round, see [`Table16Chip::assign_round`].
+    pub const ROUND_ROWS: usize = SUBREGION_MAIN_WORD;
+
     /// Reconstructs this chip from the given config.
     pub fn construct(config: <Self as Chip<bn256::Fr>>::Config) -> Self {
         Self {
@@ -314,6 +713,117 @@ impl Table16Chip {
     pub fn configure(
         meta: &mut ConstraintSystem<bn256::Fr>,
     ) -> <Self as Chip<bn256::Fr>>::Config {
+        let (message_schedule, extras, lookup_inputs) = Self::advice_columns(meta);
+        Self::configure_with_columns(meta, message_schedule, extras, lookup_inputs)
+    }
+
+    /// Configures another instance of this chip in the same circuit, looking up into
+    /// the spread table of `shared` instead of allocating a second one.
+    ///
+    /// Only `shared` should be passed to [`Table16Chip::load`]; the returned config
+    /// must not be loaded, since it does not own its spread table.
+    pub fn configure_with_table(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        shared: &Table16Config,
+    ) -> <Self as Chip<bn256::Fr>>::Config {
+        let (message_schedule, extras, lookup_inputs) = Self::advice_columns(meta);
+        Self::configure_columns(
+            meta,
+            message_schedule,
+            extras,
+            lookup_inputs,
+            Some(shared.lookup.table.clone()),
+        )
+    }
+
+    /// Configures this chip to look up into `table`, a spread table allocated by another
+    /// gadget of the circuit, typically with [`SpreadTableChip::configure`].
+    ///
+    /// The other gadget loads the table; pass the returned config to
+    /// [`Table16Chip::load_with_table`] rather than [`Table16Chip::load`], which would
+    /// assign the same table cells twice.
+    pub fn configure_with_spread_table(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        table: SpreadTable,
+    ) -> <Self as Chip<bn256::Fr>>::Config {
+        let (message_schedule, extras, lookup_inputs) = Self::advice_columns(meta);
+        Self::configure_columns(meta, message_schedule, extras, lookup_inputs, Some(table))
+    }
+
+    /// Configures a circuit to include this chip, using advice columns provided by the
+    /// caller instead of allocating new ones.
+    ///
+    /// `lookup_inputs` are the `[tag, dense, spread]` columns looked up in the spread
+    /// table, which is always allocated by this chip as its own fixed table columns.
+    ///
+    /// All ten columns must be distinct, since the chip's gates query them at the same
+    /// rotations. They may be shared with the rest of the circuit: the floor planner
+    /// keeps the chip's regions apart from the caller's regions in shared columns.
+    /// Equality is enabled on every column except the lookup `tag` and `extras[5]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the same column is passed more than once; use
+    /// [`Table16Chip::try_configure_with_columns`] to get an error instead.
+    pub fn configure_with_columns(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        message_schedule: Column<Advice>,
+        extras: [Column<Advice>; EXTRA_COLUMNS],
+        lookup_inputs: [Column<Advice>; 3],
+    ) -> <Self as Chip<bn256::Fr>>::Config {
+        Self::configure_columns(meta, message_schedule, extras, lookup_inputs, None)
+    }
+
+    /// Like [`Table16Chip::configure_with_columns`], but takes `extras` as a slice and
+    /// returns an error instead of panicking when the columns cannot be used.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::WrongColumnCount`] if `extras` does not hold exactly six
+    /// columns, and [`Sha512Error::DuplicateColumn`] if a column is passed more than once,
+    /// including a lookup column reused in `extras`. Returns
+    /// [`Sha512Error::FieldTooSmall`] if the field is too small for the gates, as checked
+    /// by [`Table16Config::check_field`]. Nothing is configured in `meta` in any case.
+    pub fn try_configure_with_columns(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        message_schedule: Column<Advice>,
+        extras: &[Column<Advice>],
+        lookup_inputs: [Column<Advice>; 3],
+    ) -> Result<<Self as Chip<bn256::Fr>>::Config, Sha512Error> {
+        let extras: [Column<Advice>; EXTRA_COLUMNS] =
+            extras.try_into().map_err(|_| Sha512Error::WrongColumnCount {
+                expected: EXTRA_COLUMNS,
+                given: extras.len(),
+            })?;
+        Self::check_columns(message_schedule, &extras, &lookup_inputs)?;
+        Table16Config::check_field::<bn256::Fr>()?;
+        Ok(Self::configure_columns(meta, message_schedule, extras, lookup_inputs, None))
+    }
+
+    /// Checks that the advice columns of this chip are distinct.
+    fn check_columns(
+        message_schedule: Column<Advice>,
+        extras: &[Column<Advice>; EXTRA_COLUMNS],
+        lookup_inputs: &[Column<Advice>; 3],
+    ) -> Result<(), Sha512Error> {
+        let columns: Vec<Column<Advice>> = lookup_inputs
+            .iter()
+            .chain(std::iter::once(&message_schedule))
+            .chain(extras.iter())
+            .copied()
+            .collect();
+        for (i, column) in columns.iter().enumerate() {
+            if columns[..i].contains(column) {
+                return Err(Sha512Error::DuplicateColumn(*column));
+            }
+        }
+        Ok(())
+    }
+
+    /// Allocates the advice columns required by this chip.
+    fn advice_columns(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+    ) -> (Column<Advice>, [Column<Advice>; EXTRA_COLUMNS], [Column<Advice>; 3]) {
         // Columns required by this chip:
         let message_schedule = meta.advice_column();
         let extras = [
@@ -330,7 +840,31 @@ impl Table16Chip {
         let input_dense = meta.advice_column();
         let input_spread = meta.advice_column();
 
-        let lookup = SpreadTableChip::configure(meta, input_tag, input_dense, input_spread);
+        (message_schedule, extras, [input_tag, input_dense, input_spread])
+    }
+
+    fn configure_columns(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        message_schedule: Column<Advice>,
+        extras: [Column<Advice>; EXTRA_COLUMNS],
+        lookup_inputs: [Column<Advice>; 3],
+        table: Option<SpreadTable>,
+    ) -> <Self as Chip<bn256::Fr>>::Config {
+        if let Err(err) = Self::check_columns(message_schedule, &extras, &lookup_inputs) {
+            panic!("{}", err);
+        }
+
+        let [input_tag, input_dense, input_spread] = lookup_inputs;
+        let lookup = match table {
+            Some(table) => SpreadTableChip::configure_with_table(
+                meta,
+                input_tag,
+                input_dense,
+                input_spread,
+                table,
+            ),
+            None => SpreadTableChip::configure(meta, input_tag, input_dense, input_spread),
+        };
         let lookup_inputs = lookup.input.clone();
 
         // Rename these here for ease of matching the gates to the specification.
@@ -350,9 +884,21 @@ impl Table16Chip {
             meta.enable_equality(*column);
         }
 
+        // The IV and other constants are copy-constrained to this column.
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
         let compression =
             CompressionConfig::configure(meta, lookup_inputs.clone(), message_schedule, extras);
 
+        let merkle = MerkleConfig::configure(meta, message_schedule, extras);
+
+        let field_element =
+            FieldElementConfig::configure(meta, lookup_inputs.clone(), message_schedule, extras);
+
+        let dynamic_length =
+            DynamicLengthConfig::configure(meta, lookup_inputs.clone(), message_schedule, extras);
+
         let message_schedule =
             MessageScheduleConfig::configure(meta, lookup_inputs, message_schedule, extras);
 
@@ -360,7 +906,851 @@ impl Table16Chip {
             lookup,
             message_schedule,
             compression,
+            merkle,
+            field_element,
+            dynamic_length,
+        }
+    }
+
+    /// Initializes the state from any 512-bit chaining value `iv`, given as eight 64-bit
+    /// words. With [`IV`] this is [`Sha512Instructions::initialization_vector`]; other
+    /// values resume hashing from a known intermediate state, as in SHA-512/t or
+    /// length extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::BadIvLength`] if `iv` does not hold exactly eight words.
+    pub fn initialize_with_iv(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        iv: &[u64],
+    ) -> Result<State, Sha512Error> {
+        let iv: [u64; STATE] = iv.try_into().map_err(|_| Sha512Error::BadIvLength(iv.len()))?;
+        Ok(self.config().compression.initialize_with_iv(layouter, iv)?)
+    }
+
+    /// Compresses one message block starting from `prev_state`, which may be the output
+    /// of [`Sha512Instructions::initialization_vector`] or of a previous block. The
+    /// feed-forward is not added; see [`Table16Chip::add_feed_forward`].
+    pub fn compress_block(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev_state: &State,
+        input: [BlockWord; super::BLOCK_SIZE],
+    ) -> Result<State, Error> {
+        self.compress_block_rounds(layouter, prev_state, input, ROUNDS)
+    }
+
+    /// Like [`Table16Chip::compress_block`], but only applies the first `rounds` of the
+    /// 80 rounds, with their round constants and schedule words. This gives the
+    /// reduced-round compression function used in differential testing and cryptanalysis,
+    /// not SHA-512.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Synthesis`] if `rounds` is greater than 80.
+    pub fn compress_block_rounds(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev_state: &State,
+        input: [BlockWord; super::BLOCK_SIZE],
+        rounds: usize,
+    ) -> Result<State, Error> {
+        let config = self.config();
+        let initialized_state = config
+            .compression
+            .initialize_with_state(layouter, prev_state.clone())?;
+        let w_halves = config.message_schedule.process(layouter, input)?.halves;
+        config
+            .compression
+            .compress(layouter, initialized_state, &w_halves, rounds)
+    }
+
+    /// Like [`Table16Chip::compress_block`], but takes the block from 64-bit words already
+    /// assigned elsewhere in the circuit. They are copy-constrained into the message
+    /// schedule rather than witnessed again, so the hash is bound to them.
+    pub fn compress_block_from_cells(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev_state: &State,
+        words: &[AssignedBits<64>; super::BLOCK_SIZE],
+    ) -> Result<State, Error> {
+        let config = self.config();
+        let initialized_state = config
+            .compression
+            .initialize_with_state(layouter, prev_state.clone())?;
+        let w_halves = config.message_schedule.process_from_cells(layouter, words)?.halves;
+        config
+            .compression
+            .compress(layouter, initialized_state, &w_halves, ROUNDS)
+    }
+
+    /// Like [`Table16Chip::compress_block_from_cells`], but takes the `(lo, hi)` 32-bit
+    /// halves of the words, the form the message schedule stores them in, so a circuit
+    /// that already holds the block as halves need not recombine it.
+    pub fn compress_block_from_halves(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev_state: &State,
+        halves: &[(AssignedBits<32>, AssignedBits<32>); super::BLOCK_SIZE],
+    ) -> Result<State, Error> {
+        let config = self.config();
+        let initialized_state = config
+            .compression
+            .initialize_with_state(layouter, prev_state.clone())?;
+        let w_halves = config.message_schedule.process_from_halves(layouter, halves)?.halves;
+        config
+            .compression
+            .compress(layouter, initialized_state, &w_halves, ROUNDS)
+    }
+
+    /// Expands a message block into its 80-word message schedule, returning the
+    /// `(lo, hi)` 32-bit halves of each word as consumed by [`Table16Chip::assign_round`].
+    pub fn message_schedule(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        input: [BlockWord; super::BLOCK_SIZE],
+    ) -> Result<[(AssignedBits<32>, AssignedBits<32>); ROUNDS], Error> {
+        let w_halves = self.config().message_schedule.process(layouter, input)?.halves;
+        Ok(w_halves)
+    }
+
+    /// Assigns compression round `round_idx` of a block in `region`, adding the schedule
+    /// word `w_halves`, and returns the state after the round.
+    ///
+    /// The caller manages the region: the 80 rounds of a block must be assigned in order
+    /// in one region, starting from a state returned by
+    /// [`Sha512Instructions::initialization_vector`] or
+    /// [`Sha512Instructions::initialization`]. Round `i` occupies the
+    /// [`Table16Chip::ROUND_ROWS`] rows from offset `i * ROUND_ROWS`, and places the
+    /// decomposition of the new `A` and `E` in the rows of round `i + 1`. The last round
+    /// instead writes the new `A` and `E` in the rows following round 79, so a block spans
+    /// `80 * ROUND_ROWS + 5` rows.
+    ///
+    /// This reproduces [`Table16Chip::compress_block`]:
+    ///
+    /// ```no_run
+    /// # use halo2_proofs::{circuit::Layouter, halo2curves::bn256, plonk::Error};
+    /// # use sha512_halo2::sha512::{BlockWord, Sha512Instructions, State, Table16Chip, BLOCK_SIZE};
+    /// fn compress_block(
+    ///     chip: &Table16Chip,
+    ///     layouter: &mut impl Layouter<bn256::Fr>,
+    ///     prev_state: &State,
+    ///     input: [BlockWord; BLOCK_SIZE],
+    /// ) -> Result<State, Error> {
+    ///     let initialized_state = chip.initialization(layouter, prev_state)?;
+    ///     let w_halves = chip.message_schedule(layouter, input)?;
+    ///     layouter.assign_region(
+    ///         || "compress",
+    ///         |mut region| {
+    ///             let mut state = initialized_state.clone();
+    ///             for (round_idx, w_halves) in w_halves.iter().enumerate() {
+    ///                 state = chip.assign_round(&mut region, round_idx, state, w_halves)?;
+    ///             }
+    ///             Ok(state)
+    ///         },
+    ///     )
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Synthesis`] if `state` does not hold the decomposition of `A`
+    /// and `E`, as for the state returned by [`Table16Chip::compress_block`] or
+    /// [`Table16Chip::add_feed_forward`], which must be initialized first. Also returns
+    /// [`Sha512Error::RoundOutOfRange`], as an [`Error::Synthesis`], if `round_idx` is not
+    /// less than 80.
+    pub fn assign_round(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        round_idx: usize,
+        state: State,
+        w_halves: &(AssignedBits<32>, AssignedBits<32>),
+    ) -> Result<State, Error> {
+        if round_idx >= ROUNDS {
+            return Err(Sha512Error::RoundOutOfRange {
+                round: round_idx,
+                rounds: ROUNDS,
+            }
+            .into());
         }
+        let last_round = round_idx == ROUNDS - 1;
+        self.config()
+            .compression
+            .assign_round(region, round_idx.into(), state, w_halves, last_round)
+    }
+
+    /// Adds `prev` to `compressed` word by word modulo $2^{64}$, giving the chaining
+    /// value $H_i = H_{i-1} + \text{compress}(H_{i-1}, M_i)$.
+    pub fn add_feed_forward(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev: &State,
+        compressed: &State,
+    ) -> Result<State, Error> {
+        self.config()
+            .compression
+            .add_feed_forward(layouter, prev.clone(), compressed.clone())
+    }
+
+    /// Like [`Table16Chip::add_feed_forward`], but also returns the spread form of each
+    /// word `A` to `H` of the chaining value, as its `(lo, hi)` halves. After the last
+    /// block these are the digest words in spread form, in digest order.
+    ///
+    /// The feed-forward already looks up the spread form of each sum to range-check it,
+    /// so this costs no extra rows. Adding the spread forms of two words gives their
+    /// XOR in the even bits and their AND in the odd bits, which is what
+    /// XOR-heavy constructions built on the digest need.
+    pub fn add_feed_forward_spread(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev: &State,
+        compressed: &State,
+    ) -> Result<(State, [RoundWordSpread; super::DIGEST_SIZE]), Error> {
+        self.config()
+            .compression
+            .add_feed_forward_spread(layouter, prev.clone(), compressed.clone())
+    }
+
+    /// Converts the given state into a message digest, like
+    /// [`Sha512Instructions::digest`], but returns the assigned 64-bit words so they can
+    /// be copy-constrained by the caller.
+    pub fn digest_cells(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        state: &State,
+    ) -> Result<[AssignedBits<64>; super::DIGEST_SIZE], Error> {
+        self.config().compression.digest_cells(layouter, state.clone())
+    }
+
+    /// Like [`Table16Chip::digest_cells`], but returns only the first `output_words`
+    /// words, for the variants that truncate the state. All eight words are still
+    /// assigned and range-checked by the digest gate.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::BadOutputWords`] unless `output_words` is between 1 and 8.
+    pub fn digest_words(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        state: &State,
+        output_words: usize,
+    ) -> Result<Vec<AssignedBits<64>>, Sha512Error> {
+        if output_words == 0 || output_words > super::DIGEST_SIZE {
+            return Err(Sha512Error::BadOutputWords(output_words));
+        }
+        let mut words = self.digest_cells(layouter, state)?.to_vec();
+        words.truncate(output_words);
+        Ok(words)
+    }
+
+    /// Hashes the given padded blocks with `variant`, returning its digest as assigned
+    /// cells that the caller may constrain to an instance column.
+    ///
+    /// The digest is made of the first [`Sha512Variant::output_words`] words of the
+    /// final state. For SHA-512/224 the last of them is returned as its upper 32-bit
+    /// half, the cell the digest gate recombines the word from, so the lower half that
+    /// is not part of the digest is never exposed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::BadBlockCount`] if `blocks` is empty.
+    pub fn hash_variant(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        variant: Sha512Variant,
+        blocks: &[[BlockWord; super::BLOCK_SIZE]],
+    ) -> Result<VariantDigest, Sha512Error> {
+        if blocks.is_empty() {
+            return Err(Sha512Error::BadBlockCount(0));
+        }
+        let mut state = self.initialize_with_iv(layouter, &variant.iv())?;
+        for block in blocks {
+            let compressed = self.compress_block(layouter, &state, *block)?;
+            state = self.add_feed_forward(layouter, &state, &compressed)?;
+        }
+        let mut words = self.digest_words(layouter, &state, variant.output_words())?;
+
+        let whole_words = variant.output_bits() / 64;
+        let last_half = if whole_words < words.len() {
+            words.truncate(whole_words);
+            let (_, hi) = state.into_digest_words()?[whole_words].clone().into();
+            Some(hi)
+        } else {
+            None
+        };
+        Ok(VariantDigest { words, last_half })
+    }
+
+    /// Packs the 64 bytes of `digest` into three field elements and constrains them to
+    /// rows `0..3` of `instance`, which must have equality enabled. Returns the packed
+    /// cells.
+    ///
+    /// Element `i` is the big-endian integer of bytes `24 * i..min(24 * i + 24, 64)` of
+    /// the digest, that is, of words `3 * i..min(3 * i + 3, 8)`. Every element is below
+    /// $2^{192}$, so none wraps the modulus, and a Solidity verifier reconstructs the
+    /// digest from the public inputs `p0`, `p1` and `p2` as
+    ///
+    /// ```text
+    /// abi.encodePacked(bytes24(uint192(p0)), bytes24(uint192(p1)), bytes16(uint128(p2)))
+    /// ```
+    ///
+    /// [`circuits::packed_digest_instance`] computes the elements outside the circuit.
+    pub fn expose_digest_packed(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        digest: &[AssignedBits<64>; super::DIGEST_SIZE],
+        instance: Column<Instance>,
+    ) -> Result<[AssignedCell<bn256::Fr, bn256::Fr>; 3], Error> {
+        let packed = self.config().compression.digest_packed(layouter, digest)?;
+        for (row, cell) in packed.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), instance, row)?;
+        }
+        Ok(packed)
+    }
+
+    /// Converts `state` into its digest and packs the digest into field elements, as
+    /// [`Table16Chip::expose_digest_packed`] does, for a circuit that consumes the digest
+    /// as field elements rather than as words, such as a recursive verifier.
+    ///
+    /// Three elements are the fewest that can hold the digest, as two elements of
+    /// [`bn256::Fr`] hold at most 2 × 253 bits without wrapping the modulus. Each element
+    /// is constrained to the big-endian sum of its digest words, each of which is
+    /// recombined from range-checked halves, so the elements determine the digest bytes.
+    pub fn digest_as_field_elements(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        state: &State,
+    ) -> Result<Vec<AssignedCell<bn256::Fr, bn256::Fr>>, Error> {
+        let digest = self.digest_cells(layouter, state)?;
+        let packed = self.config().compression.digest_packed(layouter, &digest)?;
+        Ok(packed.to_vec())
+    }
+
+    /// Returns `a` if `cond` is one and `b` if it is zero, copy-constrained to the
+    /// selected words, without hashing again. `cond` is constrained to be a bit.
+    ///
+    /// The digests are typically returned by [`Table16Chip::digest_cells`].
+    pub fn select_digest(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        cond: AssignedCell<bn256::Fr, bn256::Fr>,
+        a: [AssignedBits<64>; super::DIGEST_SIZE],
+        b: [AssignedBits<64>; super::DIGEST_SIZE],
+    ) -> Result<[AssignedBits<64>; super::DIGEST_SIZE], Error> {
+        let merkle = &self.config().merkle;
+        layouter.assign_region(
+            || "conditional digest",
+            |mut region| merkle.assign_select(&mut region, &cond, &a, &b),
+        )
+    }
+
+    /// Constrains the digests `a` and `b` to be equal, word by word, for instance to check
+    /// a recomputed root against an expected one.
+    ///
+    /// The digests are typically returned by [`Table16Chip::digest_cells`].
+    pub fn constrain_digests_equal(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        a: &[AssignedBits<64>; super::DIGEST_SIZE],
+        b: &[AssignedBits<64>; super::DIGEST_SIZE],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "constrain digests equal",
+            |mut region| {
+                for (a, b) in a.iter().zip(b.iter()) {
+                    region.constrain_equal(a.cell(), b.cell())?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Converts the given state into the bytes of the message digest, with each 64-bit
+    /// word written in the byte order `endian`.
+    ///
+    /// [`Endianness::Big`] gives the canonical SHA-512 digest.
+    pub fn digest_bytes(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        state: &State,
+        endian: Endianness,
+    ) -> Result<Value<[u8; 64]>, Error> {
+        let digest = self.digest(layouter, state)?;
+        let words: Value<Vec<u64>> = digest.iter().map(|word| word.0).collect();
+
+        Ok(words.map(|words| {
+            let mut bytes = [0u8; 64];
+            for (chunk, word) in bytes.chunks_exact_mut(8).zip(words) {
+                chunk.copy_from_slice(&match endian {
+                    Endianness::Big => word.to_be_bytes(),
+                    Endianness::Little => word.to_le_bytes(),
+                });
+            }
+            bytes
+        }))
+    }
+
+    /// Hashes several independent messages, each given as already padded blocks such as
+    /// those returned by [`blocks_from_bytes`](super::blocks_from_bytes), and returns one
+    /// digest per message.
+    ///
+    /// The hashes are laid out one after another in the columns of this chip and all
+    /// look up into the spread table loaded once by [`Table16Chip::load`].
+    pub fn batch_hash(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        inputs: &[Vec<[BlockWord; super::BLOCK_SIZE]>],
+    ) -> Result<Vec<[BlockWord; super::DIGEST_SIZE]>, Error> {
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, blocks)| {
+                let mut layouter = layouter.namespace(|| format!("hash {}", idx));
+                let mut state = self.initialization_vector(&mut layouter)?;
+                for block in blocks.iter() {
+                    let compressed = self.compress_block(&mut layouter, &state, *block)?;
+                    state = self.add_feed_forward(&mut layouter, &state, &compressed)?;
+                }
+                self.digest(&mut layouter, &state)
+            })
+            .collect()
+    }
+
+    /// Hashes the padded `blocks` from the IV, like [`Sha512::hash_blocks`], and returns
+    /// the chaining value after each block as a digest, the last one being the digest of
+    /// the whole message.
+    ///
+    /// SHA-512 only has intermediate values at block boundaries: the value after block
+    /// `i` is the internal state once `blocks[..=i]` are compressed, not the digest of a
+    /// byte prefix of the message. It equals the SHA-512 digest of a message only if
+    /// `blocks[..=i]` are exactly that message padded, as for a message padded to
+    /// `i + 1` blocks and extended. Each intermediate costs the rows of a digest.
+    ///
+    /// [`Sha512::hash_blocks`]: super::Sha512::hash_blocks
+    pub fn hash_blocks_with_intermediates(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        blocks: &[[BlockWord; super::BLOCK_SIZE]],
+    ) -> Result<Vec<[BlockWord; super::DIGEST_SIZE]>, Error> {
+        let mut state = self.initialization_vector(layouter)?;
+        let mut intermediates = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let compressed = self.compress_block(layouter, &state, *block)?;
+            state = self.add_feed_forward(layouter, &state, &compressed)?;
+            intermediates.push(self.digest(layouter, &state)?);
+        }
+        Ok(intermediates)
+    }
+
+    /// Hashes the 128-byte concatenation of two digests, as done at each node of a
+    /// SHA-512 Merkle tree.
+    ///
+    /// The concatenation fills a whole block, so the padding (the `0x80` byte, zeros and
+    /// the 1024-bit length) is compressed as a second block. The words of `left` and
+    /// `right` are copy-constrained into the message schedule of the first block, and
+    /// the padding words to constants, so the returned digest words are bound to the
+    /// digests given.
+    pub fn hash_two(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        left: &[AssignedBits<64>; super::DIGEST_SIZE],
+        right: &[AssignedBits<64>; super::DIGEST_SIZE],
+    ) -> Result<[AssignedBits<64>; super::DIGEST_SIZE], Error> {
+        let words = left.iter().chain(right.iter()).cloned().map(InputWord::Word).collect();
+        let state = self.hash_input_words(layouter, words)?;
+        self.digest_cells(layouter, &state)
+    }
+
+    /// Hashes the constant `message` outside the circuit and assigns its digest as
+    /// constants, like [`Table16Chip::digest_cells`] but in [`DIGEST_SIZE`] rows instead
+    /// of the rows of a full compression per block.
+    ///
+    /// Each word is copy-constrained to the constant column of the chip. The message
+    /// is a parameter of the circuit rather than a witness, so the layout is the same
+    /// during key generation and proving.
+    ///
+    /// [`DIGEST_SIZE`]: super::DIGEST_SIZE
+    pub fn hash_constant(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        message: &[u8],
+    ) -> Result<[AssignedBits<64>; super::DIGEST_SIZE], Error> {
+        let digest = reference_sha512(message);
+        let mut words = [0u64; super::DIGEST_SIZE];
+        for (word, bytes) in words.iter_mut().zip(digest.chunks_exact(8)) {
+            *word = u64::from_be_bytes(bytes.try_into().expect("bytes.len() == 8"));
+        }
+        self.config().compression.constant_digest_cells(layouter, words)
+    }
+
+    /// Hashes `message`, a message of whole 64-bit words, each of which is either a
+    /// public constant or a private witness, and returns the assigned digest words.
+    ///
+    /// [`Visibility::Public`] words, and the padding, are copy-constrained to constants
+    /// in the constant column of the chip. They are parameters of the circuit, so they
+    /// must stay known in
+    /// [`Circuit::without_witnesses`](halo2_proofs::plonk::Circuit::without_witnesses).
+    /// [`Visibility::Private`] words are witnessed. All of them are copy-constrained
+    /// into the message schedule, as by [`Table16Chip::compress_block_from_cells`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Synthesis`] if the value of a public word is unknown.
+    pub fn hash_partially_public(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        message: &[(BlockWord, Visibility)],
+    ) -> Result<[AssignedBits<64>; super::DIGEST_SIZE], Error> {
+        let state = self.hash_words(layouter, message)?;
+        self.digest_cells(layouter, &state)
+    }
+
+    /// Hashes `msg` under the domain tag `domain`, so that the same message hashed under
+    /// two different tags gives unrelated digests.
+    ///
+    /// The tag block holds the length of the tag in its first byte, then the tag,
+    /// zero-padded to a whole block. It is prepended to the message, and the padding
+    /// length counts both the tag block and the message. The result is the SHA-512
+    /// digest of the tag block followed by the big-endian bytes of `msg`. Tags of more
+    /// than 127 bytes are rejected. As the length is encoded, tags that only differ in
+    /// trailing zero bytes name different domains.
+    ///
+    /// The tag block and the padding are copy-constrained to constants, as by
+    /// [`Table16Chip::hash_partially_public`]; the words of `msg` are witnessed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::DomainTooLong`] if `domain` is longer than 127 bytes.
+    pub fn hash_with_domain(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        domain: &[u8],
+        msg: &[BlockWord],
+    ) -> Result<[BlockWord; super::DIGEST_SIZE], Sha512Error> {
+        let max = super::BLOCK_SIZE * 8 - 1;
+        if domain.len() > max {
+            return Err(Sha512Error::DomainTooLong { len: domain.len(), max });
+        }
+        let mut tag = [0u8; super::BLOCK_SIZE * 8];
+        tag[0] = domain.len() as u8;
+        tag[1..=domain.len()].copy_from_slice(domain);
+
+        let mut input: Vec<(BlockWord, Visibility)> = tag
+            .chunks_exact(8)
+            .map(|word| {
+                let word = u64::from_be_bytes(word.try_into().expect("word.len() == 8"));
+                (BlockWord(Value::known(word)), Visibility::Public)
+            })
+            .collect();
+        input.extend(msg.iter().map(|word| (*word, Visibility::Private)));
+        let state = self.hash_words(layouter, &input)?;
+        Ok(self.digest(layouter, &state)?)
+    }
+
+    /// Hashes `msg` followed by its length in bits, as a 128-bit big-endian integer.
+    ///
+    /// `H(key || msg)` is not a MAC: from its digest and the length of `key || msg`,
+    /// anyone can resume hashing with [`Table16Chip::initialize_with_iv`] and obtain the
+    /// digest of `key || msg || padding || suffix` for a suffix of their choice. With the
+    /// length at the end, a verifier that recomputes the digest from the message it
+    /// receives only accepts an extension that itself ends with the length of everything
+    /// before it, so a naive append no longer verifies. The attacker still controls the
+    /// appended bytes, so this is a mitigation rather than a MAC; where a key is
+    /// involved, HMAC remains the standard construction.
+    ///
+    /// The length words and the padding are copy-constrained to constants, as by
+    /// [`Table16Chip::hash_partially_public`]; the words of `msg` are witnessed.
+    pub fn hash_with_length_suffix(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        msg: &[BlockWord],
+    ) -> Result<[BlockWord; super::DIGEST_SIZE], Error> {
+        let bit_len = msg.len() as u128 * 64;
+        let mut input: Vec<(BlockWord, Visibility)> =
+            msg.iter().map(|word| (*word, Visibility::Private)).collect();
+        input.push((BlockWord(Value::known((bit_len >> 64) as u64)), Visibility::Public));
+        input.push((BlockWord(Value::known(bit_len as u64)), Visibility::Public));
+        let state = self.hash_words(layouter, &input)?;
+        self.digest(layouter, &state)
+    }
+
+    /// Pads `message`, a message of whole 64-bit words, and hashes it from the IV,
+    /// returning the final state.
+    ///
+    /// The public words and the padding are copy-constrained to constants, and the
+    /// private words are witnessed, as described in
+    /// [`Table16Chip::hash_partially_public`].
+    fn hash_words(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        message: &[(BlockWord, Visibility)],
+    ) -> Result<State, Error> {
+        let bit_len = message.len() as u128 * 64;
+        let padding = |word: u64| (BlockWord(Value::known(word)), Visibility::Public);
+        let mut words = message.to_vec();
+        words.push(padding(1 << 63));
+        while words.len() % super::BLOCK_SIZE != super::BLOCK_SIZE - 2 {
+            words.push(padding(0));
+        }
+        words.push(padding((bit_len >> 64) as u64));
+        words.push(padding(bit_len as u64));
+
+        let cells = self.config().compression.assign_message_words(layouter, &words)?;
+        super::ShaGadget::hash_blocks(self, layouter, &cells)
+    }
+
+    /// Hashes the 32-byte big-endian encodings of `elements`, concatenated.
+    ///
+    /// Each element is decomposed in the circuit into the four 64-bit words of its
+    /// encoding. The decomposition is range-checked, shown to be canonical, and
+    /// copy-constrained both to the element and to the hashed message words. The
+    /// padding words are copy-constrained to constants.
+    pub fn hash_field_elements(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        elements: &[AssignedCell<bn256::Fr, bn256::Fr>],
+    ) -> Result<[BlockWord; super::DIGEST_SIZE], Error> {
+        let state = self.absorb_field_elements(layouter, elements)?;
+        self.digest(layouter, &state)
+    }
+
+    /// Derives a Fiat-Shamir challenge from `elements`: hashes their 32-byte big-endian
+    /// encodings like [`Table16Chip::hash_field_elements`] and returns the first 256
+    /// bits of the digest as four assigned words, most significant first.
+    ///
+    /// The encoding is canonical, so the challenge is a function of the elements alone.
+    pub fn absorb_and_squeeze(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        elements: &[AssignedCell<bn256::Fr, bn256::Fr>],
+    ) -> Result<[AssignedBits<64>; 4], Error> {
+        let state = self.absorb_field_elements(layouter, elements)?;
+        let words = self.digest_words(layouter, &state, 4)?;
+        Ok(words.try_into().expect("digest_words returns 4 words"))
+    }
+
+    /// Decomposes `elements` and hashes their encodings, returning the final state.
+    fn absorb_field_elements(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        elements: &[AssignedCell<bn256::Fr, bn256::Fr>],
+    ) -> Result<State, Error> {
+        let field_element = &self.config().field_element;
+
+        let mut message = Vec::with_capacity(4 * elements.len());
+        for element in elements.iter() {
+            let words = layouter.assign_region(
+                || "decompose field element",
+                |mut region| field_element.assign_element(&mut region, element),
+            )?;
+            message.extend(words);
+        }
+
+        self.hash_bound_words(layouter, &message)
+    }
+
+    /// Hashes `message`, whose length is a private witness of at most
+    /// `MAX_BLOCKS * 128 - 17` bytes, so that its padding fits in `MAX_BLOCKS` blocks.
+    /// The shape of the circuit only depends on `MAX_BLOCKS`.
+    ///
+    /// The message is padded in the circuit and all `MAX_BLOCKS` blocks are compressed.
+    /// The padding is checked byte by byte against the witnessed length: the `0x80`
+    /// byte follows the message, then zeros, then the length in bits at the end of the
+    /// last block. The blocks after the last one are not checked and their output is
+    /// masked out when the digest is selected.
+    ///
+    /// Returns the cell holding the length in bytes, and the digest words.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::BadBlockCount`] if `MAX_BLOCKS` is zero, and
+    /// [`Sha512Error::MessageTooLong`] if the message is known and longer than
+    /// `MAX_BLOCKS * 128 - 17` bytes.
+    pub fn hash_dynamic<const MAX_BLOCKS: usize>(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        message: Value<Vec<u8>>,
+    ) -> Result<
+        (
+            AssignedCell<bn256::Fr, bn256::Fr>,
+            [AssignedBits<64>; super::DIGEST_SIZE],
+        ),
+        Sha512Error,
+    > {
+        self.hash_within_blocks(layouter, MAX_BLOCKS, message)
+    }
+
+    /// Like [`Table16Chip::hash_dynamic`], for a number of blocks chosen at run time.
+    fn hash_within_blocks(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        max_blocks: usize,
+        message: Value<Vec<u8>>,
+    ) -> Result<
+        (
+            AssignedCell<bn256::Fr, bn256::Fr>,
+            [AssignedBits<64>; super::DIGEST_SIZE],
+        ),
+        Sha512Error,
+    > {
+        if max_blocks == 0 {
+            return Err(Sha512Error::BadBlockCount(0));
+        }
+        let capacity = max_blocks * super::BLOCK_SIZE * 8 - MIN_PADDING_BYTES;
+        let mut len = 0;
+        message.as_ref().map(|message| len = message.len());
+        if len > capacity {
+            return Err(Sha512Error::MessageTooLong { len, max: capacity });
+        }
+
+        let dynamic_length = &self.config().dynamic_length;
+        let padded = layouter.assign_region(
+            || "dynamic padding",
+            |mut region| dynamic_length.assign_padding(&mut region, max_blocks, message.as_ref()),
+        )?;
+        // The message is not needed once it is padded.
+        #[cfg(feature = "secrets")]
+        {
+            use zeroize::Zeroize;
+            let mut message = message;
+            message.as_mut().map(|message| message.zeroize());
+        }
+
+        let mut state = self.initialization_vector(layouter)?;
+        let mut states: Vec<DigestHalves> = Vec::with_capacity(max_blocks);
+        for bound in padded.words.chunks_exact(super::BLOCK_SIZE) {
+            let bound = bound.try_into().expect("bound.len() == BLOCK_SIZE");
+            let compressed = self.compress_block_from_halves(layouter, &state, bound)?;
+            state = self.add_feed_forward(layouter, &state, &compressed)?;
+            states.push(dense_state(state.clone())?.map(|word| word.into()));
+        }
+
+        let digest = layouter.assign_region(
+            || "select digest",
+            |mut region| dynamic_length.assign_select(&mut region, &padded.last, &states),
+        )?;
+        Ok((padded.length, digest))
+    }
+
+    /// Proves that the private `message` hashes to the digest committed in `instance`,
+    /// without revealing the message or its length beyond the bound set by `MAX_BLOCKS`.
+    ///
+    /// The message is padded and hashed in the circuit by
+    /// [`Table16Chip::hash_dynamic`], and the digest is packed into rows `0..3` of
+    /// `instance` by [`Table16Chip::expose_digest_packed`]; `instance` must have equality
+    /// enabled. [`circuits::packed_digest_instance`] computes the public input.
+    ///
+    /// Returns the cell holding the length of the message in bytes, which stays private
+    /// unless the caller exposes or bounds it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Table16Chip::hash_dynamic`] for a message that does not
+    /// fit in `MAX_BLOCKS` blocks.
+    pub fn prove_preimage<const MAX_BLOCKS: usize>(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        message: Value<Vec<u8>>,
+        instance: Column<Instance>,
+    ) -> Result<AssignedCell<bn256::Fr, bn256::Fr>, Sha512Error> {
+        let (length, digest) = self.hash_dynamic::<MAX_BLOCKS>(layouter, message)?;
+        self.expose_digest_packed(layouter, &digest, instance)?;
+        Ok(length)
+    }
+
+    /// Recomputes a Merkle root by hashing `leaf` with each sibling digest on `path`,
+    /// as [`Table16Chip::hash_two`] does, and copy-constrains it to `root`.
+    ///
+    /// `path` goes from the leaf up to the root. Each step pairs a sibling with whether
+    /// it is the left child; the order is selected in-circuit, so it may be a witness.
+    /// The leaf is copy-constrained into the first level, each computed node into the
+    /// next, and the last one to `root`, so the circuit is only satisfied if the path
+    /// leads from `leaf` to `root`.
+    pub fn verify_merkle_path(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        leaf: &[AssignedBits<64>; super::DIGEST_SIZE],
+        path: &[([BlockWord; super::DIGEST_SIZE], Value<bool>)],
+        root: &[AssignedBits<64>; super::DIGEST_SIZE],
+    ) -> Result<(), Error> {
+        let merkle = &self.config().merkle;
+
+        let mut node = leaf.clone();
+        for (sibling, sibling_is_left) in path.iter() {
+            let message = layouter.assign_region(
+                || "merkle swap",
+                |mut region| merkle.assign_swap(&mut region, &node, *sibling, *sibling_is_left),
+            )?;
+            let (left, right) = message.split_at(super::DIGEST_SIZE);
+            node = self.hash_two(
+                layouter,
+                left.try_into().expect("left.len() == DIGEST_SIZE"),
+                right.try_into().expect("right.len() == DIGEST_SIZE"),
+            )?;
+        }
+        self.constrain_digests_equal(layouter, &node, root)
+    }
+
+    /// Hashes the message made of the 64-bit words whose halves are given, padding it
+    /// in the circuit, and returns the final state instead of the digest.
+    ///
+    /// The message words of each block are copy-constrained to the given halves, and
+    /// the padding words to constants, so the state is that of SHA-512 over the bound
+    /// words.
+    fn hash_bound_words(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        message: &[(AssignedBits<32>, AssignedBits<32>)],
+    ) -> Result<State, Error> {
+        let words = message
+            .iter()
+            .map(|(lo, hi)| InputWord::Halves(lo.clone(), hi.clone()))
+            .collect();
+        self.hash_input_words(layouter, words)
+    }
+
+    /// Like [`Table16Chip::hash_bound_words`], for message words given either whole or
+    /// as halves.
+    fn hash_input_words(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        mut words: Vec<InputWord>,
+    ) -> Result<State, Error> {
+        let padding = super::ShaGadget::padding(self, layouter, words.len() * 8)?;
+        words.extend(padding.into_iter().map(InputWord::Word));
+
+        let mut state = self.initialization_vector(layouter)?;
+        for block in words.chunks_exact(super::BLOCK_SIZE) {
+            state = self.compress_bound_block(
+                layouter,
+                &state,
+                block.try_into().expect("block.len() == BLOCK_SIZE"),
+            )?;
+        }
+        Ok(state)
+    }
+
+    /// Compresses the block of `words` from `prev_state` and adds the feed-forward,
+    /// copy-constraining the words into the message schedule.
+    fn compress_bound_block(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev_state: &State,
+        words: &[InputWord; super::BLOCK_SIZE],
+    ) -> Result<State, Error> {
+        let config = self.config();
+        let initialized_state = config
+            .compression
+            .initialize_with_state(layouter, prev_state.clone())?;
+        let w_halves = config
+            .message_schedule
+            .process_from_input_words(layouter, words)?
+            .halves;
+        let compressed = config
+            .compression
+            .compress(layouter, initialized_state, &w_halves, ROUNDS)?;
+        self.add_feed_forward(layouter, prev_state, &compressed)
     }
 
     /// Loads the lookup table required by this chip into the circuit.
@@ -370,6 +1760,26 @@ impl Table16Chip {
     ) -> Result<(), Error> {
         SpreadTableChip::load(config.lookup, layouter)
     }
+
+    /// Stands in for [`Table16Chip::load`] when `config` looks up into `table`, a spread
+    /// table already loaded by another gadget, as configured by
+    /// [`Table16Chip::configure_with_spread_table`]. Nothing is assigned: the lookup was
+    /// wired at configuration, and the table must be loaded exactly once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::SpreadTableMismatch`] if `config` does not look up into
+    /// `table`, in which case its own table would be left empty.
+    pub fn load_with_table(
+        config: Table16Config,
+        _layouter: &mut impl Layouter<bn256::Fr>,
+        table: &SpreadTable,
+    ) -> Result<(), Sha512Error> {
+        if config.lookup.table != *table {
+            return Err(Sha512Error::SpreadTableMismatch);
+        }
+        Ok(())
+    }
 }
 
 impl Sha512Instructions<bn256::Fr> for Table16Chip {
@@ -402,10 +1812,19 @@ impl Sha512Instructions<bn256::Fr> for Table16Chip {
         input: [Self::BlockWord; super::BLOCK_SIZE],
     ) -> Result<Self::State, Error> {
         let config = self.config();
-        let (_, w_halves) = config.message_schedule.process(layouter, input)?;
+        let w_halves = config.message_schedule.process(layouter, input)?.halves;
         config
             .compression
-            .compress(layouter, initialized_state.clone(), w_halves)
+            .compress(layouter, initialized_state.clone(), &w_halves, ROUNDS)
+    }
+
+    fn add_feed_forward(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev: &Self::State,
+        compressed: &Self::State,
+    ) -> Result<Self::State, Error> {
+        Table16Chip::add_feed_forward(self, layouter, prev, compressed)
     }
 
     fn digest(
@@ -419,6 +1838,95 @@ impl Sha512Instructions<bn256::Fr> for Table16Chip {
     }
 }
 
+// Not imported: its `digest` would make the calls to `Sha512Instructions::digest`
+// ambiguous.
+impl super::ShaGadget<bn256::Fr> for Table16Chip {
+    const DIGEST_BYTES: usize = super::DIGEST_SIZE * 8;
+    const BLOCK_BYTES: usize = super::BLOCK_SIZE * 8;
+
+    type Word = AssignedBits<64>;
+    type State = State;
+
+    /// Returns [`Error::Synthesis`] unless `message_bytes` is a whole number of words.
+    fn padding(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        message_bytes: usize,
+    ) -> Result<Vec<AssignedBits<64>>, Error> {
+        if message_bytes % 8 != 0 {
+            return Err(Error::Synthesis);
+        }
+        let bit_len = message_bytes as u128 * 8;
+        let words = message_bytes / 8;
+        let mut padding = vec![1 << 63];
+        while (words + padding.len()) % super::BLOCK_SIZE != super::BLOCK_SIZE - 2 {
+            padding.push(0);
+        }
+        padding.push((bit_len >> 64) as u64);
+        padding.push(bit_len as u64);
+
+        let padding: Vec<_> = padding
+            .into_iter()
+            .map(|word| (BlockWord(Value::known(word)), Visibility::Public))
+            .collect();
+        self.config().compression.assign_message_words(layouter, &padding)
+    }
+
+    /// Returns [`Error::Synthesis`] if `words` is empty or not a whole number of
+    /// blocks.
+    fn hash_blocks(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        words: &[AssignedBits<64>],
+    ) -> Result<State, Error> {
+        if words.is_empty() || words.len() % super::BLOCK_SIZE != 0 {
+            return Err(Error::Synthesis);
+        }
+        let mut state = self.initialization_vector(layouter)?;
+        for block in words.chunks_exact(super::BLOCK_SIZE) {
+            let block = block.try_into().expect("block.len() == BLOCK_SIZE");
+            let compressed = self.compress_block_from_cells(layouter, &state, block)?;
+            state = self.add_feed_forward(layouter, &state, &compressed)?;
+        }
+        Ok(state)
+    }
+
+    fn digest(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        state: &State,
+    ) -> Result<Vec<AssignedBits<64>>, Error> {
+        Ok(self.digest_cells(layouter, state)?.to_vec())
+    }
+}
+
+/// Configures the gate enabled by [`Table16Assignment::assign_spread_outputs`], which
+/// ties the 32-bit dense outputs it places in `a_3` to the 16-bit halves it looks up in
+/// `a_1`. Without it the outputs copied out of a spread gate region are unconstrained.
+fn configure_spread_outputs(
+    meta: &mut ConstraintSystem<bn256::Fr>,
+    lookup: &SpreadInputs,
+    a_3: Column<Advice>,
+) -> Selector {
+    let s_spread_outputs = meta.selector();
+    let a_1 = lookup.dense;
+
+    meta.create_gate("s_spread_outputs", |meta| {
+        let s_spread_outputs = meta.query_selector(s_spread_outputs);
+        let half = |meta: &mut VirtualCells<bn256::Fr>, rot| meta.query_advice(a_1, Rotation(rot));
+        let halves = [
+            (half(meta, -1), half(meta, 0)),
+            (half(meta, 1), half(meta, 2)),
+            (half(meta, 3), half(meta, 4)),
+            (half(meta, 5), half(meta, 6)),
+        ];
+        let words = [2, 3, 4, 5].map(|rot| meta.query_advice(a_3, Rotation(rot)));
+
+        Gate::s_spread_outputs(s_spread_outputs, halves, words)
+    });
+
+    s_spread_outputs
+}
 
 /// Common assignment patterns used by Table16 regions.
 trait Table16Assignment {
@@ -458,6 +1966,7 @@ trait Table16Assignment {
         &self,
         region: &mut Region<'_, bn256::Fr>,
         lookup: &SpreadInputs,
+        s_spread_outputs: Selector,
         a_3: Column<Advice>,
         row: usize,
         r_0_even: Value<[bool; 32]>,
@@ -471,6 +1980,7 @@ trait Table16Assignment {
         ),
         Error,
     > {
+        s_spread_outputs.enable(region, row)?;
 
          // Lookup R_0^{even}, R_0^{odd}, R_1^{even}, R_1^{odd}
         let r_0_even_lo: Value<[bool; 16]> = r_0_even.map(|r_0_even| r_0_even[..16].try_into().unwrap());
@@ -566,6 +2076,7 @@ trait Table16Assignment {
         &self,
         region: &mut Region<'_, bn256::Fr>,
         lookup: &SpreadInputs,
+        s_spread_outputs: Selector,
         a_3: Column<Advice>,
         row: usize,
         r_0_even: Value<[bool; 32]>,
@@ -574,7 +2085,15 @@ trait Table16Assignment {
         r_1_odd: Value<[bool; 32]>,
     ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
         let (even, _odd) = self.assign_spread_outputs(
-            region, lookup, a_3, row, r_0_even, r_0_odd, r_1_even, r_1_odd,
+            region,
+            lookup,
+            s_spread_outputs,
+            a_3,
+            row,
+            r_0_even,
+            r_0_odd,
+            r_1_even,
+            r_1_odd,
         )?;
 
         Ok(even)
@@ -0,0 +1,509 @@
+//! Ready-made circuits over [`Table16Chip`], for key generation and proving without a
+//! hand-written [`Circuit`].
+//!
+//! Each circuit keeps its input private and exposes the eight 64-bit words of the
+//! digest, big-endian, in rows `0..8` of one instance column; see [`digest_instance`].
+//! [`BoundedPreimageCircuit`] instead packs the digest into three field elements; see
+//! [`packed_digest_instance`].
+//! Size the parameters with [`Table16Config::minimum_k`] for the number of blocks hashed.
+//!
+//! [`SingleBlockHashCircuit`] and [`PreimageCircuit`] are laid out with
+//! [`SimpleFloorPlanner`] unless another planner is chosen with their
+//! `with_floor_planner` method.
+
+use std::convert::TryInto;
+use std::fmt;
+use std::marker::PhantomData;
+
+use super::{
+    witness_debug, AssignedBits, BlockWord, Table16Chip, Table16Config, Visibility,
+    MIN_PADDING_BYTES,
+};
+#[cfg(feature = "self-test")]
+use super::super::Sha512Error;
+use super::super::{blocks_from_bytes, BLOCK_SIZE, DIGEST_SIZE};
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{FloorPlanner, Layouter, SimpleFloorPlanner, Value},
+    halo2curves::bn256,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+#[cfg(feature = "secrets")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Configuration shared by the circuits of this module: the chip, and the instance
+/// column holding the digest.
+#[derive(Clone, Debug)]
+pub struct DigestCircuitConfig {
+    table16: Table16Config,
+    digest: Column<Instance>,
+}
+
+impl DigestCircuitConfig {
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self {
+        let digest = meta.instance_column();
+        meta.enable_equality(digest);
+        DigestCircuitConfig {
+            table16: Table16Chip::configure(meta),
+            digest,
+        }
+    }
+
+    /// Loads the chip and constructs it.
+    fn chip(&self, layouter: &mut impl Layouter<bn256::Fr>) -> Result<Table16Chip, Error> {
+        Table16Chip::load(self.table16.clone(), layouter)?;
+        Ok(Table16Chip::construct(self.table16.clone()))
+    }
+
+    /// Pads `message` in the circuit into at most `num_blocks` blocks, as
+    /// [`Table16Chip::hash_dynamic`] does, hashes it and constrains the digest to the
+    /// instance column.
+    fn hash_to_instance(
+        &self,
+        mut layouter: impl Layouter<bn256::Fr>,
+        num_blocks: usize,
+        message: Value<Vec<u8>>,
+    ) -> Result<(), Error> {
+        let table16_chip = self.chip(&mut layouter)?;
+        let (_, digest) = table16_chip.hash_within_blocks(&mut layouter, num_blocks, message)?;
+        self.constrain_digest(&mut layouter, &digest)
+    }
+
+    /// Constrains the words of `digest` to rows `0..8` of the instance column.
+    fn constrain_digest(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        digest: &[AssignedBits<64>; DIGEST_SIZE],
+    ) -> Result<(), Error> {
+        for (row, word) in digest.iter().enumerate() {
+            layouter.constrain_instance(word.cell(), self.digest, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the instance column of the circuits in this module for `digest`: its eight
+/// 64-bit words, big-endian.
+pub fn digest_instance(digest: &[u8; DIGEST_SIZE * 8]) -> Vec<bn256::Fr> {
+    digest
+        .chunks_exact(8)
+        .map(|word| bn256::Fr::from(u64::from_be_bytes(word.try_into().expect("word.len() == 8"))))
+        .collect()
+}
+
+/// Returns the three field elements [`Table16Chip::expose_digest_packed`] packs `digest`
+/// into: its bytes `0..24`, `24..48` and `48..64`, each read as a big-endian integer.
+pub fn packed_digest_instance(digest: &[u8; DIGEST_SIZE * 8]) -> Vec<bn256::Fr> {
+    let word_radix = bn256::Fr::from_u128(1 << 64);
+    digest
+        .chunks(24)
+        .map(|chunk| {
+            chunk.chunks_exact(8).fold(bn256::Fr::zero(), |sum, word| {
+                let word = u64::from_be_bytes(word.try_into().expect("word.len() == 8"));
+                sum * word_radix + bn256::Fr::from(word)
+            })
+        })
+        .collect()
+}
+
+/// The digest of "abc", from FIPS 180-2.
+#[cfg(feature = "self-test")]
+const ABC_DIGEST: [u64; DIGEST_SIZE] = [
+    0xddaf35a193617aba,
+    0xcc417349ae204131,
+    0x12e6fa4e89a97ea2,
+    0x0a9eeee64b55d39a,
+    0x2192992a274fc1a8,
+    0x36ba3c23a3feebbd,
+    0x454d4423643ce80e,
+    0x2a9ac94fa54ca49f,
+];
+
+/// Hashes "abc" with [`SingleBlockHashCircuit`] on the [`MockProver`], and checks the
+/// constraints hold with the known digest as the public input.
+///
+/// This checks at run time that the chip works with the halo2 version it was built
+/// against, for example in the start-up or CI of a dependent crate.
+///
+/// # Errors
+///
+/// Returns [`Sha512Error::SelfTestFailed`] if the software digest or the circuit does
+/// not match the known digest, and [`Sha512Error::Synthesis`] if the circuit cannot be
+/// laid out.
+///
+/// [`MockProver`]: halo2_proofs::dev::MockProver
+#[cfg(feature = "self-test")]
+pub fn self_test() -> Result<(), Sha512Error> {
+    use halo2_proofs::dev::MockProver;
+
+    let digest: Vec<u8> = ABC_DIGEST.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let digest: [u8; DIGEST_SIZE * 8] = digest.try_into().expect("digest.len() == 64");
+    if crate::reference::reference_sha512(b"abc") != digest {
+        return Err(Sha512Error::SelfTestFailed(
+            "the reference digest of \"abc\" is wrong".to_string(),
+        ));
+    }
+
+    let circuit = SingleBlockHashCircuit::new(blocks_from_bytes(b"abc")[0]);
+    let instance = digest_instance(&digest);
+    let prover = MockProver::run(Table16Config::minimum_k(1), &circuit, vec![instance])?;
+    prover.verify().map_err(|failures| {
+        Sha512Error::SelfTestFailed(format!(
+            "{} constraints fail, the first being {}",
+            failures.len(),
+            failures[0]
+        ))
+    })
+}
+
+/// Hashes the message of at most 111 bytes that one padded block holds. Needs
+/// [`Table16Config::minimum_k`] of 1.
+///
+/// The message is read from the block by its length field, and padded again in the
+/// circuit as by [`Table16Chip::hash_dynamic`], so the padding is checked rather than
+/// witnessed. A block that is not padded as by [`blocks_from_bytes`] does not satisfy
+/// the circuit.
+pub struct SingleBlockHashCircuit<FP: FloorPlanner = SimpleFloorPlanner> {
+    /// The padded block.
+    pub block: [BlockWord; BLOCK_SIZE],
+    _marker: PhantomData<FP>,
+}
+
+impl SingleBlockHashCircuit {
+    /// Creates a circuit hashing the padded `block`.
+    pub fn new(block: [BlockWord; BLOCK_SIZE]) -> Self {
+        SingleBlockHashCircuit {
+            block,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<FP: FloorPlanner> SingleBlockHashCircuit<FP> {
+    /// Returns the same circuit, laid out with the floor planner `P`.
+    pub fn with_floor_planner<P: FloorPlanner>(self) -> SingleBlockHashCircuit<P> {
+        SingleBlockHashCircuit {
+            block: self.block,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The message the block pads, as many bytes as its length field gives, up to the
+    /// 111 bytes a block can hold.
+    fn message(&self) -> Value<Vec<u8>> {
+        let words: Value<Vec<u64>> = self.block.iter().map(|word| word.0).collect();
+        words.map(|words| {
+            let capacity = BLOCK_SIZE * 8 - MIN_PADDING_BYTES;
+            let len = (words[BLOCK_SIZE - 1] / 8).min(capacity as u64) as usize;
+            let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+            bytes[..len].to_vec()
+        })
+    }
+}
+
+impl<FP: FloorPlanner> Clone for SingleBlockHashCircuit<FP> {
+    fn clone(&self) -> Self {
+        SingleBlockHashCircuit {
+            block: self.block,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<FP: FloorPlanner> Default for SingleBlockHashCircuit<FP> {
+    fn default() -> Self {
+        SingleBlockHashCircuit {
+            block: Default::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<FP: FloorPlanner> fmt::Debug for SingleBlockHashCircuit<FP> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SingleBlockHashCircuit")
+            .field("block", &self.block)
+            .finish()
+    }
+}
+
+impl<FP: FloorPlanner> Circuit<bn256::Fr> for SingleBlockHashCircuit<FP> {
+    type Config = DigestCircuitConfig;
+    type FloorPlanner = FP;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        DigestCircuitConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        config.hash_to_instance(layouter, 1, self.message())
+    }
+}
+
+/// Hashes the concatenation of two digests, as [`Table16Chip::hash_two`] does at each
+/// node of a Merkle tree. Needs [`Table16Config::minimum_k`] of 2.
+///
+/// The digests are witnessed, and the padding block is copy-constrained to constants,
+/// as by [`Table16Chip::hash_partially_public`].
+#[derive(Clone, Debug, Default)]
+pub struct TwoToOneCircuit {
+    /// The first half of the message.
+    pub left: [BlockWord; DIGEST_SIZE],
+    /// The second half of the message.
+    pub right: [BlockWord; DIGEST_SIZE],
+}
+
+impl Circuit<bn256::Fr> for TwoToOneCircuit {
+    type Config = DigestCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        DigestCircuitConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        let table16_chip = config.chip(&mut layouter)?;
+        let message: Vec<_> = self
+            .left
+            .iter()
+            .chain(self.right.iter())
+            .map(|word| (*word, Visibility::Private))
+            .collect();
+        let digest = table16_chip.hash_partially_public(&mut layouter, &message)?;
+        config.constrain_digest(&mut layouter, &digest)
+    }
+}
+
+/// Proves knowledge of a message whose digest is the public input.
+///
+/// The circuit depends only on the number of padded blocks, which is kept apart from
+/// the message so that [`Circuit::without_witnesses`] has the same shape. The message
+/// is padded in the circuit as by [`Table16Chip::hash_dynamic`], so any message that
+/// pads into at most that many blocks satisfies it.
+pub struct PreimageCircuit<FP: FloorPlanner = SimpleFloorPlanner> {
+    num_blocks: usize,
+    message: Value<Vec<u8>>,
+    _marker: PhantomData<FP>,
+}
+
+impl<FP: FloorPlanner> Clone for PreimageCircuit<FP> {
+    fn clone(&self) -> Self {
+        PreimageCircuit {
+            num_blocks: self.num_blocks,
+            message: self.message.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<FP: FloorPlanner> fmt::Debug for PreimageCircuit<FP> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreimageCircuit")
+            .field("num_blocks", &self.num_blocks)
+            .field("message", witness_debug(&self.message))
+            .finish()
+    }
+}
+
+impl PreimageCircuit {
+    /// Creates a circuit proving knowledge of `message`.
+    pub fn new(message: &[u8]) -> Self {
+        PreimageCircuit {
+            num_blocks: blocks_from_bytes(message).len(),
+            message: Value::known(message.to_vec()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<FP: FloorPlanner> PreimageCircuit<FP> {
+    /// Returns the same circuit, laid out with the floor planner `P`.
+    pub fn with_floor_planner<P: FloorPlanner>(mut self) -> PreimageCircuit<P> {
+        // Take the message rather than copying it, as dropping `self` clears it.
+        PreimageCircuit {
+            num_blocks: self.num_blocks,
+            message: std::mem::replace(&mut self.message, Value::unknown()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of padded blocks hashed, for [`Table16Config::minimum_k`].
+    pub fn num_blocks(&self) -> usize {
+        self.num_blocks
+    }
+}
+
+/// Clears the message. It is also cleared when the circuit is dropped.
+#[cfg(feature = "secrets")]
+impl<FP: FloorPlanner> Zeroize for PreimageCircuit<FP> {
+    fn zeroize(&mut self) {
+        self.message.as_mut().map(|message| message.zeroize());
+    }
+}
+
+#[cfg(feature = "secrets")]
+impl<FP: FloorPlanner> Drop for PreimageCircuit<FP> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "secrets")]
+impl<FP: FloorPlanner> ZeroizeOnDrop for PreimageCircuit<FP> {}
+
+impl<FP: FloorPlanner> Circuit<bn256::Fr> for PreimageCircuit<FP> {
+    type Config = DigestCircuitConfig;
+    type FloorPlanner = FP;
+
+    fn without_witnesses(&self) -> Self {
+        PreimageCircuit {
+            num_blocks: self.num_blocks,
+            message: Value::unknown(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        DigestCircuitConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        config.hash_to_instance(layouter, self.num_blocks, self.message.clone())
+    }
+}
+
+/// Proves knowledge of a message of private length whose digest is the public input,
+/// with [`Table16Chip::prove_preimage`].
+///
+/// The circuit depends only on `MAX_BLOCKS`: any message of at most
+/// `MAX_BLOCKS * 128 - 17` bytes fits, and the verifier learns nothing else about its
+/// length. The digest is exposed as [`packed_digest_instance`].
+#[derive(Clone)]
+pub struct BoundedPreimageCircuit<const MAX_BLOCKS: usize> {
+    message: Value<Vec<u8>>,
+}
+
+impl<const MAX_BLOCKS: usize> fmt::Debug for BoundedPreimageCircuit<MAX_BLOCKS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoundedPreimageCircuit")
+            .field("message", witness_debug(&self.message))
+            .finish()
+    }
+}
+
+impl<const MAX_BLOCKS: usize> BoundedPreimageCircuit<MAX_BLOCKS> {
+    /// Creates a circuit proving knowledge of `message`.
+    pub fn new(message: &[u8]) -> Self {
+        BoundedPreimageCircuit {
+            message: Value::known(message.to_vec()),
+        }
+    }
+
+    /// The smallest `k` that fits the circuit, with room for the in-circuit padding and
+    /// the selection of the digest.
+    pub fn minimum_k() -> u32 {
+        Table16Config::minimum_k(MAX_BLOCKS + 1)
+    }
+}
+
+impl<const MAX_BLOCKS: usize> Default for BoundedPreimageCircuit<MAX_BLOCKS> {
+    fn default() -> Self {
+        BoundedPreimageCircuit {
+            message: Value::unknown(),
+        }
+    }
+}
+
+/// Clears the message. It is also cleared when the circuit is dropped.
+#[cfg(feature = "secrets")]
+impl<const MAX_BLOCKS: usize> Zeroize for BoundedPreimageCircuit<MAX_BLOCKS> {
+    fn zeroize(&mut self) {
+        self.message.as_mut().map(|message| message.zeroize());
+    }
+}
+
+#[cfg(feature = "secrets")]
+impl<const MAX_BLOCKS: usize> Drop for BoundedPreimageCircuit<MAX_BLOCKS> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "secrets")]
+impl<const MAX_BLOCKS: usize> ZeroizeOnDrop for BoundedPreimageCircuit<MAX_BLOCKS> {}
+
+impl<const MAX_BLOCKS: usize> Circuit<bn256::Fr> for BoundedPreimageCircuit<MAX_BLOCKS> {
+    type Config = DigestCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        DigestCircuitConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.table16.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config.table16);
+        table16_chip.prove_preimage::<MAX_BLOCKS>(
+            &mut layouter,
+            self.message.clone(),
+            config.digest,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "secrets"))]
+mod tests {
+    use super::{BoundedPreimageCircuit, PreimageCircuit};
+    use zeroize::{Zeroize, ZeroizeOnDrop};
+
+    fn zeroize_on_drop<T: ZeroizeOnDrop>() {}
+
+    #[test]
+    fn zeroize_message() {
+        zeroize_on_drop::<PreimageCircuit>();
+        zeroize_on_drop::<BoundedPreimageCircuit<2>>();
+
+        let mut circuit = PreimageCircuit::new(b"a secret preimage");
+        // The buffer stays allocated after zeroize, so it can be read back.
+        let mut buffer = None;
+        circuit
+            .message
+            .as_ref()
+            .map(|message| buffer = Some((message.as_ptr(), message.capacity())));
+        let (ptr, capacity) = buffer.unwrap();
+
+        circuit.zeroize();
+        circuit.message.as_ref().assert_if_known(|message| message.is_empty());
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+        assert!(bytes.iter().all(|byte| *byte == 0));
+    }
+}
@@ -1,26 +1,37 @@
 use super::{
-    super::DIGEST_SIZE,
-    util::{i2lebsp, lebs2ip},
-    AssignedBits, BlockWord, SpreadInputs, SpreadVar, Table16Assignment, ROUNDS, STATE,
+    super::{Sha512Error, DIGEST_SIZE},
+    configure_spread_outputs,
+    util::{even_bits, i2lebsp, lebs2ip},
+    AssignedBits, Bits, BlockWord, Sha512Params, SpreadInputs, SpreadVar, Table16Assignment,
+    Table16Chip, Visibility, ROUNDS, STATE,
 };
 
 use halo2_proofs::{
-    circuit::{Layouter, Value},
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Chip, Layouter, Region, Value},
     halo2curves::bn256,
-    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    plonk::{Advice, Column, ConstraintSystem, Error, Instance, Selector},
     poly::Rotation,
 };
 
 use std::convert::TryInto;
+use std::fmt;
 use std::ops::Range;
 
 mod compression_gates;
 mod compression_util;
 mod subregion_digest;
+mod subregion_feed_forward;
 mod subregion_initial;
 mod subregion_main;
+#[cfg(feature = "trace")]
+pub mod trace;
 
-use compression_gates::CompressionGate;
+type CompressionGate<F> = compression_gates::CompressionGate<F, Sha512Params>;
+pub(super) use compression_util::{
+    dense_state, SUBREGION_DIGEST_ROWS, SUBREGION_FEED_FORWARD_ROWS, SUBREGION_INITIAL_ROWS,
+    SUBREGION_MAIN_ROWS, SUBREGION_MAIN_WORD,
+};
 
 pub trait UpperSigmaVar<
     const A_LEN: usize,
@@ -71,6 +82,122 @@ pub trait UpperSigmaVar<
     }
 }
 
+/// A piece of a word decomposition whose length does not match the spread variable it
+/// is assigned to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct DecompositionError {
+    /// The decomposed variable, such as `AbcdVar`.
+    pub var: &'static str,
+    /// The piece of `var` that failed, such as `b_lo`.
+    pub piece: &'static str,
+    /// The length of the piece, in bits.
+    pub len: usize,
+    /// The length expected by `var`, in bits.
+    pub expected: usize,
+}
+
+impl fmt::Display for DecompositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} piece {} has {} bits, expected {}",
+            self.var, self.piece, self.len, self.expected
+        )
+    }
+}
+
+impl std::error::Error for DecompositionError {}
+
+impl From<DecompositionError> for Error {
+    fn from(_: DecompositionError) -> Self {
+        Error::Synthesis
+    }
+}
+
+impl From<DecompositionError> for Sha512Error {
+    fn from(err: DecompositionError) -> Self {
+        Sha512Error::DecompositionMismatch { var: err.var }
+    }
+}
+
+/// Checks that `pieces` has one piece of the expected length for each `(name, bits)`
+/// entry of `expected`.
+fn check_pieces(
+    var: &'static str,
+    expected: &[(&'static str, usize)],
+    pieces: Vec<Vec<bool>>,
+) -> Result<Vec<Vec<bool>>, DecompositionError> {
+    for (idx, &(piece, bits)) in expected.iter().enumerate() {
+        let len = pieces.get(idx).map_or(0, |piece| piece.len());
+        if len != bits {
+            return Err(DecompositionError {
+                var,
+                piece,
+                len,
+                expected: bits,
+            });
+        }
+    }
+    if pieces.len() != expected.len() {
+        return Err(DecompositionError {
+            var,
+            piece: "(extra)",
+            len: pieces[expected.len()].len(),
+            expected: 0,
+        });
+    }
+    Ok(pieces)
+}
+
+/// Moves the error of a decomposition out of its [`Value`].
+fn known_pieces(
+    pieces: Value<Result<Vec<Vec<bool>>, DecompositionError>>,
+) -> Result<Value<Vec<Vec<bool>>>, DecompositionError> {
+    let mut error = None;
+    let pieces = pieces.map(|pieces| {
+        pieces.unwrap_or_else(|e| {
+            error = Some(e);
+            vec![]
+        })
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(pieces),
+    }
+}
+
+/// Concatenates the spread forms of the pieces `lo` and `hi` of a word, `lo` being the
+/// little end.
+///
+/// # Panics
+///
+/// Panics if the spread lengths of `lo` and `hi` do not add up to `N`.
+fn concat_spread<
+    const LO: usize,
+    const LO_SPREAD: usize,
+    const HI: usize,
+    const HI_SPREAD: usize,
+    const N: usize,
+>(
+    lo: &SpreadVar<LO, LO_SPREAD>,
+    hi: &SpreadVar<HI, HI_SPREAD>,
+) -> Value<[bool; N]> {
+    assert_eq!(
+        LO_SPREAD + HI_SPREAD,
+        N,
+        "cannot concatenate spread pieces of {} and {} bits into {} bits",
+        LO_SPREAD,
+        HI_SPREAD,
+        N
+    );
+    lo.spread.value().zip(hi.spread.value()).map(|(lo, hi)| {
+        let mut spread = [false; N];
+        spread[..LO_SPREAD].copy_from_slice(&lo.0);
+        spread[LO_SPREAD..].copy_from_slice(&hi.0);
+        spread
+    })
+}
+
 /// A variable that represents the `[A,B,C,D]` words of the SHA-512 internal state.
 ///
 /// The structure of this variable is influenced by the following factors:
@@ -124,80 +251,52 @@ impl AbcdVar {
         53..64
     }
 
-    fn pieces(val: u64) -> Vec<Vec<bool>> {
+    /// The length of each piece, matching the spread variables above.
+    const PIECE_BITS: [(&'static str, usize); 8] = [
+        ("a_lo", 14),
+        ("a_hi", 14),
+        ("b_lo", 3),
+        ("b_hi", 3),
+        ("c_lo", 2),
+        ("c_hi", 3),
+        ("d_lo", 14),
+        ("d_hi", 11),
+    ];
+
+    fn pieces(val: u64) -> Result<Vec<Vec<bool>>, DecompositionError> {
         let val: [bool; 64] = i2lebsp(val.into());
-        vec![
-            val[Self::a_lo_range()].to_vec(),
-            val[Self::a_hi_range()].to_vec(),
-            val[Self::b_lo_range()].to_vec(),
-            val[Self::b_hi_range()].to_vec(),
-            val[Self::c_lo_range()].to_vec(),
-            val[Self::c_hi_range()].to_vec(),
-            val[Self::d_lo_range()].to_vec(),
-            val[Self::d_hi_range()].to_vec(),
-        ]
+        check_pieces(
+            "AbcdVar",
+            &Self::PIECE_BITS,
+            vec![
+                val[Self::a_lo_range()].to_vec(),
+                val[Self::a_hi_range()].to_vec(),
+                val[Self::b_lo_range()].to_vec(),
+                val[Self::b_hi_range()].to_vec(),
+                val[Self::c_lo_range()].to_vec(),
+                val[Self::c_hi_range()].to_vec(),
+                val[Self::d_lo_range()].to_vec(),
+                val[Self::d_hi_range()].to_vec(),
+            ],
+        )
     }
 }
 
 impl UpperSigmaVar<56,12,10,50> for AbcdVar {
     fn spread_a(&self) -> Value<[bool; 56]> {
-        self.a_lo
-        .spread
-        .value()
-        .zip(self.a_hi.spread.value())
-        .map(|(a_lo, a_hi)| {
-            a_lo.iter()
-                .chain(a_hi.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
+        concat_spread(&self.a_lo, &self.a_hi)
     }
 
     fn spread_b(&self) -> Value<[bool; 12]> {
-        self.b_lo
-        .spread
-        .value()
-        .zip(self.b_hi.spread.value())
-        .map(|(b_lo, b_hi)| {
-            b_lo.iter()
-                .chain(b_hi.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
+        concat_spread(&self.b_lo, &self.b_hi)
     }
 
     fn spread_c(&self) -> Value<[bool; 10]> {
-        self.c_lo
-        .spread
-        .value()
-        .zip(self.c_hi.spread.value())
-        .map(|(c_lo, c_hi)| {
-            c_lo.iter()
-                .chain(c_hi.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
+        concat_spread(&self.c_lo, &self.c_hi)
     }
 
     fn spread_d(&self) -> Value<[bool; 50]> {
-        self.d_lo
-        .spread
-        .value()
-        .zip(self.d_hi.spread.value())
-        .map(|(d_lo, d_hi)| {
-            d_lo.iter()
-                .chain(d_hi.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
+        concat_spread(&self.d_lo, &self.d_hi)
     }
 }
 
@@ -249,17 +348,32 @@ impl EfghVar {
         54..64
     }
 
-    fn pieces(val: u64) -> Vec<Vec<bool>> {
+    /// The length of each piece, matching the spread variables above.
+    const PIECE_BITS: [(&'static str, usize); 7] = [
+        ("a", 14),
+        ("b_lo", 2),
+        ("b_hi", 2),
+        ("c_lo", 13),
+        ("c_hi", 10),
+        ("d_lo", 13),
+        ("d_hi", 10),
+    ];
+
+    fn pieces(val: u64) -> Result<Vec<Vec<bool>>, DecompositionError> {
         let val: [bool; 64] = i2lebsp(val.into());
-        vec![
-            val[Self::a_range()].to_vec(),
-            val[Self::b_lo_range()].to_vec(),
-            val[Self::b_hi_range()].to_vec(),
-            val[Self::c_lo_range()].to_vec(),
-            val[Self::c_hi_range()].to_vec(),
-            val[Self::d_lo_range()].to_vec(),
-            val[Self::d_hi_range()].to_vec(),
-        ]
+        check_pieces(
+            "EfghVar",
+            &Self::PIECE_BITS,
+            vec![
+                val[Self::a_range()].to_vec(),
+                val[Self::b_lo_range()].to_vec(),
+                val[Self::b_hi_range()].to_vec(),
+                val[Self::c_lo_range()].to_vec(),
+                val[Self::c_hi_range()].to_vec(),
+                val[Self::d_lo_range()].to_vec(),
+                val[Self::d_hi_range()].to_vec(),
+            ],
+        )
     }
 }
 impl UpperSigmaVar<28, 8, 46, 46> for EfghVar {
@@ -268,51 +382,26 @@ impl UpperSigmaVar<28, 8, 46, 46> for EfghVar {
     }
 
     fn spread_b(&self) -> Value<[bool; 8]> {
-        self.b_lo
-            .spread
-            .value()
-            .zip(self.b_hi.spread.value())
-            .map(|(b_lo, b_hi)| {
-                b_lo.iter()
-                    .chain(b_hi.iter())
-                    .copied()
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap()
-            })
+        concat_spread(&self.b_lo, &self.b_hi)
     }
 
     fn spread_c(&self) -> Value<[bool; 46]> {
-        self.c_lo
-            .spread
-            .value()
-            .zip(self.c_hi.spread.value())
-            .map(|(c_lo, c_hi)| {
-                c_lo.iter()
-                    .chain(c_hi.iter())
-                    .copied()
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap()
-            })
+        concat_spread(&self.c_lo, &self.c_hi)
     }
 
     fn spread_d(&self) -> Value<[bool; 46]> {
-        self.d_lo
-            .spread
-            .value()
-            .zip(self.d_hi.spread.value())
-            .map(|(d_lo, d_hi)| {
-                d_lo.iter()
-                    .chain(d_hi.iter())
-                    .copied()
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap()
-            })
+        concat_spread(&self.d_lo, &self.d_hi)
     }
 }
 
+/// The dense form of a state word, as its `(lo, hi)` 32-bit halves.
+///
+/// The field could hold the whole 64-bit word, and the addition gates (`H'`, `A_new`,
+/// `E_new`, the feed-forward) already recombine `lo + 2^32 * hi` with a single carry.
+/// The halves are kept because a word is only range-checked through the spread table,
+/// two 16-bit chunks per half, and each half is copied next to its chunks. A single
+/// 64-bit cell would still need all four chunks beside it, so it would move copies
+/// around rather than remove them.
 #[derive(Clone, Debug)]
 pub struct RoundWordDense(AssignedBits<32>, AssignedBits<32>);
 
@@ -322,7 +411,41 @@ impl From<(AssignedBits<32>, AssignedBits<32>)> for RoundWordDense {
     }
 }
 
+impl From<RoundWordDense> for (AssignedBits<32>, AssignedBits<32>) {
+    fn from(word: RoundWordDense) -> Self {
+        (word.0, word.1)
+    }
+}
+
 impl RoundWordDense {
+    /// Assigns the `(lo, hi)` 32-bit halves of `word` to `(lo_col, lo_row)` and
+    /// `(hi_col, hi_row)`. The halves are not range-checked here.
+    pub(super) fn from_u64(
+        region: &mut Region<'_, bn256::Fr>,
+        lo_row: usize,
+        lo_col: Column<Advice>,
+        hi_row: usize,
+        hi_col: Column<Advice>,
+        word: Value<u64>,
+    ) -> Result<Self, Error> {
+        let lo = AssignedBits::<32>::assign(
+            region,
+            || "lo",
+            lo_col,
+            lo_row,
+            word.map(|w| w as u32),
+        )?;
+        let hi = AssignedBits::<32>::assign(
+            region,
+            || "hi",
+            hi_col,
+            hi_row,
+            word.map(|w| (w >> 32) as u32),
+        )?;
+        Ok(Self(lo, hi))
+    }
+
+    /// Returns the word, the inverse of [`RoundWordDense::from_u64`].
     pub fn value(&self) -> Value<u64> {
         self.0
             .value_u32()
@@ -331,6 +454,7 @@ impl RoundWordDense {
     }
 }
 
+/// The spread form of a state word, as the spread forms of its `(lo, hi)` 32-bit halves.
 #[derive(Clone, Debug)]
 pub struct RoundWordSpread(AssignedBits<64>, AssignedBits<64>);
 
@@ -341,12 +465,30 @@ impl From<(AssignedBits<64>, AssignedBits<64>)> for RoundWordSpread {
 }
 
 impl RoundWordSpread {
+    /// Returns the assigned spread forms of the `(lo, hi)` halves, so they can be
+    /// copy-constrained by another spread-based gadget.
+    pub fn halves(&self) -> (&AssignedBits<64>, &AssignedBits<64>) {
+        (&self.0, &self.1)
+    }
+
+    /// Returns the spread form of the whole word, `lo + 2^64 * hi`, where `lo` and `hi`
+    /// are the 64-bit spread forms of its low and high 32-bit halves.
+    ///
+    /// As each half spreads to exactly 64 bits, this is the spread of the 64-bit word:
+    /// bit `i` of the word is at bit `2i`, and every odd bit is zero. Use
+    /// [`RoundWordSpread::dense_value`] to recover the word.
     pub fn value(&self) -> Value<u128> {
         self.0
             .value_u64()
             .zip(self.1.value_u64())
             .map(|(lo, hi)| lo as u128 + (1 << 64) * hi as u128)
     }
+
+    /// Returns the 64-bit word this is the spread form of, from the even bits of
+    /// [`RoundWordSpread::value`].
+    pub fn dense_value(&self) -> Value<u64> {
+        self.value().map(|spread| lebs2ip(&even_bits::<128, 64>(i2lebsp(spread))) as u64)
+    }
 }
 
 
@@ -438,6 +580,16 @@ pub struct State {
 }
 
 impl State {
+    /// Creates a state from its eight words, each of which must be the [`StateWord`]
+    /// variant named after its position.
+    ///
+    /// `A` and `E` may be dense-only, as after the last round; such a state cannot start
+    /// a round until it has been initialized again, and
+    /// [`Table16Chip::assign_round`](super::Table16Chip::assign_round) rejects it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Synthesis`] if a word is in the wrong position.
     #[allow(clippy::many_single_char_names)]
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -449,19 +601,46 @@ impl State {
         f: StateWord,
         g: StateWord,
         h: StateWord,
-    ) -> Self {
-        State {
-            a: Some(a),
-            b: Some(b),
-            c: Some(c),
-            d: Some(d),
-            e: Some(e),
-            f: Some(f),
-            g: Some(g),
-            h: Some(h),
+    ) -> Result<Self, Error> {
+        match (&a, &b, &c, &d, &e, &f, &g, &h) {
+            (
+                StateWord::A(_),
+                StateWord::B(_),
+                StateWord::C(_),
+                StateWord::D(_),
+                StateWord::E(_),
+                StateWord::F(_),
+                StateWord::G(_),
+                StateWord::H(_),
+            ) => Ok(State {
+                a: Some(a),
+                b: Some(b),
+                c: Some(c),
+                d: Some(d),
+                e: Some(e),
+                f: Some(f),
+                g: Some(g),
+                h: Some(h),
+            }),
+            _ => Err(Error::Synthesis),
         }
     }
 
+    /// Assigns the state whose words `A` to `H` are in rows `0..8` of `instance`, for
+    /// instance a chaining value or digest made public by another proof. The column
+    /// must have equality enabled.
+    ///
+    /// Each word is range-checked to 64 bits and copy-constrained to its instance cell,
+    /// and the state can be compressed further or passed to
+    /// [`Sha512Instructions::digest`](super::super::Sha512Instructions::digest).
+    pub fn from_instance_words(
+        chip: &Table16Chip,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        instance: Column<Instance>,
+    ) -> Result<Self, Error> {
+        chip.config().compression.initialize_with_instance(layouter, instance)
+    }
+
     pub fn empty_state() -> Self {
         State {
             a: None,
@@ -474,6 +653,52 @@ impl State {
             h: None,
         }
     }
+
+    /// Clears every word, leaving the state as [`State::empty_state`]. Resetting an
+    /// empty state does nothing.
+    pub fn reset(&mut self) {
+        *self = State::empty_state();
+    }
+
+    /// Returns the spread form of each of the words `A` to `H`, where the state carries
+    /// one.
+    ///
+    /// `B`, `C`, `F` and `G` always carry their spread forms. `A` and `E` carry them in
+    /// the states returned by the initialization and by the feed-forward, but not in
+    /// the state returned by the 80 rounds of a compression, whose new `A` and `E` are
+    /// only assigned in dense form. `D` and `H` are always dense-only, and words of an
+    /// empty state are `None`.
+    pub fn spread_halves(&self) -> [Option<&RoundWordSpread>; STATE] {
+        [&self.a, &self.b, &self.c, &self.d, &self.e, &self.f, &self.g, &self.h]
+            .map(|word| word.as_ref().and_then(StateWord::spread_halves))
+    }
+
+    /// Returns the dense halves of the words `A` to `H` without assigning anything, for
+    /// composing with gadgets that take the `(lo, hi)` halves of a digest.
+    ///
+    /// These are the digest words only if the state is finalized, that is, if the
+    /// feed-forward of the last block has been applied, as in the state returned by
+    /// [`Table16Chip::add_feed_forward`]; this is not checked. Unlike
+    /// [`Sha512Instructions::digest`](super::super::Sha512Instructions::digest), no
+    /// region is assigned and no 64-bit cell is formed from the halves.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Synthesis`] for an empty state.
+    pub fn into_digest_words(self) -> Result<[RoundWordDense; STATE], Error> {
+        dense_state(self)
+    }
+
+    /// Returns the values of [`State::spread_halves`].
+    pub fn spread_values(&self) -> [Option<Value<u128>>; STATE] {
+        self.spread_halves().map(|spread| spread.map(RoundWordSpread::value))
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State::empty_state()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -488,6 +713,21 @@ pub enum StateWord {
     H(RoundWordDense),
 }
 
+impl StateWord {
+    /// Returns the spread form of this word, or `None` for the dense-only `D` and `H`,
+    /// and for an `A` or `E` assigned without one.
+    pub fn spread_halves(&self) -> Option<&RoundWordSpread> {
+        match self {
+            StateWord::A(word) => word.spread_halves.as_ref(),
+            StateWord::E(word) => word.spread_halves.as_ref(),
+            StateWord::B(word) | StateWord::C(word) | StateWord::F(word) | StateWord::G(word) => {
+                Some(&word.spread_halves)
+            }
+            StateWord::D(_) | StateWord::H(_) => None,
+        }
+    }
+}
+
 
 #[derive(Clone, Debug)]
 pub(super) struct CompressionConfig {
@@ -511,6 +751,11 @@ pub(super) struct CompressionConfig {
     s_decompose_efgh: Selector,
 
     s_digest: Selector,
+    // Packs the digest words into three field elements
+    s_digest_packed: Selector,
+    s_feed_forward: Selector,
+    // Ties the dense outputs of the sigma, ch, ch_neg and maj gates to their lookups
+    s_spread_outputs: Selector,
 }
 
 impl Table16Assignment for CompressionConfig {}
@@ -538,6 +783,8 @@ impl CompressionConfig {
         let s_decompose_efgh = meta.selector();
 
         let s_digest = meta.selector();
+        let s_digest_packed = meta.selector();
+        let s_feed_forward = meta.selector();
 
         // Rename these here for ease of matching the gates to the specification.
         let a_0 = lookup.tag;
@@ -550,6 +797,9 @@ impl CompressionConfig {
         let a_7 = extras[3];
         let a_8 = extras[4];
         let a_9 = extras[5];
+
+        let s_spread_outputs = configure_spread_outputs(meta, &lookup, a_3);
+
         // Decompose `A,B,C,D` words into (28, 6, 5, 25)-bit chunks.
         // `b` is split into (3,3)-bit b_lo and b_hi.
         // `c` is split into (2,3)-bit c_lo and c_hi.
@@ -872,7 +1122,9 @@ impl CompressionConfig {
             let h_prime_carry = meta.query_advice(a_9, Rotation::next());
             let sigma_e_lo = meta.query_advice(a_4, Rotation::cur());
             let sigma_e_hi = meta.query_advice(a_5, Rotation::cur());
-            let ch_lo = meta.query_advice(a_3, Rotation(3)); //changed
+            // The low half of E ∧ F is read in place, from the R_0^{odd} output of the
+            // s_ch gate on the same row, and its high half is copied next to H'.
+            let ch_lo = meta.query_advice(a_3, Rotation(3));
             let ch_hi = meta.query_advice(a_6, Rotation::next());
             let ch_neg_lo = meta.query_advice(a_5, Rotation::prev());
             let ch_neg_hi = meta.query_advice(a_5, Rotation::next());
@@ -911,7 +1163,9 @@ impl CompressionConfig {
             let a_new_carry = meta.query_advice(a_9, Rotation::cur());
             let sigma_a_lo = meta.query_advice(a_6, Rotation::cur());
             let sigma_a_hi = meta.query_advice(a_6, Rotation::next());
-            let maj_abc_lo = meta.query_advice(a_3, Rotation(3)); //changed
+            // As in s_h_prime, the low half of Maj(A, B, C) is read in place, from the
+            // R_0^{odd} output of the s_maj gate on the same row.
+            let maj_abc_lo = meta.query_advice(a_3, Rotation(3));
             let maj_abc_hi = meta.query_advice(a_3, Rotation::prev());
             let h_prime_lo = meta.query_advice(a_7, Rotation::prev());
             let h_prime_hi = meta.query_advice(a_8, Rotation::prev());
@@ -975,6 +1229,72 @@ impl CompressionConfig {
             )
         });
 
+        // s_digest_packed to pack the digest words into three field elements
+        meta.create_gate("s_digest_packed", |meta| {
+            let s_digest_packed = meta.query_selector(s_digest_packed);
+            let words = [
+                meta.query_advice(a_3, Rotation::cur()),
+                meta.query_advice(a_4, Rotation::cur()),
+                meta.query_advice(a_5, Rotation::cur()),
+                meta.query_advice(a_3, Rotation::next()),
+                meta.query_advice(a_4, Rotation::next()),
+                meta.query_advice(a_5, Rotation::next()),
+                meta.query_advice(a_4, Rotation(2)),
+                meta.query_advice(a_5, Rotation(2)),
+            ];
+            let packed = [
+                meta.query_advice(a_6, Rotation::cur()),
+                meta.query_advice(a_6, Rotation::next()),
+                meta.query_advice(a_6, Rotation(2)),
+            ];
+
+            CompressionGate::s_digest_packed(s_digest_packed, words, packed)
+        });
+
+        // s_feed_forward to get H_i = H_{i-1} + H
+        meta.create_gate("s_feed_forward", |meta| {
+            let s_feed_forward = meta.query_selector(s_feed_forward);
+            let prev_lo = meta.query_advice(a_3, Rotation::cur());
+            let prev_hi = meta.query_advice(a_4, Rotation::cur());
+            let cur_lo = meta.query_advice(a_5, Rotation::cur());
+            let cur_hi = meta.query_advice(a_6, Rotation::cur());
+            let sum_lo = meta.query_advice(a_7, Rotation::cur());
+            let sum_hi = meta.query_advice(a_8, Rotation::cur());
+            let carry = meta.query_advice(a_9, Rotation::cur());
+            let spread_sum_lo = meta.query_advice(a_7, Rotation::next());
+            let spread_sum_hi = meta.query_advice(a_8, Rotation::next());
+            // 16-bit chunks of the sum, looked up in the spread table
+            let dense_0 = meta.query_advice(a_1, Rotation::cur());
+            let dense_1 = meta.query_advice(a_1, Rotation::next());
+            let dense_2 = meta.query_advice(a_1, Rotation(2));
+            let dense_3 = meta.query_advice(a_1, Rotation(3));
+            let spread_0 = meta.query_advice(a_2, Rotation::cur());
+            let spread_1 = meta.query_advice(a_2, Rotation::next());
+            let spread_2 = meta.query_advice(a_2, Rotation(2));
+            let spread_3 = meta.query_advice(a_2, Rotation(3));
+
+            CompressionGate::s_feed_forward(
+                s_feed_forward,
+                prev_lo,
+                prev_hi,
+                cur_lo,
+                cur_hi,
+                sum_lo,
+                sum_hi,
+                carry,
+                spread_sum_lo,
+                spread_sum_hi,
+                dense_0,
+                dense_1,
+                dense_2,
+                dense_3,
+                spread_0,
+                spread_1,
+                spread_2,
+                spread_3,
+            )
+        });
+
         CompressionConfig {
             lookup,
             message_schedule,
@@ -990,6 +1310,9 @@ impl CompressionConfig {
             s_decompose_abcd,
             s_decompose_efgh,
             s_digest,
+            s_digest_packed,
+            s_feed_forward,
+            s_spread_outputs,
         }
     }
 
@@ -1011,6 +1334,38 @@ impl CompressionConfig {
         Ok(new_state)
     }
 
+    /// Initialize compression with the state whose words `A` to `H` are in rows `0..8`
+    /// of `instance`, which must have equality enabled.
+    ///
+    /// The words are decomposed as the IV is. Each one is then bound to its instance
+    /// cell through the 64-bit word of a digest region, which recombines its halves.
+    pub(super) fn initialize_with_instance(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        instance: Column<Instance>,
+    ) -> Result<State, Error> {
+        let mut new_state = State::empty_state();
+        layouter.assign_region(
+            || "initialize_with_instance",
+            |mut region| {
+                let mut words = [Value::unknown(); STATE];
+                for (row, word) in words.iter_mut().enumerate() {
+                    *word = region
+                        .instance_value(instance, row)?
+                        .map(|word| word.get_lower_128() as u64);
+                }
+                new_state = self.initialize_words(&mut region, words)?;
+                Ok(())
+            },
+        )?;
+
+        let words = self.digest_cells(layouter, new_state.clone())?;
+        for (row, word) in words.iter().enumerate() {
+            layouter.constrain_instance(word.cell(), instance, row)?;
+        }
+        Ok(new_state)
+    }
+
     /// Initialize compression with some initialized state. This could be a state
     /// output from a previous compression round.
     pub(super) fn initialize_with_state(
@@ -1029,20 +1384,46 @@ impl CompressionConfig {
         Ok(new_state)
     }
 
-    /// Given an initialized state and a message schedule, perform 80 compression rounds.
+    /// Given an initialized state and a message schedule, perform the first `rounds`
+    /// compression rounds. Standard SHA-512 uses all 80.
+    ///
+    /// Returns [`Sha512Error::RoundOutOfRange`], as an [`Error::Synthesis`], if `rounds`
+    /// is greater than 80, and [`Error::Synthesis`] if `w_halves` has fewer than `rounds`
+    /// words.
     pub(super) fn compress(
         &self,
         layouter: &mut impl Layouter<bn256::Fr>,
         initialized_state: State,
-        w_halves: [(AssignedBits<32>, AssignedBits<32>); ROUNDS],
+        w_halves: &[(AssignedBits<32>, AssignedBits<32>)],
+        rounds: usize,
     ) -> Result<State, Error> {
+        if rounds > ROUNDS {
+            return Err(Sha512Error::RoundOutOfRange {
+                round: rounds - 1,
+                rounds: ROUNDS,
+            }
+            .into());
+        }
+        if w_halves.len() < rounds {
+            return Err(Error::Synthesis);
+        }
+
         let mut state = State::empty_state();
         layouter.assign_region(
             || "compress",
             |mut region| {
                 state = initialized_state.clone();
-                for (idx, w_halves) in w_halves.iter().enumerate() {
-                    state = self.assign_round(&mut region, idx.into(), state.clone(), w_halves)?;
+                for (idx, w_halves) in w_halves.iter().take(rounds).enumerate() {
+                    let last_round = idx + 1 == rounds;
+                    // Each round consumes the state of the previous one, so move it
+                    // rather than copying it.
+                    state = self.assign_round(
+                        &mut region,
+                        idx.into(),
+                        std::mem::take(&mut state),
+                        w_halves,
+                        last_round,
+                    )?;
                 }
                 Ok(())
             },
@@ -1050,32 +1431,154 @@ impl CompressionConfig {
         Ok(state)
     }
 
+    /// Add the state a block was compressed from to the compressed state, word by word
+    /// modulo $2^{64}$. Returns the chaining value for the next block.
+    pub(super) fn add_feed_forward(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev: State,
+        compressed: State,
+    ) -> Result<State, Error> {
+        self.add_feed_forward_spread(layouter, prev, compressed)
+            .map(|(state, _)| state)
+    }
+
+    /// Like [`CompressionConfig::add_feed_forward`], also returning the spread halves of
+    /// the words `A` to `H` of the chaining value.
+    pub(super) fn add_feed_forward_spread(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        prev: State,
+        compressed: State,
+    ) -> Result<(State, [RoundWordSpread; STATE]), Error> {
+        layouter.assign_region(
+            || "feed_forward",
+            |mut region| self.assign_feed_forward(&mut region, prev.clone(), compressed.clone()),
+        )
+    }
+
     /// After the final round, convert the state into the final digest.
     pub(super) fn digest(
         &self,
         layouter: &mut impl Layouter<bn256::Fr>,
         state: State,
     ) -> Result<[BlockWord; DIGEST_SIZE], Error> {
-        let mut digest = [BlockWord(Value::known(0)); DIGEST_SIZE];
+        let digest = self.digest_cells(layouter, state)?;
+        Ok(digest.map(|word| BlockWord(word.value_u64())))
+    }
+
+    /// After the final round, convert the state into the assigned 64-bit words of the
+    /// final digest.
+    pub(super) fn digest_cells(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        state: State,
+    ) -> Result<[AssignedBits<64>; DIGEST_SIZE], Error> {
         layouter.assign_region(
             || "digest",
+            |mut region| self.assign_digest(&mut region, state.clone()),
+        )
+    }
+
+    /// Packs the words of `digest` into three field elements, as documented on
+    /// [`Table16Chip::expose_digest_packed`].
+    pub(super) fn digest_packed(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        digest: &[AssignedBits<64>; DIGEST_SIZE],
+    ) -> Result<[AssignedCell<bn256::Fr, bn256::Fr>; 3], Error> {
+        layouter.assign_region(
+            || "digest_packed",
+            |mut region| self.assign_digest_packed(&mut region, digest),
+        )
+    }
+
+    /// Assigns the words of a message, one per row: public words copy-constrained to
+    /// constants, private ones witnessed.
+    pub(super) fn assign_message_words(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        words: &[(BlockWord, Visibility)],
+    ) -> Result<Vec<AssignedBits<64>>, Error> {
+        let a_3 = self.extras[0];
+
+        layouter.assign_region(
+            || "message words",
             |mut region| {
-                digest = self.assign_digest(&mut region, state.clone())?;
-                
-                Ok(())
+                let mut cells = Vec::with_capacity(words.len());
+                for (row, (word, visibility)) in words.iter().enumerate() {
+                    let cell = match visibility {
+                        Visibility::Public => {
+                            let mut known = None;
+                            word.0.map(|word| known = Some(word));
+                            let word = known.ok_or(Error::Synthesis)?;
+                            AssignedBits(region.assign_advice_from_constant(
+                                || "public word",
+                                a_3,
+                                row,
+                                Bits::<64>::from(word),
+                            )?)
+                        }
+                        Visibility::Private => AssignedBits::<64>::assign(
+                            &mut region,
+                            || "private word",
+                            a_3,
+                            row,
+                            word.0,
+                        )?,
+                    };
+                    cells.push(cell);
+                }
+                Ok(cells)
             },
-        )?;
-        Ok(digest)
+        )
+    }
+
+    /// Assigns the words of a digest computed outside the circuit, each one
+    /// copy-constrained to a constant.
+    pub(super) fn constant_digest_cells(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        digest: [u64; DIGEST_SIZE],
+    ) -> Result<[AssignedBits<64>; DIGEST_SIZE], Error> {
+        let a_3 = self.extras[0];
+
+        layouter.assign_region(
+            || "constant digest",
+            |mut region| {
+                let mut words = Vec::with_capacity(DIGEST_SIZE);
+                for (row, word) in digest.iter().enumerate() {
+                    let cell = region.assign_advice_from_constant(
+                        || "digest word",
+                        a_3,
+                        row,
+                        Bits::<64>::from(*word),
+                    )?;
+                    words.push(AssignedBits(cell));
+                }
+                Ok(words.try_into().unwrap())
+            },
+        )
     }
 }
 #[cfg(test)]
 mod tests {
     use super::super::{
-        super::BLOCK_SIZE, msg_schedule_test_input, BlockWord, Table16Chip, Table16Config, IV,
+        super::{Sha512Error, BLOCK_SIZE},
+        msg_schedule_test_input,
+        util::{i2lebsp, lebs2ip, negate_spread, reference_compress_rounds, MASK_EVEN_64},
+        BlockWord, Sha512Instructions, Sha512Params, Table16Chip, Table16Config, WordParams,
+        IV, ROUNDS, STATE,
+    };
+    use super::{
+        check_pieces,
+        compression_util::{get_ch_row, get_maj_row, MainRoundIdx},
+        dense_state, AbcdVar, AssignedBits, CompressionConfig, EfghVar, RoundWordDense, State,
+        StateWord,
     };
     use halo2_proofs::{
-        circuit::{Layouter, SimpleFloorPlanner},
-        dev::MockProver,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{MockProver, VerifyFailure},
         plonk::{Circuit, ConstraintSystem, Error},
     };
     use halo2_proofs::halo2curves::bn256;
@@ -1106,14 +1609,14 @@ mod tests {
                 // Test vector: "abc"
                 let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
 
-                let (_, w_halves) = config.message_schedule.process(&mut layouter, input)?;
+                let w_halves = config.message_schedule.process(&mut layouter, input)?.halves;
 
                 let compression = config.compression.clone();
                 let initial_state = compression.initialize_with_iv(&mut layouter, IV)?;
 
                 let state = config
                     .compression
-                    .compress(&mut layouter, initial_state, w_halves)?;
+                    .compress(&mut layouter, initial_state, &w_halves, ROUNDS)?;
 
                 let digest = config.compression.digest(&mut layouter, state)?;
                 println!("{:?}",digest);
@@ -1131,10 +1634,1293 @@ mod tests {
 
         let circuit: MyCircuit = MyCircuit {};
 
-        let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![]) {
+        let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn compress_rounds() {
+        struct MyCircuit {
+            rounds: usize,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { rounds: self.rounds }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let table16_chip = Table16Chip::construct(config);
+
+                // Test vector: "abc"
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let mut block = [0; BLOCK_SIZE];
+                block[0] = 0x6162638000000000;
+                block[BLOCK_SIZE - 1] = 24;
+
+                let iv = table16_chip.initialization_vector(&mut layouter)?;
+                let compressed =
+                    table16_chip.compress_block_rounds(&mut layouter, &iv, input, self.rounds)?;
+                let state = table16_chip.add_feed_forward(&mut layouter, &iv, &compressed)?;
+                let digest = table16_chip.digest(&mut layouter, &state)?;
+
+                let expected = reference_compress_rounds(IV, block, self.rounds);
+                for (idx, digest_word) in digest.iter().enumerate() {
+                    let expected = expected[idx].wrapping_add(IV[idx]);
+                    digest_word.0.assert_if_known(|digest_word| *digest_word == expected);
+                    if self.rounds == ROUNDS {
+                        assert_eq!(expected, super::compression_util::COMPRESSION_OUTPUT[idx]);
+                    }
+                }
+
+                Ok(())
+            }
+        }
+
+        for rounds in [16, ROUNDS] {
+            let circuit = MyCircuit { rounds };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            assert_eq!(prover.verify(), Ok(()));
+        }
+
+        // One round more than SHA-512 has fails synthesis rather than panicking.
+        let circuit = MyCircuit { rounds: ROUNDS + 1 };
+        assert!(matches!(
+            MockProver::<bn256::Fr>::run(17, &circuit, vec![]),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn round_trace() {
+        use super::super::ROUND_CONSTANTS;
+        use super::trace::{self, Step};
+
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let table16_chip = Table16Chip::construct(config);
+
+                // Test vector: "abc"
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let iv = table16_chip.initialization_vector(&mut layouter)?;
+                table16_chip.compress_block(&mut layouter, &iv, input)?;
+                Ok(())
+            }
+        }
+
+        trace::take();
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
             Ok(prover) => prover,
             Err(e) => panic!("{:?}", e),
         };
         assert_eq!(prover.verify(), Ok(()));
+
+        let trace = trace::take();
+        assert_eq!(trace.len(), 3 * ROUNDS);
+        for (idx, entry) in trace.iter().enumerate() {
+            assert_eq!(entry.round, idx / 3);
+            assert_eq!(entry.step, [Step::HPrime, Step::ENew, Step::ANew][idx % 3]);
+        }
+
+        // The unreduced sums of the first round, from the IV and W_0.
+        let mut block = [0; BLOCK_SIZE];
+        block[0] = 0x6162638000000000;
+        block[BLOCK_SIZE - 1] = 24;
+        let [a, b, c, d, e, f, g, h] = IV;
+        let sigma_1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ (!e & g);
+        let sigma_0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let sum = |words: &[u64]| words.iter().map(|&word| word as u128).sum::<u128>();
+        let h_prime = sum(&[h, ch, sigma_1, ROUND_CONSTANTS[0], block[0]]);
+        let e_new = sum(&[h_prime as u64, d]);
+        let a_new = sum(&[h_prime as u64, sigma_0, maj]);
+
+        for (entry, sum) in trace.iter().zip([h_prime, e_new, a_new]) {
+            entry.value.assert_if_known(|value| *value == (sum as u64) as u128);
+            entry.carry.assert_if_known(|carry| *carry == sum >> 64);
+        }
+
+        // The last round leaves A and E of the compressed state.
+        let expected = reference_compress_rounds(IV, block, ROUNDS);
+        trace[3 * ROUNDS - 2]
+            .value
+            .assert_if_known(|e_new| *e_new == expected[4] as u128);
+        trace[3 * ROUNDS - 1]
+            .value
+            .assert_if_known(|a_new| *a_new == expected[0] as u128);
+    }
+
+    #[test]
+    fn round_word_dense_from_u64() {
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let a_7 = config.compression.extras[3];
+                let a_8 = config.compression.extras[4];
+
+                layouter.assign_region(
+                    || "dense words",
+                    |mut region| {
+                        let words = [0, 1, 1 << 32, u32::MAX as u64, u64::MAX, 0x0123456789abcdef];
+                        for (row, word) in words.into_iter().enumerate() {
+                            let dense = RoundWordDense::from_u64(
+                                &mut region,
+                                row,
+                                a_7,
+                                row,
+                                a_8,
+                                Value::known(word),
+                            )?;
+                            dense.value().assert_if_known(|value| *value == word);
+                            dense.0.value_u32().assert_if_known(|lo| *lo == word as u32);
+                            dense.1.value_u32().assert_if_known(|hi| *hi == (word >> 32) as u32);
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ch() {
+        struct MyCircuit {
+            efg: [u64; 3],
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { efg: self.efg }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let compression = config.compression;
+                let [e, f, g] = self.efg;
+
+                layouter.assign_region(
+                    || "ch",
+                    |mut region| {
+                        // The halves of E, F and G take rows 0..12, before the Ch rows of
+                        // round 0.
+                        let (_, spread_e) =
+                            compression.assign_word_halves(&mut region, 0, Value::known(e))?;
+                        let (_, spread_f) =
+                            compression.assign_word_halves(&mut region, 4, Value::known(f))?;
+                        let (_, spread_g) =
+                            compression.assign_word_halves(&mut region, 8, Value::known(g))?;
+
+                        let round_idx = MainRoundIdx::from(0);
+                        let (ch_lo, ch_hi) =
+                            compression.assign_ch(&mut region, round_idx, &spread_e, &spread_f)?;
+                        let (ch_neg_lo, ch_neg_hi) = compression.assign_ch_neg(
+                            &mut region,
+                            round_idx,
+                            &spread_e,
+                            &spread_g,
+                        )?;
+
+                        let word = |lo: &AssignedBits<32>, hi: &AssignedBits<32>| {
+                            lo.value_u32()
+                                .zip(hi.value_u32())
+                                .map(|(lo, hi)| lo as u64 + ((hi as u64) << 32))
+                        };
+                        word(&ch_lo, &ch_hi)
+                            .zip(word(&ch_neg_lo, &ch_neg_hi))
+                            .assert_if_known(|(e_and_f, not_e_and_g)| {
+                                *e_and_f == e & f
+                                    && *not_e_and_g == !e & g
+                                    && e_and_f + not_e_and_g == (e & f) ^ (!e & g)
+                            });
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        /// Spreads the bits of `half`, bit `i` going to bit `2i`.
+        fn spread(half: u32) -> u64 {
+            (0..32).map(|i| (((half >> i) & 1) as u64) << (2 * i)).sum()
+        }
+
+        use rand::{Rng, SeedableRng};
+        use rand_xorshift::XorShiftRng;
+
+        let mut rng = XorShiftRng::seed_from_u64(0xc4);
+        let mut triples = vec![
+            [0, 0, 0],
+            [u64::MAX, u64::MAX, u64::MAX],
+            [0x5555_5555_5555_5555, u64::MAX, 0],
+            [0xaaaa_aaaa_aaaa_aaaa, 0, u64::MAX],
+            [0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b],
+        ];
+        triples.extend((0..5).map(|_| [rng.gen(), rng.gen(), rng.gen()]));
+
+        for efg in triples {
+            // s_ch_neg witnesses spread_e_neg per half, and checks it against the mask.
+            let e = efg[0];
+            for half in [e as u32, (e >> 32) as u32] {
+                let spread_neg_half = negate_spread(i2lebsp::<64>(spread(half).into()));
+                assert_eq!(spread(half) + lebs2ip(&spread_neg_half) as u64, MASK_EVEN_64);
+            }
+
+            let circuit = MyCircuit { efg };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn forged_ch_output() {
+        // Overwrites the dense E ∧ F output of round 0 after assign_ch has placed it,
+        // leaving its looked-up halves alone.
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let compression = config.compression;
+                let [e, f] = [0x510e527fade682d1, 0x9b05688c2b3e6c1f];
+
+                layouter.assign_region(
+                    || "forged ch",
+                    |mut region| {
+                        let (_, spread_e) =
+                            compression.assign_word_halves(&mut region, 0, Value::known(e))?;
+                        let (_, spread_f) =
+                            compression.assign_word_halves(&mut region, 4, Value::known(f))?;
+
+                        let round_idx = MainRoundIdx::from(0);
+                        let (ch_lo, _) =
+                            compression.assign_ch(&mut region, round_idx, &spread_e, &spread_f)?;
+
+                        // R_0^{odd} is the low half of E ∧ F, at row + 3 of a_3.
+                        region.assign_advice(
+                            || "forged r_0_odd_d",
+                            compression.extras[0],
+                            get_ch_row(round_idx) + 3,
+                            || ch_lo.value_u32().map(|lo| bn256::Fr::from(lo as u64 + 1)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let circuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        let failures = prover.verify().expect_err("forged Ch output was accepted");
+        assert!(failures.iter().all(|failure| match failure {
+            VerifyFailure::ConstraintNotSatisfied { constraint, .. } => {
+                constraint.to_string().contains("s_spread_outputs")
+            }
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn ch_maj_read_in_place() {
+        // s_h_prime and s_a_new read the low halves of Ch and Maj from the outputs of the
+        // s_ch and s_maj gates of their round. Overwriting either output must break the
+        // sum reading it.
+        struct MyCircuit {
+            round: usize,
+            maj: bool,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {
+                    round: self.round,
+                    maj: self.maj,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let w_halves = config.message_schedule.process(&mut layouter, input)?.halves;
+                let compression = config.compression;
+                let initial_state = compression.initialize_with_iv(&mut layouter, IV)?;
+
+                layouter.assign_region(
+                    || "compress",
+                    |mut region| {
+                        let mut state = initial_state.clone();
+                        for (idx, w_halves) in w_halves.iter().take(self.round + 1).enumerate() {
+                            let last_round = idx == self.round;
+                            state = compression.assign_round(
+                                &mut region,
+                                idx.into(),
+                                state,
+                                w_halves,
+                                last_round,
+                            )?;
+                        }
+
+                        // R_0^{odd} is at row + 3 of a_3, and no 32-bit half is 2^32.
+                        let round_idx = MainRoundIdx::from(self.round);
+                        let row = if self.maj {
+                            get_maj_row(round_idx)
+                        } else {
+                            get_ch_row(round_idx)
+                        };
+                        region.assign_advice(
+                            || "forged r_0_odd_d",
+                            compression.extras[0],
+                            row + 3,
+                            || Value::known(bn256::Fr::from(1 << 32)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        for round in [0, 37, ROUNDS - 1] {
+            for (maj, gate) in [(false, "s_h_prime"), (true, "s_a_new")] {
+                let circuit = MyCircuit { round, maj };
+                let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                    Ok(prover) => prover,
+                    Err(e) => panic!("{:?}", e),
+                };
+                let failures = prover.verify().expect_err("forged output was accepted");
+                assert!(
+                    failures.iter().any(|failure| match failure {
+                        VerifyFailure::ConstraintNotSatisfied { constraint, .. } => {
+                            constraint.to_string().contains(gate)
+                        }
+                        _ => false,
+                    }),
+                    "{} of round {} did not read the forged output",
+                    gate,
+                    round
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decompose_abcd_across_word_halves() {
+        // b_hi covers bits 31..34, so it is the piece split between word_lo and word_hi.
+        struct MyCircuit {
+            word: u64,
+            pieces_of: u64,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {
+                    word: self.word,
+                    pieces_of: self.pieces_of,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let compression = config.compression;
+
+                layouter.assign_region(
+                    || "decompose ABCD",
+                    |mut region| {
+                        let (dense, _) =
+                            compression.assign_word_halves(&mut region, 0, Value::known(self.word))?;
+                        let pieces = compression.decompose_abcd(
+                            &mut region,
+                            0,
+                            Value::known(self.pieces_of),
+                        )?;
+
+                        let word = self.word;
+                        dense.0.value_u32().assert_if_known(|lo| *lo == word as u32);
+                        dense.1.value_u32().assert_if_known(|hi| *hi == (word >> 32) as u32);
+                        pieces.b_hi.dense.value().assert_if_known(|b_hi| {
+                            lebs2ip(&b_hi.0) == (self.pieces_of >> 31) as u128 & 0b111
+                        });
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        for word in [1 << 31, 1 << 32, 0b111 << 31, 0xffff_fffe_7fff_ffff] {
+            let circuit = MyCircuit {
+                word,
+                pieces_of: word,
+            };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            prover.assert_satisfied();
+
+            // Pieces of a word differing in bit 32 do not match the halves.
+            let circuit = MyCircuit {
+                word,
+                pieces_of: word ^ (1 << 32),
+            };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn decompose_efgh_spread_weights() {
+        // The pieces of s_decompose_efgh, recombined with the weights of its
+        // spread_check, must give the spread form of the word. Bits 40 and 41 are the last
+        // bit of c_hi and the first bit of d_lo.
+        struct MyCircuit {
+            word: u64,
+            pieces_of: u64,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {
+                    word: self.word,
+                    pieces_of: self.pieces_of,
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                fn spread<const LEN: usize>(piece: &AssignedBits<LEN>) -> Value<u128> {
+                    piece.value().map(|bits| lebs2ip(&bits.0))
+                }
+
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let compression = config.compression;
+
+                layouter.assign_region(
+                    || "decompose EFGH",
+                    |mut region| {
+                        compression.assign_word_halves(&mut region, 0, Value::known(self.word))?;
+                        let pieces = compression.decompose_efgh(
+                            &mut region,
+                            0,
+                            Value::known(self.pieces_of),
+                        )?;
+
+                        // Dense offset o is spread offset 2o.
+                        let weighted = [
+                            (spread(&pieces.a.spread), 0),
+                            (spread(&pieces.b_lo.spread), 28),
+                            (spread(&pieces.b_hi.spread), 32),
+                            (spread(&pieces.c_lo.spread), 36),
+                            (spread(&pieces.c_hi.spread), 62),
+                            (spread(&pieces.d_lo.spread), 82),
+                            (spread(&pieces.d_hi.spread), 108),
+                        ];
+                        let spread_word = weighted
+                            .iter()
+                            .fold(Value::known(0u128), |acc, (piece, shift)| {
+                                acc.zip(*piece).map(|(acc, piece)| acc + (piece << shift))
+                            });
+                        let expected: u128 = (0..64)
+                            .filter(|idx| (self.pieces_of >> idx) & 1 == 1)
+                            .map(|idx| 1u128 << (2 * idx))
+                            .sum();
+                        spread_word.assert_if_known(|spread_word| *spread_word == expected);
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        for word in [
+            1 << 40,
+            1 << 41,
+            0b11 << 40,
+            0x8060_0000_c006_0000,
+            0xffff_ffff_ffff_ffff,
+        ] {
+            let circuit = MyCircuit {
+                word,
+                pieces_of: word,
+            };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            prover.assert_satisfied();
+
+            // Pieces of a word differing at the c/d boundary do not match the halves.
+            let circuit = MyCircuit {
+                word,
+                pieces_of: word ^ (1 << 41),
+            };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn decompose_abcd_tag_out_of_range() {
+        // Overwrites the tag of one looked-up piece of decompose_abcd with 4, the tag of a
+        // full 16-bit word, which the gate must reject for every piece.
+        struct MyCircuit {
+            row: usize,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { row: self.row }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let compression = config.compression;
+                let word = 0x6a09e667f3bcc908;
+
+                layouter.assign_region(
+                    || "forged tag",
+                    |mut region| {
+                        compression.assign_word_halves(&mut region, 0, Value::known(word))?;
+                        compression.decompose_abcd(&mut region, 0, Value::known(word))?;
+                        region.assign_advice(
+                            || "forged tag",
+                            compression.lookup.tag,
+                            self.row,
+                            || Value::known(bn256::Fr::from(4)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        // a_lo, a_hi and d_lo are 14-bit pieces and d_hi an 11-bit piece, on rows 0..4.
+        let checks = [
+            "range_check_tag_a_lo",
+            "range_check_tag_a_hi",
+            "range_check_tag_d_lo",
+            "range_check_tag_d_hi",
+        ];
+        for (row, check) in checks.into_iter().enumerate() {
+            let circuit = MyCircuit { row };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            let failures = prover.verify().expect_err("out-of-range tag was accepted");
+            assert!(
+                failures.iter().any(|failure| match failure {
+                    VerifyFailure::ConstraintNotSatisfied { constraint, .. } => {
+                        constraint.to_string().contains(check)
+                    }
+                    _ => false,
+                }),
+                "{} did not fail",
+                check
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_pieces() {
+        let mut pieces = AbcdVar::pieces(0x0123456789abcdef).unwrap();
+        assert_eq!(pieces.iter().map(Vec::len).sum::<usize>(), 64);
+
+        // Move a bit from b_lo to b_hi.
+        let bit = pieces[2].pop().unwrap();
+        pieces[3].insert(0, bit);
+        let err = check_pieces("AbcdVar", &AbcdVar::PIECE_BITS, pieces).unwrap_err();
+        assert_eq!(err.piece, "b_lo");
+        assert_eq!(err.to_string(), "AbcdVar piece b_lo has 2 bits, expected 3");
+        assert!(matches!(Error::from(err.clone()), Error::Synthesis));
+        assert!(matches!(
+            Sha512Error::from(err),
+            Sha512Error::DecompositionMismatch { var: "AbcdVar" }
+        ));
+    }
+
+    /// Reassembles `pieces` with the given bit offsets, checking each piece fits below the
+    /// next offset.
+    fn reassemble(pieces: &[Vec<bool>], offsets: &[usize]) -> u64 {
+        assert_eq!(pieces.len(), offsets.len());
+        let ends = offsets.iter().skip(1).copied().chain(Some(64));
+        pieces
+            .iter()
+            .zip(offsets.iter().zip(ends))
+            .fold(0, |acc, (piece, (&offset, end))| {
+                assert_eq!(piece.len(), end - offset);
+                let piece = piece
+                    .iter()
+                    .enumerate()
+                    .fold(0u64, |acc, (idx, &bit)| acc | ((bit as u64) << idx));
+                acc | (piece << offset)
+            })
+    }
+
+    #[test]
+    fn pieces_round_trip() {
+        use rand::{Rng, SeedableRng};
+        use rand_xorshift::XorShiftRng;
+
+        // The pieces (a,b,c,d) of (28,6,5,25) and (14,4,23,23) bits, with the lo/hi halves
+        // of each piece counted from the little end.
+        const ABCD_OFFSETS: [usize; 8] = [0, 14, 28, 31, 34, 36, 39, 53];
+        const EFGH_OFFSETS: [usize; 7] = [0, 14, 16, 18, 31, 41, 54];
+
+        let mut values = vec![0, u64::MAX, 0x0123456789abcdef];
+        for offset in ABCD_OFFSETS.iter().chain(EFGH_OFFSETS.iter()).filter(|&&o| o > 0) {
+            // The top bit of one piece, the bottom bit of the next, and both.
+            values.extend([1 << (offset - 1), 1 << offset, 3 << (offset - 1)]);
+            values.extend([!(1 << (offset - 1)), !(1 << offset)]);
+        }
+        let mut rng = XorShiftRng::seed_from_u64(0x5eed);
+        values.extend((0..10_000).map(|_| rng.gen::<u64>()));
+
+        for val in values {
+            let pieces = AbcdVar::pieces(val).unwrap();
+            assert_eq!(reassemble(&pieces, &ABCD_OFFSETS), val, "AbcdVar {:#x}", val);
+            let pieces = EfghVar::pieces(val).unwrap();
+            assert_eq!(reassemble(&pieces, &EFGH_OFFSETS), val, "EfghVar {:#x}", val);
+        }
+    }
+
+    #[test]
+    fn sha512_params() {
+        fn widths(pieces: &[(&str, usize)]) -> Vec<usize> {
+            pieces.iter().map(|&(_, bits)| bits).collect()
+        }
+
+        assert_eq!(Sha512Params::ABCD_CHUNKS, widths(&AbcdVar::PIECE_BITS));
+        assert_eq!(Sha512Params::EFGH_CHUNKS, widths(&EfghVar::PIECE_BITS));
+        for chunks in [Sha512Params::ABCD_CHUNKS, Sha512Params::EFGH_CHUNKS] {
+            assert_eq!(chunks.iter().sum::<usize>(), Sha512Params::WORD_BITS);
+        }
+        assert_eq!(Sha512Params::HALF_BITS, 32);
+        assert_eq!(Sha512Params::ROUNDS, ROUNDS);
+        assert_eq!(Sha512Params::IV, IV);
+        assert_eq!(Sha512Params::ROUND_CONSTANTS.len(), ROUNDS);
+    }
+
+    #[test]
+    fn round_constant_out_of_range() {
+        assert_eq!(Sha512Params::round_constant(0).unwrap(), 0x428a2f98d728ae22);
+        assert_eq!(Sha512Params::round_constant(ROUNDS - 1).unwrap(), 0x6c44198c4a475817);
+        match Sha512Params::round_constant(ROUNDS) {
+            Err(Sha512Error::RoundOutOfRange { round, rounds }) => {
+                assert_eq!((round, rounds), (ROUNDS, ROUNDS));
+            }
+            other => panic!("expected RoundOutOfRange, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn spread_halves() {
+        struct MyCircuit {}
+
+        /// Spreads the bits of `word`, bit `i` going to bit `2i`.
+        fn spread(word: u64) -> u128 {
+            (0..64).map(|i| (((word >> i) & 1) as u128) << (2 * i)).sum()
+        }
+
+        /// Checks the spread form of each word of `state` against its dense form, and
+        /// that exactly the words in `expected` carry one.
+        fn check_spread(state: &State, expected: [bool; 8]) {
+            let dense = dense_state(state.clone()).unwrap();
+            for ((spread_value, dense), expected) in
+                state.spread_values().into_iter().zip(dense.iter()).zip(expected)
+            {
+                assert_eq!(spread_value.is_some(), expected);
+                if let Some(spread_value) = spread_value {
+                    spread_value
+                        .zip(dense.value())
+                        .assert_if_known(|&(spread_value, dense)| spread_value == spread(dense));
+                }
+            }
+            for (spread_halves, dense) in state.spread_halves().into_iter().zip(dense.iter()) {
+                if let Some(spread_halves) = spread_halves {
+                    spread_halves
+                        .dense_value()
+                        .zip(dense.value())
+                        .assert_if_known(|(value, dense)| value == dense);
+                }
+            }
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let table16_chip = Table16Chip::construct(config);
+
+                let iv = table16_chip.initialization_vector(&mut layouter)?;
+                check_spread(&iv, [true, true, true, false, true, true, true, false]);
+                let values = iv.spread_values();
+                values[0].unwrap().assert_if_known(|&a| a == spread(IV[0]));
+                assert!(values[3].is_none() && values[7].is_none());
+
+                // The rounds leave the new A and E dense-only.
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let compressed = table16_chip.compress_block(&mut layouter, &iv, input)?;
+                check_spread(&compressed, [false, true, true, false, false, true, true, false]);
+
+                let state = table16_chip.add_feed_forward(&mut layouter, &iv, &compressed)?;
+                check_spread(&state, [true, true, true, false, true, true, true, false]);
+
+                assert!(State::empty_state().spread_halves().iter().all(Option::is_none));
+                Ok(())
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn into_digest_words() {
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let table16_chip = Table16Chip::construct(config);
+
+                // Test vector: "abc"
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let iv = table16_chip.initialization_vector(&mut layouter)?;
+                let compressed = table16_chip.compress_block(&mut layouter, &iv, input)?;
+                let state = table16_chip.add_feed_forward(&mut layouter, &iv, &compressed)?;
+
+                let digest = table16_chip.digest(&mut layouter, &state)?;
+                let words = state.into_digest_words()?;
+                for (idx, (word, digest_word)) in words.iter().zip(digest.iter()).enumerate() {
+                    word.value()
+                        .zip(digest_word.0)
+                        .assert_if_known(|&(word, digest_word)| {
+                            word == digest_word
+                                && word == super::compression_util::COMPRESSION_OUTPUT[idx]
+                        });
+                }
+
+                assert!(matches!(State::empty_state().into_digest_words(), Err(Error::Synthesis)));
+                Ok(())
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn assigned_bits_128() {
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let a_3 = config.compression.extras[0];
+
+                layouter.assign_region(
+                    || "u128",
+                    |mut region| {
+                        let ints = [
+                            0,
+                            0x5555_5555_5555_5555_5555_5555_5555_5555,
+                            1 << 127,
+                            u128::MAX,
+                        ];
+                        for (row, int) in ints.into_iter().enumerate() {
+                            let cell = AssignedBits::<128>::assign(
+                                &mut region,
+                                || "u128",
+                                a_3,
+                                row,
+                                Value::known(int),
+                            )?;
+                            cell.value_u128().assert_if_known(|value| *value == int);
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn state_misuse() {
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let table16_chip = Table16Chip::construct(config);
+
+                let iv = table16_chip.initialization_vector(&mut layouter)?;
+
+                // Words in the wrong position.
+                let word = |word: &Option<StateWord>| word.clone().unwrap();
+                let swapped = State::new(
+                    word(&iv.b),
+                    word(&iv.a),
+                    word(&iv.c),
+                    word(&iv.d),
+                    word(&iv.e),
+                    word(&iv.f),
+                    word(&iv.g),
+                    word(&iv.h),
+                );
+                assert!(matches!(swapped, Err(Error::Synthesis)));
+
+                // An empty state has nothing to compress.
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let empty = table16_chip.compress_block(&mut layouter, &State::default(), input);
+                assert!(matches!(empty, Err(Error::Synthesis)));
+
+                // The compressed and feed-forward states lack the decomposition of A and
+                // E that a round needs.
+                let compressed = table16_chip.compress_block(&mut layouter, &iv, input)?;
+                let state = table16_chip.add_feed_forward(&mut layouter, &iv, &compressed)?;
+                let w_halves = table16_chip.message_schedule(&mut layouter, input)?;
+                for state in [compressed, state] {
+                    let round = layouter.assign_region(
+                        || "round from an uninitialized state",
+                        |mut region| {
+                            table16_chip.assign_round(&mut region, 0, state.clone(), &w_halves[0])
+                        },
+                    );
+                    assert!(matches!(round, Err(Error::Synthesis)));
+                }
+
+                Ok(())
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn short_schedule() {
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let w_halves = config.message_schedule.process(&mut layouter, input)?.halves;
+                let initial_state = config.compression.initialize_with_iv(&mut layouter, IV)?;
+
+                // Too few words for the requested rounds is an error, not an index panic.
+                let short = config.compression.compress(
+                    &mut layouter,
+                    initial_state.clone(),
+                    &w_halves[..ROUNDS - 1],
+                    ROUNDS,
+                );
+                assert!(matches!(short, Err(Error::Synthesis)));
+
+                // Enough words for a reduced-round compression are accepted.
+                config
+                    .compression
+                    .compress(&mut layouter, initial_state, &w_halves[..20], 20)?;
+
+                Ok(())
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn tampered_iv() {
+        // A prover witnessing another state where the IV belongs must fail the copy
+        // constraints to the constant column.
+        struct MyCircuit {
+            witnessed: [u64; STATE],
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { witnessed: IV }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let compression = config.compression;
+                layouter.assign_region(
+                    || "tampered iv",
+                    |mut region| {
+                        let state = compression
+                            .initialize_words(&mut region, self.witnessed.map(Value::known))?;
+                        CompressionConfig::constrain_iv(&mut region, &state, IV)
+                    },
+                )
+            }
+        }
+
+        let circuit = MyCircuit { witnessed: IV };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+
+        for (idx, flip) in [(0, 1), (3, 1 << 40), (7, 1 << 63)] {
+            let mut witnessed = IV;
+            witnessed[idx] ^= flip;
+            let circuit = MyCircuit { witnessed };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            let failures = prover.verify().expect_err("tampered IV was accepted");
+            assert!(
+                failures
+                    .iter()
+                    .any(|failure| matches!(failure, VerifyFailure::Permutation { .. })),
+                "word {} of the IV is not bound to a constant",
+                idx
+            );
+        }
+    }
+
+    #[test]
+    fn digest_binds_all_words() {
+        // s_digest is enabled once for A to D and once for E to H. Overwriting the 64-bit
+        // word of any digest cell, in either invocation, must break the gate.
+        struct MyCircuit {
+            tampered: Option<usize>,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { tampered: None }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let compression = config.compression;
+                let a_5 = compression.message_schedule;
+                let a_8 = compression.extras[4];
+                // The (column, row) of the word of A to H in the digest region.
+                let word_cells = [
+                    (a_5, 0),
+                    (a_8, 0),
+                    (a_5, 1),
+                    (a_8, 1),
+                    (a_5, 4),
+                    (a_8, 4),
+                    (a_5, 5),
+                    (a_8, 5),
+                ];
+
+                let state = layouter.assign_region(
+                    || "initialize",
+                    |mut region| compression.initialize_words(&mut region, IV.map(Value::known)),
+                )?;
+                layouter.assign_region(
+                    || "digest",
+                    |mut region| {
+                        let digest = compression.assign_digest(&mut region, state.clone())?;
+                        if let Some(idx) = self.tampered {
+                            let (column, row) = word_cells[idx];
+                            let word = digest[idx].value_u64().map(|word| word ^ 1);
+                            AssignedBits::<64>::assign(
+                                &mut region,
+                                || "tampered",
+                                column,
+                                row,
+                                word,
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let circuit = MyCircuit { tampered: None };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+
+        for idx in 0..STATE {
+            let circuit = MyCircuit { tampered: Some(idx) };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            let failures = prover.verify().expect_err("tampered digest word was accepted");
+            assert!(
+                failures.iter().any(|failure| matches!(
+                    failure,
+                    VerifyFailure::ConstraintNotSatisfied { constraint, .. }
+                        if format!("{:?}", constraint).contains("s_digest")
+                )),
+                "word {} of the digest is not bound by s_digest",
+                idx
+            );
+        }
+    }
+}
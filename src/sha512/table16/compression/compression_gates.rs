@@ -1,18 +1,20 @@
-use super::super::{util::*, Gate};
+use super::super::Gate;
 
 use halo2_proofs::plonk::{
     Constraint, Constraints, Expression
 };
+use super::super::word_params::{Sha512Params, WordParams};
 use std::marker::PhantomData;
 use halo2_proofs::arithmetic::FieldExt;
 
-pub struct CompressionGate<F: FieldExt>(PhantomData<F>);
-
-impl<F: FieldExt> CompressionGate<F> {
-    fn ones() -> Expression<F> {
-        Expression::Constant(F::one())
-    }
+/// The compression gates, over the word size and constants of `P`.
+///
+/// The decompositions and the `Σ` gates follow the SHA-512 rotations and are only
+/// defined for [`Sha512Params`]; the rest recombine words from their halves and only
+/// depend on `P::WORD_BITS`.
+pub struct CompressionGate<F: FieldExt, P: WordParams>(PhantomData<(F, P)>);
 
+impl<F: FieldExt> CompressionGate<F, Sha512Params> {
     // Decompose `A,B,C,D` words
     // (28, 6, 5, 25)-bit chunks
     #[allow(clippy::too_many_arguments)]
@@ -280,6 +282,44 @@ impl<F: FieldExt> CompressionGate<F> {
 
         Some(("s_upper_sigma_1", s_upper_sigma_1 * check))
     }
+}
+
+impl<F: FieldExt, P: WordParams> CompressionGate<F, P> {
+    fn ones() -> Expression<F> {
+        Expression::Constant(F::one())
+    }
+
+    /// `2^(HALF_BITS / 2)`, the weight of the high quarter of a half word.
+    fn quarter_radix() -> F {
+        F::from_u128(1u128 << (P::HALF_BITS / 2))
+    }
+
+    /// `2^HALF_BITS`, the weight of the high half of a word, and of the high half of
+    /// the spread form of a half word.
+    fn half_radix() -> F {
+        F::from_u128(1u128 << P::HALF_BITS)
+    }
+
+    /// `2^WORD_BITS`, the weight of a carry, and of the high half of a spread word.
+    fn word_radix() -> F {
+        F::from_u128(1u128 << P::WORD_BITS)
+    }
+
+    /// The spread form of a half word with every bit set.
+    fn spread_half_evens() -> F {
+        F::from_u128((0..P::HALF_BITS).map(|i| 1u128 << (2 * i)).sum())
+    }
+
+    // The Ch and Maj gates below recombine the spread halves of their inputs as
+    // `lo + 2^64 * hi` rather than reading whole 128-bit spread words. Merging the halves
+    // into one cell would not reduce the cost of a round:
+    // - the outputs `p`, `q` and `m` are 16-bit lookups in a_2, one per row, so each of
+    //   these gates spans rows -1 to 6 whatever the layout of its inputs;
+    // - every (column, rotation) these gates query for their inputs is also queried by
+    //   another gate, so the set of advice queries would stay the same;
+    // - the recombination is linear and leaves every gate at degree 2.
+    // A merged word would need its own cell and a recombination gate where the halves
+    // are produced, and `s_ch_neg` would still need the halves for its negation.
 
     // First part of choice gate on (E, F, G), E ∧ F
     #[allow(clippy::too_many_arguments)]
@@ -300,14 +340,14 @@ impl<F: FieldExt> CompressionGate<F> {
     ) -> Option<(&'static str, Expression<F>)> {
         let lhs_lo = spread_e_lo + spread_f_lo;
         let lhs_hi = spread_e_hi + spread_f_hi;
-        let lhs = lhs_lo + lhs_hi * F::from_u128(1 << 64);
-
-        let spread_p0_even = spread_p0_even_lo + spread_p0_even_hi * F::from(1<<32);
-        let spread_p1_even = spread_p1_even_lo + spread_p1_even_hi * F::from(1<<32);
-        let rhs_even = spread_p0_even + spread_p1_even * F::from_u128(1 << 64);
-        let spread_p0_odd = spread_p0_odd_lo + spread_p0_odd_hi * F::from(1<<32);
-        let spread_p1_odd = spread_p1_odd_lo + spread_p1_odd_hi * F::from(1<<32);
-        let rhs_odd = spread_p0_odd + spread_p1_odd * F::from_u128(1 << 64);
+        let lhs = lhs_lo + lhs_hi * Self::word_radix();
+
+        let spread_p0_even = spread_p0_even_lo + spread_p0_even_hi * Self::half_radix();
+        let spread_p1_even = spread_p1_even_lo + spread_p1_even_hi * Self::half_radix();
+        let rhs_even = spread_p0_even + spread_p1_even * Self::word_radix();
+        let spread_p0_odd = spread_p0_odd_lo + spread_p0_odd_hi * Self::half_radix();
+        let spread_p1_odd = spread_p1_odd_lo + spread_p1_odd_hi * Self::half_radix();
+        let rhs_odd = spread_p0_odd + spread_p1_odd * Self::word_radix();
         let rhs = rhs_even + rhs_odd * F::from(2);
 
         let check = lhs + rhs * -F::one();
@@ -339,7 +379,7 @@ impl<F: FieldExt> CompressionGate<F> {
         impl Iterator<Item = (&'static str, Expression<F>)>,
     > {
         let neg_check = {
-            let evens = Self::ones() * F::from_u128(MASK_EVEN_64 as u128);
+            let evens = Self::ones() * Self::spread_half_evens();
             // evens - spread_e_lo = spread_e_neg_lo
             let lo_check = spread_e_neg_lo.clone() + spread_e_lo + (evens.clone() * (-F::one()));
             // evens - spread_e_hi = spread_e_neg_hi
@@ -352,13 +392,13 @@ impl<F: FieldExt> CompressionGate<F> {
 
         let lhs_lo = spread_e_neg_lo + spread_g_lo;
         let lhs_hi = spread_e_neg_hi + spread_g_hi;
-        let lhs = lhs_lo + lhs_hi * F::from_u128(1 << 64);
-        let spread_q0_even = spread_q0_even_lo + spread_q0_even_hi * F::from(1<<32);
-        let spread_q1_even = spread_q1_even_lo + spread_q1_even_hi * F::from(1<<32);
-        let rhs_even = spread_q0_even + spread_q1_even * F::from_u128(1 << 64);
-        let spread_q0_odd = spread_q0_odd_lo + spread_q0_odd_hi * F::from(1<<32);
-        let spread_q1_odd = spread_q1_odd_lo + spread_q1_odd_hi * F::from(1<<32);
-        let rhs_odd = spread_q0_odd + spread_q1_odd * F::from_u128(1 << 64);
+        let lhs = lhs_lo + lhs_hi * Self::word_radix();
+        let spread_q0_even = spread_q0_even_lo + spread_q0_even_hi * Self::half_radix();
+        let spread_q1_even = spread_q1_even_lo + spread_q1_even_hi * Self::half_radix();
+        let rhs_even = spread_q0_even + spread_q1_even * Self::word_radix();
+        let spread_q0_odd = spread_q0_odd_lo + spread_q0_odd_hi * Self::half_radix();
+        let spread_q1_odd = spread_q1_odd_lo + spread_q1_odd_hi * Self::half_radix();
+        let rhs_odd = spread_q0_odd + spread_q1_odd * Self::word_radix();
         let rhs = rhs_even + rhs_odd * F::from(2);
 
         Constraints::with_selector(s_ch_neg, neg_check.chain(Some(("s_ch_neg", lhs - rhs))))
@@ -383,17 +423,17 @@ impl<F: FieldExt> CompressionGate<F> {
         spread_c_lo: Expression<F>,
         spread_c_hi: Expression<F>,
     ) -> Option<(&'static str, Expression<F>)> {
-        let spread_m0_even = spread_m0_even_lo + spread_m0_even_hi * F::from(1<<32);
-        let spread_m1_even = spread_m1_even_lo + spread_m1_even_hi * F::from(1<<32);
-        let maj_even = spread_m0_even + spread_m1_even * F::from_u128(1 << 64);
-        let spread_m0_odd = spread_m0_odd_lo + spread_m0_odd_hi * F::from(1<<32);
-        let spread_m1_odd = spread_m1_odd_lo + spread_m1_odd_hi * F::from(1<<32);
-        let maj_odd = spread_m0_odd + spread_m1_odd * F::from_u128(1 << 64);
+        let spread_m0_even = spread_m0_even_lo + spread_m0_even_hi * Self::half_radix();
+        let spread_m1_even = spread_m1_even_lo + spread_m1_even_hi * Self::half_radix();
+        let maj_even = spread_m0_even + spread_m1_even * Self::word_radix();
+        let spread_m0_odd = spread_m0_odd_lo + spread_m0_odd_hi * Self::half_radix();
+        let spread_m1_odd = spread_m1_odd_lo + spread_m1_odd_hi * Self::half_radix();
+        let maj_odd = spread_m0_odd + spread_m1_odd * Self::word_radix();
         let maj = maj_even + maj_odd * F::from(2);
 
-        let a = spread_a_lo + spread_a_hi * F::from_u128(1 << 64);
-        let b = spread_b_lo + spread_b_hi * F::from_u128(1 << 64);
-        let c = spread_c_lo + spread_c_hi * F::from_u128(1 << 64);
+        let a = spread_a_lo + spread_a_hi * Self::word_radix();
+        let b = spread_b_lo + spread_b_hi * Self::word_radix();
+        let c = spread_c_lo + spread_c_hi * Self::word_radix();
         let sum = a + b + c;
 
         Some(("maj", s_maj * (sum - maj)))
@@ -422,10 +462,10 @@ impl<F: FieldExt> CompressionGate<F> {
         let lo = h_lo + ch_lo + ch_neg_lo + sigma_e_lo + k_lo + w_lo;
         let hi = h_hi + ch_hi + ch_neg_hi + sigma_e_hi + k_hi + w_hi;
 
-        let sum = lo + hi * F::from(1 << 32);
-        let h_prime = h_prime_lo + h_prime_hi * F::from(1 << 32);
+        let sum = lo + hi * Self::half_radix();
+        let h_prime = h_prime_lo + h_prime_hi * Self::half_radix();
 
-        let check = sum - (h_prime_carry * F::from_u128(1 << 64)) - h_prime;
+        let check = sum - (h_prime_carry * Self::word_radix()) - h_prime;
 
         Some(("s_h_prime", s_h_prime * check))
     }
@@ -446,10 +486,10 @@ impl<F: FieldExt> CompressionGate<F> {
     ) -> Option<(&'static str, Expression<F>)> {
         let lo = sigma_a_lo + maj_abc_lo + h_prime_lo;
         let hi = sigma_a_hi + maj_abc_hi + h_prime_hi;
-        let sum = lo + hi * F::from(1 << 32);
-        let a_new = a_new_lo + a_new_hi * F::from(1 << 32);
+        let sum = lo + hi * Self::half_radix();
+        let a_new = a_new_lo + a_new_hi * Self::half_radix();
 
-        let check = sum - (a_new_carry * F::from_u128(1 << 64)) - a_new;
+        let check = sum - (a_new_carry * Self::word_radix()) - a_new;
 
         Some(("s_a_new", s_a_new * check))
     }
@@ -468,14 +508,62 @@ impl<F: FieldExt> CompressionGate<F> {
     ) -> Option<(&'static str, Expression<F>)> {
         let lo = h_prime_lo + d_lo;
         let hi = h_prime_hi + d_hi;
-        let sum = lo + hi * F::from(1 << 32);
-        let e_new = e_new_lo + e_new_hi * F::from(1 << 32);
+        let sum = lo + hi * Self::half_radix();
+        let e_new = e_new_lo + e_new_hi * Self::half_radix();
 
-        let check = sum - (e_new_carry * F::from_u128(1 << 64)) - e_new;
+        let check = sum - (e_new_carry * Self::word_radix()) - e_new;
 
         Some(("s_e_new", s_e_new * check))
     }
 
+    // s_feed_forward to get H_i = H_{i-1} + H, with the sum range-checked through
+    // its 16-bit chunks
+    #[allow(clippy::too_many_arguments)]
+    pub fn s_feed_forward(
+        s_feed_forward: Expression<F>,
+        prev_lo: Expression<F>,
+        prev_hi: Expression<F>,
+        cur_lo: Expression<F>,
+        cur_hi: Expression<F>,
+        sum_lo: Expression<F>,
+        sum_hi: Expression<F>,
+        carry: Expression<F>,
+        spread_sum_lo: Expression<F>,
+        spread_sum_hi: Expression<F>,
+        dense_0: Expression<F>,
+        dense_1: Expression<F>,
+        dense_2: Expression<F>,
+        dense_3: Expression<F>,
+        spread_0: Expression<F>,
+        spread_1: Expression<F>,
+        spread_2: Expression<F>,
+        spread_3: Expression<F>,
+    ) -> impl IntoIterator<Item = Constraint<F>> {
+        let lo = prev_lo + cur_lo;
+        let hi = prev_hi + cur_hi;
+        let sum = lo + hi * Self::half_radix();
+        let word = sum_lo.clone() + sum_hi.clone() * Self::half_radix();
+
+        let check = sum - (carry.clone() * Self::word_radix()) - word;
+
+        let dense_lo_check = dense_0 + dense_1 * Self::quarter_radix() - sum_lo;
+        let dense_hi_check = dense_2 + dense_3 * Self::quarter_radix() - sum_hi;
+        let spread_lo_check = spread_0 + spread_1 * Self::half_radix() - spread_sum_lo;
+        let spread_hi_check = spread_2 + spread_3 * Self::half_radix() - spread_sum_hi;
+
+        Constraints::with_selector(
+            s_feed_forward,
+            [
+                ("s_feed_forward", check),
+                ("carry_range_check", Gate::range_check(carry, 0, 1)),
+                ("dense_lo_check", dense_lo_check),
+                ("dense_hi_check", dense_hi_check),
+                ("spread_lo_check", spread_lo_check),
+                ("spread_hi_check", spread_hi_check),
+            ],
+        )
+    }
+
     // s_digest on final round
     #[allow(clippy::too_many_arguments)]
     pub fn s_digest(
@@ -494,7 +582,7 @@ impl<F: FieldExt> CompressionGate<F> {
         word_3: Expression<F>,
     ) -> impl IntoIterator<Item = Constraint<F>> {
         let check_lo_hi = |lo: Expression<F>, hi: Expression<F>, word: Expression<F>| {
-            lo + hi * F::from(1 << 32) - word
+            lo + hi * Self::half_radix() - word
         };
 
         Constraints::with_selector(
@@ -507,4 +595,45 @@ impl<F: FieldExt> CompressionGate<F> {
             ],
         )
     }
-}
\ No newline at end of file
+
+    // s_digest_packed packs the eight digest words into three field elements, big-endian:
+    // (word_0, word_1, word_2), (word_3, word_4, word_5) and (word_6, word_7).
+    pub fn s_digest_packed(
+        s_digest_packed: Expression<F>,
+        words: [Expression<F>; 8],
+        packed: [Expression<F>; 3],
+    ) -> impl IntoIterator<Item = Constraint<F>> {
+        let pack = |words: &[Expression<F>]| {
+            words[1..]
+                .iter()
+                .fold(words[0].clone(), |sum, word| sum * Self::word_radix() + word.clone())
+        };
+        let [packed_0, packed_1, packed_2] = packed;
+
+        Constraints::with_selector(
+            s_digest_packed,
+            [
+                ("packed_0", pack(&words[0..3]) - packed_0),
+                ("packed_1", pack(&words[3..6]) - packed_1),
+                ("packed_2", pack(&words[6..8]) - packed_2),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{util::MASK_EVEN_64, Sha512Params};
+    use super::CompressionGate;
+    use halo2_proofs::halo2curves::bn256;
+
+    #[test]
+    fn spread_half_evens() {
+        let spread_u32_max: u64 = (0..32).map(|i| 1 << (2 * i)).sum();
+        assert_eq!(MASK_EVEN_64, spread_u32_max);
+        assert_eq!(
+            CompressionGate::<bn256::Fr, Sha512Params>::spread_half_evens(),
+            bn256::Fr::from(MASK_EVEN_64)
+        );
+    }
+}
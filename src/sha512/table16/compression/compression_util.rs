@@ -1,9 +1,10 @@
 use super::{
-    AbcdVar, CompressionConfig, EfghVar, RoundWord, RoundWordA, RoundWordDense, RoundWordE,
-    RoundWordSpread, State, UpperSigmaVar,
+    concat_spread, known_pieces, AbcdVar, CompressionConfig, EfghVar, RoundWord, RoundWordA,
+    RoundWordDense, RoundWordE, RoundWordSpread, State, UpperSigmaVar,
 };
-use crate::sha512::table16::{
-    util::*, AssignedBits, SpreadVar, SpreadWord, StateWord, Table16Assignment,
+use crate::sha512::{
+    table16::{util::*, AssignedBits, SpreadVar, SpreadWord, StateWord, Table16Assignment, STATE},
+    Sha512Error,
 };
 use halo2_proofs::{
     circuit::{Region, Value},
@@ -12,6 +13,9 @@ use halo2_proofs::{
 };
 use std::convert::TryInto;
 
+#[cfg(feature = "trace")]
+use super::trace;
+
 // Test vector 'abc'
 #[cfg(test)]
 #[allow(dead_code)]
@@ -40,6 +44,16 @@ pub const SUBREGION_MAIN_WORD: usize =
     DECOMPOSE_ABCD + SIGMA_0_ROWS + DECOMPOSE_EFGH + SIGMA_1_ROWS + CH_ROWS + MAJ_ROWS;
 pub const SUBREGION_MAIN_ROWS: usize = SUBREGION_MAIN_LEN * SUBREGION_MAIN_WORD;
 
+// Rows needed for initial subregion: E, F, G, H then A, B, C, D
+pub const SUBREGION_INITIAL_ROWS: usize = 4 * DECOMPOSE_EFGH + 3 * DECOMPOSE_ABCD + 2;
+
+// Rows needed for digest subregion
+pub const SUBREGION_DIGEST_ROWS: usize = 6;
+
+// Rows needed for feed-forward subregion
+pub const FEED_FORWARD_WORD: usize = 4;
+pub const SUBREGION_FEED_FORWARD_ROWS: usize = STATE * FEED_FORWARD_WORD;
+
 /// The initial round.
 pub struct InitialRound;
 
@@ -191,12 +205,22 @@ pub fn get_a_new_row(round_idx: MainRoundIdx) -> usize {
     get_maj_row(round_idx)
 }
 
-pub fn get_digest_abcd_row() -> usize {
-    SUBREGION_MAIN_ROWS
+/// Returns the row of the final A after `rounds` rounds, or
+/// [`Sha512Error::RoundOutOfRange`] if there are more than 80 of them.
+pub fn get_digest_abcd_row(rounds: usize) -> Result<usize, Sha512Error> {
+    if rounds > SUBREGION_MAIN_LEN {
+        return Err(Sha512Error::RoundOutOfRange {
+            round: rounds - 1,
+            rounds: SUBREGION_MAIN_LEN,
+        });
+    }
+    Ok(rounds * SUBREGION_MAIN_WORD)
 }
 
-pub fn get_digest_efgh_row() -> usize {
-    get_digest_abcd_row() + 4
+/// Returns the row of the final E after `rounds` rounds, or
+/// [`Sha512Error::RoundOutOfRange`] if there are more than 80 of them.
+pub fn get_digest_efgh_row(rounds: usize) -> Result<usize, Sha512Error> {
+    get_digest_abcd_row(rounds).map(|row| row + 4)
 }
 
 impl CompressionConfig {
@@ -213,8 +237,8 @@ impl CompressionConfig {
         // let a_5 = self.message_schedule;
         // let a_6 = self.extras[2];
 
-        let spread_pieces = val.map(AbcdVar::pieces);
-        let spread_pieces = spread_pieces.transpose_vec(8);
+        let spread_pieces = known_pieces(val.map(AbcdVar::pieces))?;
+        let spread_pieces = spread_pieces.transpose_vec(AbcdVar::PIECE_BITS.len());
 
         let a_lo = SpreadVar::with_lookup(
             region,
@@ -298,8 +322,8 @@ impl CompressionConfig {
         // let a_5 = self.message_schedule;
         // let a_6 = self.extras[2];
 
-        let spread_pieces = val.map(EfghVar::pieces);
-        let spread_pieces = spread_pieces.transpose_vec(7);
+        let spread_pieces = known_pieces(val.map(EfghVar::pieces))?;
+        let spread_pieces = spread_pieces.transpose_vec(EfghVar::PIECE_BITS.len());
         let a = SpreadVar::with_lookup(
             region,
             &self.lookup,
@@ -446,6 +470,7 @@ impl CompressionConfig {
         self.assign_sigma_outputs(
             region,
             &self.lookup,
+            self.s_spread_outputs,
             a_3,
             row,
             r_0_even,
@@ -505,6 +530,7 @@ impl CompressionConfig {
         self.assign_sigma_outputs(
             region,
             &self.lookup,
+            self.s_spread_outputs,
             a_3,
             row,
             r_0_even,
@@ -528,6 +554,7 @@ impl CompressionConfig {
         let (_even, odd) = self.assign_spread_outputs(
             region,
             &self.lookup,
+            self.s_spread_outputs,
             a_3,
             row,
             r_0_even,
@@ -543,8 +570,8 @@ impl CompressionConfig {
         &self,
         region: &mut Region<'_, bn256::Fr>,
         round_idx: MainRoundIdx,
-        spread_halves_e: RoundWordSpread,
-        spread_halves_f: RoundWordSpread,
+        spread_halves_e: &RoundWordSpread,
+        spread_halves_f: &RoundWordSpread,
     ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
@@ -589,8 +616,8 @@ impl CompressionConfig {
         &self,
         region: &mut Region<'_, bn256::Fr>,
         round_idx: MainRoundIdx,
-        spread_halves_e: RoundWordSpread,
-        spread_halves_g: RoundWordSpread,
+        spread_halves_e: &RoundWordSpread,
+        spread_halves_g: &RoundWordSpread,
     ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
         let row = get_ch_neg_row(round_idx);
 
@@ -677,6 +704,7 @@ impl CompressionConfig {
         let (_even, odd) = self.assign_spread_outputs(
             region,
             &self.lookup,
+            self.s_spread_outputs,
             a_3,
             row,
             r_0_even,
@@ -692,9 +720,9 @@ impl CompressionConfig {
         &self,
         region: &mut Region<'_, bn256::Fr>,
         round_idx: MainRoundIdx,
-        spread_halves_a: RoundWordSpread,
-        spread_halves_b: RoundWordSpread,
-        spread_halves_c: RoundWordSpread,
+        spread_halves_a: &RoundWordSpread,
+        spread_halves_b: &RoundWordSpread,
+        spread_halves_c: &RoundWordSpread,
     ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
         let a_4 = self.extras[1];
         let a_5 = self.message_schedule;
@@ -788,8 +816,7 @@ impl CompressionConfig {
         w.0.copy_advice(|| "w_lo", region, a_8, row - 1)?;
         w.1.copy_advice(|| "w_hi", region, a_8, row)?;
 
-        // Assign and copy ch
-        // ch.0.copy_advice(|| "ch_lo", region, a_9, row)?;
+        // Copy ch_hi. ch_lo is read by the gate where s_ch placed it, at row + 3 of a_3.
         ch.1.copy_advice(|| "ch_hi", region, a_6, row + 1)?;
 
         // Assign and copy ch_neg
@@ -814,7 +841,11 @@ impl CompressionConfig {
                 || "h_prime_carry",
                 a_9,
                 row + 1,
-                || h_prime_carry.map(bn256::Fr::from),
+                || {
+                    #[cfg(feature = "trace")]
+                    trace::record(round_idx.as_usize(), trace::Step::HPrime, h_prime, h_prime_carry);
+                    h_prime_carry.map(bn256::Fr::from)
+                },
             )?;
 
             let h_prime: Value<[bool; 64]> = h_prime.map(|w| i2lebsp(w.into()));
@@ -861,7 +892,11 @@ impl CompressionConfig {
             || "e_new_carry",
             a_9,
             row + 1,
-            || e_new_carry.map(bn256::Fr::from),
+            || {
+                #[cfg(feature = "trace")]
+                trace::record(round_idx.as_usize(), trace::Step::ENew, e_new, e_new_carry);
+                e_new_carry.map(bn256::Fr::from)
+            },
         )?;
 
         Ok(e_new_dense)
@@ -886,8 +921,7 @@ impl CompressionConfig {
         let a_8 = self.extras[4];
         let a_9 = self.extras[5];
 
-        // Assign and copy maj_1
-        // maj.0.copy_advice(|| "maj_1_lo", region, a_7, row)?;
+        // Copy maj_hi. maj_lo is read by the gate where s_maj placed it, at row + 3 of a_3.
         maj.1.copy_advice(|| "maj_1_hi", region, a_3, row - 1)?;
 
         // Assign and copy sigma_0
@@ -916,7 +950,11 @@ impl CompressionConfig {
             || "a_new_carry",
             a_9,
             row,
-            || a_new_carry.map(bn256::Fr::from),
+            || {
+                #[cfg(feature = "trace")]
+                trace::record(round_idx.as_usize(), trace::Step::ANew, a_new, a_new_carry);
+                a_new_carry.map(bn256::Fr::from)
+            },
         )?;
 
         Ok(a_new_dense)
@@ -931,19 +969,7 @@ impl CompressionConfig {
         hi_col: Column<Advice>,
         word: Value<u64>,
     ) -> Result<RoundWordDense, Error> {
-        let word: Value<[bool; 64]> = word.map(|w| i2lebsp(w.into()));
-
-        let lo = {
-            let lo: Value<[bool; 32]> = word.map(|w| w[..32].try_into().unwrap());
-            AssignedBits::<32>::assign_bits(region, || "lo", lo_col, lo_row, lo)?
-        };
-
-        let hi = {
-            let hi: Value<[bool; 32]> = word.map(|w| w[32..].try_into().unwrap());
-            AssignedBits::<32>::assign_bits(region, || "hi", hi_col, hi_row, hi)?
-        };
-
-        Ok((lo, hi).into())
+        RoundWordDense::from_u64(region, lo_row, lo_col, hi_row, hi_col, word)
     }
 
     fn joindense(x :&SpreadVar<16,32>,y : &SpreadVar<16,32>) -> Value<[bool; 32]> {
@@ -962,18 +988,7 @@ impl CompressionConfig {
     }
 
     fn joinspread(x :&SpreadVar<16,32>,y : &SpreadVar<16,32>) -> Value<[bool; 64]> {
-        x
-        .spread
-        .value()
-        .zip(y.spread.value())
-        .map(|(x, y)| {
-            x.iter()
-                .chain(y.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
+        concat_spread(x, y)
     }
 
     // Assign hi and lo halves for both dense and spread versions of a word
@@ -1021,48 +1036,47 @@ impl CompressionConfig {
 #[allow(clippy::many_single_char_names)]
 pub fn match_state(
     state: State,
-) -> (
-    RoundWordA,
-    RoundWord,
-    RoundWord,
-    RoundWordDense,
-    RoundWordE,
-    RoundWord,
-    RoundWord,
-    RoundWordDense,
-) {
-    let a = match state.a {
-        Some(StateWord::A(a)) => a,
-        _ => unreachable!(),
-    };
-    let b = match state.b {
-        Some(StateWord::B(b)) => b,
-        _ => unreachable!(),
-    };
-    let c = match state.c {
-        Some(StateWord::C(c)) => c,
-        _ => unreachable!(),
-    };
-    let d = match state.d {
-        Some(StateWord::D(d)) => d,
-        _ => unreachable!(),
-    };
-    let e = match state.e {
-        Some(StateWord::E(e)) => e,
-        _ => unreachable!(),
-    };
-    let f = match state.f {
-        Some(StateWord::F(f)) => f,
-        _ => unreachable!(),
-    };
-    let g = match state.g {
-        Some(StateWord::G(g)) => g,
-        _ => unreachable!(),
-    };
-    let h = match state.h {
-        Some(StateWord::H(h)) => h,
-        _ => unreachable!(),
-    };
-
-    (a, b, c, d, e, f, g, h)
-}
\ No newline at end of file
+) -> Result<
+    (
+        RoundWordA,
+        RoundWord,
+        RoundWord,
+        RoundWordDense,
+        RoundWordE,
+        RoundWord,
+        RoundWord,
+        RoundWordDense,
+    ),
+    Error,
+> {
+    match state {
+        State {
+            a: Some(StateWord::A(a)),
+            b: Some(StateWord::B(b)),
+            c: Some(StateWord::C(c)),
+            d: Some(StateWord::D(d)),
+            e: Some(StateWord::E(e)),
+            f: Some(StateWord::F(f)),
+            g: Some(StateWord::G(g)),
+            h: Some(StateWord::H(h)),
+        } => Ok((a, b, c, d, e, f, g, h)),
+        // An empty state, as `State::new` rejects misplaced words.
+        _ => Err(Error::Synthesis),
+    }
+}
+
+/// Returns the dense halves of each word of the state.
+#[allow(clippy::many_single_char_names)]
+pub fn dense_state(state: State) -> Result<[RoundWordDense; STATE], Error> {
+    let (a, b, c, d, e, f, g, h) = match_state(state)?;
+    Ok([
+        a.dense_halves,
+        b.dense_halves,
+        c.dense_halves,
+        d,
+        e.dense_halves,
+        f.dense_halves,
+        g.dense_halves,
+        h,
+    ])
+}
@@ -1,8 +1,8 @@
-use super::super::{super::DIGEST_SIZE, BlockWord, RoundWordDense};
+use super::super::{super::DIGEST_SIZE, AssignedBits, RoundWordDense};
 use super::{compression_util::*, CompressionConfig, State};
 use halo2_proofs::{
-    arithmetic::FieldExt,
-    circuit::{Region, Value},
+    arithmetic::{Field, FieldExt},
+    circuit::{AssignedCell, Region, Value},
     halo2curves::bn256,
     plonk::{Advice, Column, Error},
 };
@@ -13,7 +13,7 @@ impl CompressionConfig {
         &self,
         region: &mut Region<'_, bn256::Fr>,
         state: State,
-    ) -> Result<[BlockWord; DIGEST_SIZE], Error> {
+    ) -> Result<[AssignedBits<64>; DIGEST_SIZE], Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
         let a_5 = self.message_schedule;
@@ -21,7 +21,7 @@ impl CompressionConfig {
         let a_7 = self.extras[3];
         let a_8 = self.extras[4];
 
-        let (a, b, c, d, e, f, g, h) = match_state(state);
+        let (a, b, c, d, e, f, g, h) = match_state(state)?;
 
         let abcd_row = 0;
         self.s_digest.enable(region, abcd_row)?;
@@ -35,13 +35,7 @@ impl CompressionConfig {
         a.dense_halves
             .1
             .copy_advice(|| "a_hi", region, a_4, abcd_row)?;
-        let a = a.dense_halves.value();
-        region.assign_advice(
-            || "a",
-            a_5,
-            abcd_row,
-            || a.map(|a| bn256::Fr::from_u128(a as u128)),
-        )?;
+        let a = AssignedBits::<64>::assign(region, || "a", a_5, abcd_row, a.dense_halves.value())?;
 
         let b = self.assign_digest_word(region, abcd_row, a_6, a_7, a_8, b.dense_halves)?;
         let c = self.assign_digest_word(region, abcd_row + 1, a_3, a_4, a_5, c.dense_halves)?;
@@ -54,28 +48,55 @@ impl CompressionConfig {
         e.dense_halves
             .1
             .copy_advice(|| "e_hi", region, a_4, efgh_row)?;
-        let e = e.dense_halves.value();
-        region.assign_advice(
-            || "e",
-            a_5,
-            efgh_row,
-            || e.map(|e| bn256::Fr::from_u128(e as u128)),
-        )?;
+        let e = AssignedBits::<64>::assign(region, || "e", a_5, efgh_row, e.dense_halves.value())?;
 
         let f = self.assign_digest_word(region, efgh_row, a_6, a_7, a_8, f.dense_halves)?;
         let g = self.assign_digest_word(region, efgh_row + 1, a_3, a_4, a_5, g.dense_halves)?;
         let h = self.assign_digest_word(region, efgh_row + 1, a_6, a_7, a_8, h)?;
 
-        Ok([
-            BlockWord(a),
-            BlockWord(b),
-            BlockWord(c),
-            BlockWord(d),
-            BlockWord(e),
-            BlockWord(f),
-            BlockWord(g),
-            BlockWord(h),
-        ])
+        Ok([a, b, c, d, e, f, g, h])
+    }
+
+    /// Assigns the digest words and the three field elements they pack into, big-endian:
+    /// words `0..3` on row 0, `3..6` on row 1 and `6..8` on row 2.
+    pub fn assign_digest_packed(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        digest: &[AssignedBits<64>; DIGEST_SIZE],
+    ) -> Result<[AssignedCell<bn256::Fr, bn256::Fr>; 3], Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+
+        self.s_digest_packed.enable(region, 0)?;
+
+        let positions = [
+            (a_3, 0),
+            (a_4, 0),
+            (a_5, 0),
+            (a_3, 1),
+            (a_4, 1),
+            (a_5, 1),
+            (a_4, 2),
+            (a_5, 2),
+        ];
+        for (word, (column, row)) in digest.iter().zip(positions) {
+            word.copy_advice(|| "digest word", region, column, row)?;
+        }
+
+        let word_radix = bn256::Fr::from_u128(1 << 64);
+        let pack = |words: &[AssignedBits<64>]| {
+            words.iter().fold(Value::known(bn256::Fr::zero()), |sum, word| {
+                sum.zip(word.value_u64())
+                    .map(|(sum, word)| sum * word_radix + bn256::Fr::from(word))
+            })
+        };
+        let packed_0 = region.assign_advice(|| "packed_0", a_6, 0, || pack(&digest[0..3]))?;
+        let packed_1 = region.assign_advice(|| "packed_1", a_6, 1, || pack(&digest[3..6]))?;
+        let packed_2 = region.assign_advice(|| "packed_2", a_6, 2, || pack(&digest[6..8]))?;
+
+        Ok([packed_0, packed_1, packed_2])
     }
 
     fn assign_digest_word(
@@ -86,18 +107,10 @@ impl CompressionConfig {
         hi_col: Column<Advice>,
         word_col: Column<Advice>,
         dense_halves: RoundWordDense,
-    ) -> Result<Value<u64>, Error> {
+    ) -> Result<AssignedBits<64>, Error> {
         dense_halves.0.copy_advice(|| "lo", region, lo_col, row)?;
         dense_halves.1.copy_advice(|| "hi", region, hi_col, row)?;
 
-        let val = dense_halves.value();
-        region.assign_advice(
-            || "word",
-            word_col,
-            row,
-            || val.map(|val| bn256::Fr::from(val)),
-        )?;
-
-        Ok(val)
+        AssignedBits::<64>::assign(region, || "word", word_col, row, dense_halves.value())
     }
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+use super::super::{
+    util::*, AssignedBits, RoundWord, RoundWordA, RoundWordDense, RoundWordE, RoundWordSpread,
+    SpreadVar, SpreadWord, StateWord, Table16Assignment, STATE,
+};
+use super::{compression_util::*, CompressionConfig, State};
+use halo2_proofs::{
+    circuit::{Region, Value},
+    halo2curves::bn256,
+    plonk::Error,
+};
+use std::convert::TryInto;
+
+impl CompressionConfig {
+    /// Assigns the feed-forward, returning the new state and the spread halves of each
+    /// of its words `A` to `H`, looked up to range-check the sums.
+    #[allow(clippy::many_single_char_names)]
+    pub fn assign_feed_forward(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        prev: State,
+        compressed: State,
+    ) -> Result<(State, [RoundWordSpread; STATE]), Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+        let a_8 = self.extras[4];
+        let a_9 = self.extras[5];
+
+        let prev = dense_state(prev)?;
+        let compressed = dense_state(compressed)?;
+
+        let mut words = Vec::with_capacity(STATE);
+        for (idx, (prev, cur)) in prev.iter().zip(compressed.iter()).enumerate() {
+            let row = idx * FEED_FORWARD_WORD;
+            self.s_feed_forward.enable(region, row)?;
+
+            // Assign and copy prev, cur
+            prev.0.copy_advice(|| "prev_lo", region, a_3, row)?;
+            prev.1.copy_advice(|| "prev_hi", region, a_4, row)?;
+            cur.0.copy_advice(|| "cur_lo", region, a_5, row)?;
+            cur.1.copy_advice(|| "cur_hi", region, a_6, row)?;
+
+            // Assign sum, carry
+            let (sum, carry) = sum_with_carry(vec![
+                (prev.0.value_u32(), prev.1.value_u32()),
+                (cur.0.value_u32(), cur.1.value_u32()),
+            ]);
+            region.assign_advice(|| "carry", a_9, row, || carry.map(bn256::Fr::from))?;
+            let dense_halves = self.assign_word_halves_dense(region, row, a_7, row, a_8, sum)?;
+
+            // Look up the 16-bit chunks of the sum
+            let sum: Value<[bool; 64]> = sum.map(|w| i2lebsp(w.into()));
+            let mut chunks = Vec::with_capacity(4);
+            for i in 0..4 {
+                let chunk: Value<[bool; 16]> =
+                    sum.map(|w| w[16 * i..16 * (i + 1)].try_into().unwrap());
+                chunks.push(SpreadVar::with_lookup(
+                    region,
+                    &self.lookup,
+                    row + i,
+                    chunk.map(SpreadWord::<16, 32>::new),
+                )?);
+            }
+
+            // Assign spread halves of the sum
+            let spread_lo = <Self as Table16Assignment>::joinspread(&chunks[0], &chunks[1]);
+            let spread_lo =
+                AssignedBits::<64>::assign_bits(region, || "spread_sum_lo", a_7, row + 1, spread_lo)?;
+            let spread_hi = <Self as Table16Assignment>::joinspread(&chunks[2], &chunks[3]);
+            let spread_hi =
+                AssignedBits::<64>::assign_bits(region, || "spread_sum_hi", a_8, row + 1, spread_hi)?;
+            let spread_halves: RoundWordSpread = (spread_lo, spread_hi).into();
+
+            words.push((dense_halves, spread_halves));
+        }
+
+        let [a, b, c, d, e, f, g, h]: [(RoundWordDense, RoundWordSpread); STATE] =
+            words.try_into().unwrap();
+        let spread_halves = [&a, &b, &c, &d, &e, &f, &g, &h].map(|word| word.1.clone());
+
+        let state = State::new(
+            StateWord::A(RoundWordA {
+                pieces: None,
+                dense_halves: a.0,
+                spread_halves: Some(a.1),
+            }),
+            StateWord::B(RoundWord::new(b.0, b.1)),
+            StateWord::C(RoundWord::new(c.0, c.1)),
+            StateWord::D(d.0),
+            StateWord::E(RoundWordE {
+                pieces: None,
+                dense_halves: e.0,
+                spread_halves: Some(e.1),
+            }),
+            StateWord::F(RoundWord::new(f.0, f.1)),
+            StateWord::G(RoundWord::new(g.0, g.1)),
+            StateWord::H(h.0),
+        )?;
+        Ok((state, spread_halves))
+    }
+}
@@ -1,4 +1,4 @@
-use super::super::{RoundWord, StateWord, STATE};
+use super::super::{RoundWord, RoundWordDense, StateWord, STATE};
 use super::{compression_util::*, CompressionConfig, State};
 use halo2_proofs::{
     circuit::{Region, Value},
@@ -7,39 +7,62 @@ use halo2_proofs::{
 };
 
 impl CompressionConfig {
-    #[allow(clippy::many_single_char_names)]
     pub fn initialize_iv(
         &self,
         region: &mut Region<'_, bn256::Fr>,
         iv: [u64; STATE],
+    ) -> Result<State, Error> {
+        let state = self.initialize_words(region, iv.map(Value::known))?;
+        Self::constrain_iv(region, &state, iv)?;
+        Ok(state)
+    }
+
+    /// Binds the halves of each word of `state` to the constants of `iv`, so a prover
+    /// cannot start from another state.
+    pub(super) fn constrain_iv(
+        region: &mut Region<'_, bn256::Fr>,
+        state: &State,
+        iv: [u64; STATE],
+    ) -> Result<(), Error> {
+        for (word, halves) in iv.iter().zip(dense_state(state.clone())?.iter()) {
+            region.constrain_constant(halves.0.cell(), bn256::Fr::from(*word as u32 as u64))?;
+            region.constrain_constant(halves.1.cell(), bn256::Fr::from(word >> 32))?;
+        }
+        Ok(())
+    }
+
+    /// Decomposes the words `A` to `H` of a state that has not been assigned before.
+    #[allow(clippy::many_single_char_names)]
+    pub fn initialize_words(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        words: [Value<u64>; STATE],
     ) -> Result<State, Error> {
         let a_7 = self.extras[3];
 
         // Decompose E into (14, 4, 23, 23)-bit chunks
-        let e = self.decompose_e(region, RoundIdx::Init, Value::known(iv[4]))?;
+        let e = self.decompose_e(region, RoundIdx::Init, words[4])?;
 
         // Decompose F, G
-        let f = self.decompose_f(region, InitialRound, Value::known(iv[5]))?;
-        let g = self.decompose_g(region, InitialRound, Value::known(iv[6]))?;
+        let f = self.decompose_f(region, InitialRound, words[5])?;
+        let g = self.decompose_g(region, InitialRound, words[6])?;
 
         // Assign H
         let h_row = get_h_row(RoundIdx::Init);
-        let h =
-            self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, Value::known(iv[7]))?;
+        let h = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, words[7])?;
 
         // Decompose A into (28, 6, 5, 25)-bit chunks
-        let a = self.decompose_a(region, RoundIdx::Init, Value::known(iv[0]))?;
+        let a = self.decompose_a(region, RoundIdx::Init, words[0])?;
 
         // Decompose B, C
-        let b = self.decompose_b(region, InitialRound, Value::known(iv[1]))?;
-        let c = self.decompose_c(region, InitialRound, Value::known(iv[2]))?;
+        let b = self.decompose_b(region, InitialRound, words[1])?;
+        let c = self.decompose_c(region, InitialRound, words[2])?;
 
         // Assign D
         let d_row = get_d_row(RoundIdx::Init);
-        let d =
-            self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, Value::known(iv[3]))?;
+        let d = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, words[3])?;
 
-        Ok(State::new(
+        State::new(
             StateWord::A(a),
             StateWord::B(b),
             StateWord::C(c),
@@ -48,7 +71,7 @@ impl CompressionConfig {
             StateWord::F(f),
             StateWord::G(g),
             StateWord::H(h),
-        ))
+        )
     }
 
     #[allow(clippy::many_single_char_names)]
@@ -58,39 +81,47 @@ impl CompressionConfig {
         state: State,
     ) -> Result<State, Error> {
         let a_7 = self.extras[3];
-        let (a, b, c, d, e, f, g, h) = match_state(state);
+        let (a, b, c, d, e, f, g, h) = match_state(state)?;
 
         // Decompose E into (14, 4, 23, 23)-bit chunks
-        let e = e.dense_halves.value();
-        let e = self.decompose_e(region, RoundIdx::Init, e)?;
+        let e_prev = e.dense_halves;
+        let e = self.decompose_e(region, RoundIdx::Init, e_prev.value())?;
+        Self::constrain_dense_halves(region, &e_prev, &e.dense_halves)?;
 
         // Decompose F, G
-        let f = f.dense_halves.value();
-        let f = self.decompose_f(region, InitialRound, f)?;
-        let g = g.dense_halves.value();
-        let g = self.decompose_g(region, InitialRound, g)?;
+        let f_prev = f.dense_halves;
+        let f = self.decompose_f(region, InitialRound, f_prev.value())?;
+        Self::constrain_dense_halves(region, &f_prev, &f.dense_halves)?;
+        let g_prev = g.dense_halves;
+        let g = self.decompose_g(region, InitialRound, g_prev.value())?;
+        Self::constrain_dense_halves(region, &g_prev, &g.dense_halves)?;
 
         // Assign H
-        let h = h.value();
+        let h_prev = h;
         let h_row = get_h_row(RoundIdx::Init);
-        let h = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, h)?;
+        let h = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, h_prev.value())?;
+        Self::constrain_dense_halves(region, &h_prev, &h)?;
 
         // Decompose A into (28, 6, 5, 25)-bit chunks
-        let a = a.dense_halves.value();
-        let a = self.decompose_a(region, RoundIdx::Init, a)?;
+        let a_prev = a.dense_halves;
+        let a = self.decompose_a(region, RoundIdx::Init, a_prev.value())?;
+        Self::constrain_dense_halves(region, &a_prev, &a.dense_halves)?;
 
         // Decompose B, C
-        let b = b.dense_halves.value();
-        let b = self.decompose_b(region, InitialRound, b)?;
-        let c = c.dense_halves.value();
-        let c = self.decompose_c(region, InitialRound, c)?;
+        let b_prev = b.dense_halves;
+        let b = self.decompose_b(region, InitialRound, b_prev.value())?;
+        Self::constrain_dense_halves(region, &b_prev, &b.dense_halves)?;
+        let c_prev = c.dense_halves;
+        let c = self.decompose_c(region, InitialRound, c_prev.value())?;
+        Self::constrain_dense_halves(region, &c_prev, &c.dense_halves)?;
 
         // Assign D
-        let d = d.value();
+        let d_prev = d;
         let d_row = get_d_row(RoundIdx::Init);
-        let d = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, d)?;
+        let d = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, d_prev.value())?;
+        Self::constrain_dense_halves(region, &d_prev, &d)?;
 
-        Ok(State::new(
+        State::new(
             StateWord::A(a),
             StateWord::B(b),
             StateWord::C(c),
@@ -99,7 +130,17 @@ impl CompressionConfig {
             StateWord::F(f),
             StateWord::G(g),
             StateWord::H(h),
-        ))
+        )
+    }
+
+    /// Binds a re-assigned word to the word it was assigned from.
+    fn constrain_dense_halves(
+        region: &mut Region<'_, bn256::Fr>,
+        prev: &RoundWordDense,
+        word: &RoundWordDense,
+    ) -> Result<(), Error> {
+        region.constrain_equal(prev.0.cell(), word.0.cell())?;
+        region.constrain_equal(prev.1.cell(), word.1.cell())
     }
 
     fn decompose_b(
@@ -1,4 +1,6 @@
-use super::super::{AssignedBits, RoundWord, RoundWordA, RoundWordE, StateWord, ROUND_CONSTANTS};
+use super::super::{
+    AssignedBits, RoundWord, RoundWordA, RoundWordE, Sha512Params, StateWord, WordParams,
+};
 use super::{compression_util::*, CompressionConfig, State};
 use halo2_proofs::{circuit::Region, halo2curves::bn256, plonk::Error};
 
@@ -10,41 +12,40 @@ impl CompressionConfig {
         round_idx: MainRoundIdx,
         state: State,
         schedule_word: &(AssignedBits<32>, AssignedBits<32>),
+        last_round: bool,
     ) -> Result<State, Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
         let a_7 = self.extras[3];
         // let a_8 = self.extras[4];
 
-        let (a, b, c, d, e, f, g, h) = match_state(state);
+        let (a, b, c, d, e, f, g, h) = match_state(state)?;
+        // B, C, F and G carry the spread halves assigned when they were decomposed as A
+        // and E in earlier rounds; Maj and Ch copy those cells rather than looking the
+        // words up in the spread table again.
+
+        // A round needs the decomposition of A and E, which the feed-forward and the
+        // last round do not assign; such a state must go through `initialize_state`.
+        let (a_pieces, a_spread) = a.pieces.zip(a.spread_halves).ok_or(Error::Synthesis)?;
+        let (e_pieces, e_spread) = e.pieces.zip(e.spread_halves).ok_or(Error::Synthesis)?;
 
         // s_upper_sigma_1(E)
-        let sigma_1 = self.assign_upper_sigma_1(region, round_idx, e.pieces.clone().unwrap())?;
+        let sigma_1 = self.assign_upper_sigma_1(region, round_idx, e_pieces)?;
 
         // Ch(E, F, G)
-        let ch = self.assign_ch(
-            region,
-            round_idx,
-            e.spread_halves.clone().unwrap(),
-            f.spread_halves.clone(),
-        )?;
-        let ch_neg = self.assign_ch_neg(
-            region,
-            round_idx,
-            e.spread_halves.clone().unwrap(),
-            g.spread_halves.clone(),
-        )?;
+        let ch = self.assign_ch(region, round_idx, &e_spread, &f.spread_halves)?;
+        let ch_neg = self.assign_ch_neg(region, round_idx, &e_spread, &g.spread_halves)?;
 
         // s_upper_sigma_0(A)
-        let sigma_0 = self.assign_upper_sigma_0(region, round_idx, a.pieces.clone().unwrap())?;
+        let sigma_0 = self.assign_upper_sigma_0(region, round_idx, a_pieces)?;
 
         // Maj(A, B, C)
         let maj = self.assign_maj(
             region,
             round_idx,
-            a.spread_halves.clone().unwrap(),
-            b.spread_halves.clone(),
-            c.spread_halves.clone(),
+            &a_spread,
+            &b.spread_halves,
+            &c.spread_halves,
         )?;
 
         // H' = H + Ch(E, F, G) + s_upper_sigma_1(E) + K + W
@@ -55,7 +56,7 @@ impl CompressionConfig {
             ch,
             ch_neg,
             sigma_1,
-            ROUND_CONSTANTS[round_idx.as_usize()],
+            Sha512Params::round_constant(round_idx.as_usize())?,
             schedule_word,
         )?;
 
@@ -67,7 +68,7 @@ impl CompressionConfig {
         let a_new_dense = self.assign_a_new(region, round_idx, maj, sigma_0, h_prime)?;
         let a_new_val = a_new_dense.value();
 
-        if round_idx < 79.into() {
+        if !last_round {
             // Assign and copy A_new
             let a_new_row = get_decompose_a_row((round_idx + 1).into());
             a_new_dense
@@ -92,19 +93,19 @@ impl CompressionConfig {
             // Decompose E into (14, 4, 23, 23)-bit chunks
             let e_new = self.decompose_e(region, (round_idx + 1).into(), e_new_val)?;
 
-            Ok(State::new(
+            State::new(
                 StateWord::A(a_new),
-                StateWord::B(RoundWord::new(a.dense_halves, a.spread_halves.unwrap())),
+                StateWord::B(RoundWord::new(a.dense_halves, a_spread)),
                 StateWord::C(b),
                 StateWord::D(c.dense_halves),
                 StateWord::E(e_new),
-                StateWord::F(RoundWord::new(e.dense_halves, e.spread_halves.unwrap())),
+                StateWord::F(RoundWord::new(e.dense_halves, e_spread)),
                 StateWord::G(f),
                 StateWord::H(g.dense_halves),
-            ))
+            )
         } else {
-            let abcd_row = get_digest_abcd_row();
-            let efgh_row = get_digest_efgh_row();
+            let abcd_row = get_digest_abcd_row(round_idx.as_usize() + 1)?;
+            let efgh_row = get_digest_efgh_row(round_idx.as_usize() + 1)?;
 
             let a_final =
                 self.assign_word_halves_dense(region, abcd_row, a_3, abcd_row, a_4, a_new_val)?;
@@ -112,16 +113,16 @@ impl CompressionConfig {
             let e_final =
                 self.assign_word_halves_dense(region, efgh_row, a_3, efgh_row, a_4, e_new_val)?;
 
-            Ok(State::new(
+            State::new(
                 StateWord::A(RoundWordA::new_dense(a_final)),
-                StateWord::B(RoundWord::new(a.dense_halves, a.spread_halves.unwrap())),
+                StateWord::B(RoundWord::new(a.dense_halves, a_spread)),
                 StateWord::C(b),
                 StateWord::D(c.dense_halves),
                 StateWord::E(RoundWordE::new_dense(e_final)),
-                StateWord::F(RoundWord::new(e.dense_halves, e.spread_halves.unwrap())),
+                StateWord::F(RoundWord::new(e.dense_halves, e_spread)),
                 StateWord::G(f),
                 StateWord::H(g.dense_halves),
-            ))
+            )
         }
     }
 }
\ No newline at end of file
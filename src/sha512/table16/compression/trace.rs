@@ -0,0 +1,59 @@
+//! A record of the intermediate sums of each compression round, for debugging.
+//!
+//! With the `trace` feature, [`assign_round`](super::CompressionConfig::assign_round)
+//! appends the `H'`, `E_new` and `A_new` sums of every round to a thread-local log, as
+//! it assigns their carries. The log is a side channel: nothing in it is constrained,
+//! and the circuit is the same with or without the feature. Only synthesis that assigns
+//! witnesses, such as `MockProver::run` or proving, records anything; keygen does not.
+
+use halo2_proofs::circuit::Value;
+use std::cell::RefCell;
+
+/// The sums computed in a round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `H' = H + Ch(E, F, G) + Σ_1(E) + K + W`
+    HPrime,
+    /// `E_new = H' + D`
+    ENew,
+    /// `A_new = H' + Maj(A, B, C) + Σ_0(A)`
+    ANew,
+}
+
+/// One sum, reduced modulo `2^64`, and the carry out of it.
+#[derive(Clone, Debug)]
+pub struct RoundTrace {
+    /// The index of the round within its block, from 0.
+    pub round: usize,
+    /// Which of the sums of the round this is.
+    pub step: Step,
+    /// The sum modulo `2^64`: the 64-bit word the round assigns for `step`, widened to
+    /// `u128`.
+    pub value: Value<u128>,
+    /// The carry out of the sum, `(sum - value) / 2^64`. It is at most 5 for `H'`, whose
+    /// `Ch(E, F, G)` is added as two terms, 1 for `E_new` and 2 for `A_new`, so it fits
+    /// in 3 bits.
+    pub carry: Value<u128>,
+}
+
+thread_local! {
+    static TRACE: RefCell<Vec<RoundTrace>> = RefCell::new(Vec::new());
+}
+
+/// Returns the sums recorded on this thread since the last call, in assignment order.
+pub fn take() -> Vec<RoundTrace> {
+    TRACE.with(|trace| trace.take())
+}
+
+/// Records a sum. This is called from the value closure of the carry cell, which the
+/// floor planner runs only once per assignment.
+pub(super) fn record(round: usize, step: Step, value: Value<u64>, carry: Value<u64>) {
+    TRACE.with(|trace| {
+        trace.borrow_mut().push(RoundTrace {
+            round,
+            step,
+            value: value.map(u128::from),
+            carry: carry.map(u128::from),
+        })
+    });
+}
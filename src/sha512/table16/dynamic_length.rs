@@ -0,0 +1,511 @@
+use super::{
+    super::{BLOCK_SIZE, DIGEST_SIZE},
+    util::i2lebsp,
+    AssignedBits, DigestHalves, Gate, SpreadInputs, SpreadVar, SpreadWord,
+};
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{AssignedCell, Region, Value},
+    halo2curves::bn256,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::convert::TryInto;
+
+/// The number of bytes in a block.
+const BLOCK_BYTES: usize = BLOCK_SIZE * 8;
+/// The offset in a block of the 128-bit message length that ends the padding.
+const LENGTH_OFFSET: usize = BLOCK_BYTES - 16;
+/// The number of rows used by each byte: one for the byte and one for its range check.
+const BYTE_ROWS: usize = 2;
+
+/// The number of bytes taken by the padding of a message, at least.
+pub(super) const MIN_PADDING_BYTES: usize = 17;
+
+/// A message of private length padded into a fixed number of blocks.
+#[derive(Clone, Debug)]
+pub(super) struct PaddedMessage {
+    /// The length of the message in bytes.
+    pub length: AssignedCell<bn256::Fr, bn256::Fr>,
+    /// The `(lo, hi)` halves of every word of every block.
+    pub words: Vec<(AssignedBits<32>, AssignedBits<32>)>,
+    /// For each block, one if it is the last block of the padded message and zero
+    /// otherwise.
+    pub last: Vec<AssignedCell<bn256::Fr, bn256::Fr>>,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct DynamicLengthConfig {
+    lookup: SpreadInputs,
+    message_schedule: Column<Advice>,
+    extras: [Column<Advice>; 6],
+
+    /// Starts the padding region with an empty, active prefix.
+    s_dyn_header: Selector,
+    /// Range-checks a byte and counts the message bytes.
+    s_dyn_byte: Selector,
+    /// Checks a byte before the length field is message or padding.
+    s_dyn_body: Selector,
+    /// The first byte of the length field, which decides whether the block is the last.
+    s_dyn_length: Selector,
+    /// The other bytes of the length field.
+    s_dyn_tail: Selector,
+    /// Checks the length field of the last block holds the message length in bits.
+    s_dyn_length_check: Selector,
+    /// Checks the last block has been reached by the end of the region.
+    s_dyn_end: Selector,
+    /// Packs four bytes into a 32-bit half of a message word.
+    s_dyn_half: Selector,
+    /// Selects a word of the state after the last block, one block per row.
+    s_dyn_select: Selector,
+    /// Like `s_dyn_select`, for the first block.
+    s_dyn_select_first: Selector,
+}
+
+/// Returns zero if `byte` is a message byte, or the padding expected after a message
+/// that ends on the previous byte or earlier.
+fn padding_check(
+    byte: Expression<bn256::Fr>,
+    is_msg: Expression<bn256::Fr>,
+    is_msg_prev: Expression<bn256::Fr>,
+) -> Expression<bn256::Fr> {
+    let one = Expression::Constant(bn256::Fr::one());
+    let start = is_msg_prev - is_msg.clone();
+    (one - is_msg) * (byte - start * bn256::Fr::from(0x80u64))
+}
+
+/// Returns `lo + 2^32 * hi`.
+fn compose_word(lo: Expression<bn256::Fr>, hi: Expression<bn256::Fr>) -> Expression<bn256::Fr> {
+    lo + hi * bn256::Fr::from(1u64 << 32)
+}
+
+impl DynamicLengthConfig {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        lookup: SpreadInputs,
+        message_schedule: Column<Advice>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_dyn_header = meta.selector();
+        let s_dyn_byte = meta.selector();
+        let s_dyn_body = meta.selector();
+        let s_dyn_length = meta.selector();
+        let s_dyn_tail = meta.selector();
+        let s_dyn_length_check = meta.selector();
+        let s_dyn_end = meta.selector();
+        let s_dyn_half = meta.selector();
+        let s_dyn_select = meta.selector();
+        let s_dyn_select_first = meta.selector();
+
+        // Rename these here for ease of matching the gates to the specification.
+        let a_1 = lookup.dense;
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+        let a_5 = message_schedule;
+        let a_6 = extras[2];
+        let a_7 = extras[3];
+        let a_8 = extras[4];
+
+        let one = || Expression::Constant(bn256::Fr::one());
+        let prev_byte = Rotation(-(BYTE_ROWS as i32));
+
+        // Each byte of the padded message sits in a_1, with a flag in a_3 that is one
+        // for message bytes, and the number of message bytes so far in a_4. The flags
+        // must be ones followed by zeros, so the count ends on the message length.
+        meta.create_gate("s_dyn_header", |meta| {
+            let s_dyn_header = meta.query_selector(s_dyn_header);
+            let is_msg = meta.query_advice(a_3, Rotation::cur());
+            let count = meta.query_advice(a_4, Rotation::cur());
+            let active = meta.query_advice(a_5, Rotation::cur());
+            let last = meta.query_advice(a_6, Rotation::cur());
+
+            Constraints::with_selector(
+                s_dyn_header,
+                [
+                    ("is_msg_check", is_msg - one()),
+                    ("count_check", count),
+                    ("active_check", active - one()),
+                    ("last_check", last),
+                ],
+            )
+        });
+
+        // The byte is range-checked by looking up both it and 2^8 * byte.
+        meta.create_gate("s_dyn_byte", |meta| {
+            let s_dyn_byte = meta.query_selector(s_dyn_byte);
+            let byte = meta.query_advice(a_1, Rotation::cur());
+            let byte_shifted = meta.query_advice(a_1, Rotation::next());
+            let is_msg = meta.query_advice(a_3, Rotation::cur());
+            let is_msg_prev = meta.query_advice(a_3, prev_byte);
+            let count = meta.query_advice(a_4, Rotation::cur());
+            let count_prev = meta.query_advice(a_4, prev_byte);
+
+            // One on the first byte after the message.
+            let start = is_msg_prev - is_msg.clone();
+
+            Constraints::with_selector(
+                s_dyn_byte,
+                [
+                    (
+                        "byte_check",
+                        byte_shifted - byte * bn256::Fr::from(1u64 << 8),
+                    ),
+                    ("is_msg_range_check", Gate::range_check(is_msg.clone(), 0, 1)),
+                    ("start_range_check", Gate::range_check(start, 0, 1)),
+                    ("count_check", count - count_prev - is_msg),
+                ],
+            )
+        });
+
+        // Outside the message, a byte is 0x80 right after the message and zero
+        // otherwise. This is only checked in active blocks, up to the last one; the
+        // blocks after it are ignored and may hold anything.
+        //
+        // a_5 is one in the blocks up to and including the last one, and a_6 is one
+        // in the length field of the last block. A block is active if the previous
+        // one is active but not the last.
+        meta.create_gate("s_dyn_body", |meta| {
+            let s_dyn_body = meta.query_selector(s_dyn_body);
+            let active = meta.query_advice(a_5, Rotation::cur());
+            let active_prev = meta.query_advice(a_5, prev_byte);
+            let last = meta.query_advice(a_6, Rotation::cur());
+            let last_prev = meta.query_advice(a_6, prev_byte);
+            let padding_check = padding_check(
+                meta.query_advice(a_1, Rotation::cur()),
+                meta.query_advice(a_3, Rotation::cur()),
+                meta.query_advice(a_3, prev_byte),
+            );
+
+            Constraints::with_selector(
+                s_dyn_body,
+                [
+                    ("active_check", active.clone() - (active_prev - last_prev)),
+                    ("last_check", last),
+                    ("padding_check", active * padding_check),
+                ],
+            )
+        });
+
+        // The block is the last one if the message ends before its length field. The
+        // length field accumulates in a_7, most significant byte first.
+        meta.create_gate("s_dyn_length", |meta| {
+            let s_dyn_length = meta.query_selector(s_dyn_length);
+            let byte = meta.query_advice(a_1, Rotation::cur());
+            let is_msg_prev = meta.query_advice(a_3, prev_byte);
+            let active = meta.query_advice(a_5, Rotation::cur());
+            let active_prev = meta.query_advice(a_5, prev_byte);
+            let last = meta.query_advice(a_6, Rotation::cur());
+            let length = meta.query_advice(a_7, Rotation::cur());
+            let padding_check = padding_check(
+                byte.clone(),
+                meta.query_advice(a_3, Rotation::cur()),
+                is_msg_prev.clone(),
+            );
+
+            Constraints::with_selector(
+                s_dyn_length,
+                [
+                    ("active_check", active.clone() - active_prev),
+                    ("last_check", last.clone() - (active.clone() - is_msg_prev)),
+                    ("length_check", length - byte),
+                    ("padding_check", (active - last) * padding_check),
+                ],
+            )
+        });
+
+        meta.create_gate("s_dyn_tail", |meta| {
+            let s_dyn_tail = meta.query_selector(s_dyn_tail);
+            let byte = meta.query_advice(a_1, Rotation::cur());
+            let active = meta.query_advice(a_5, Rotation::cur());
+            let active_prev = meta.query_advice(a_5, prev_byte);
+            let last = meta.query_advice(a_6, Rotation::cur());
+            let last_prev = meta.query_advice(a_6, prev_byte);
+            let length = meta.query_advice(a_7, Rotation::cur());
+            let length_prev = meta.query_advice(a_7, prev_byte);
+            let padding_check = padding_check(
+                byte.clone(),
+                meta.query_advice(a_3, Rotation::cur()),
+                meta.query_advice(a_3, prev_byte),
+            );
+
+            Constraints::with_selector(
+                s_dyn_tail,
+                [
+                    ("active_check", active.clone() - active_prev),
+                    ("last_check", last.clone() - last_prev),
+                    (
+                        "length_check",
+                        length - (length_prev * bn256::Fr::from(1u64 << 8) + byte),
+                    ),
+                    ("padding_check", (active - last) * padding_check),
+                ],
+            )
+        });
+
+        // The length field is 16 range-checked bytes, so it cannot wrap around.
+        meta.create_gate("s_dyn_length_check", |meta| {
+            let s_dyn_length_check = meta.query_selector(s_dyn_length_check);
+            let count = meta.query_advice(a_4, Rotation::cur());
+            let last = meta.query_advice(a_6, Rotation::cur());
+            let length = meta.query_advice(a_7, Rotation::cur());
+
+            Constraints::with_selector(
+                s_dyn_length_check,
+                [("length_check", last * (length - count * bn256::Fr::from(8u64)))],
+            )
+        });
+
+        // Some block must be the last, otherwise the message overflows the region.
+        meta.create_gate("s_dyn_end", |meta| {
+            let s_dyn_end = meta.query_selector(s_dyn_end);
+            let active = meta.query_advice(a_5, Rotation::cur());
+            let last = meta.query_advice(a_6, Rotation::cur());
+
+            Constraints::with_selector(s_dyn_end, [("end_check", active - last)])
+        });
+
+        // half = 2^24 * b_0 + 2^16 * b_1 + 2^8 * b_2 + b_3, on the row of b_3.
+        meta.create_gate("s_dyn_half", |meta| {
+            let s_dyn_half = meta.query_selector(s_dyn_half);
+            let half = meta.query_advice(a_8, Rotation::cur());
+            let mut composed = Expression::Constant(bn256::Fr::zero());
+            for idx in 0..4 {
+                let byte = meta.query_advice(a_1, Rotation(-((BYTE_ROWS * (3 - idx)) as i32)));
+                composed = composed * bn256::Fr::from(1u64 << 8) + byte;
+            }
+
+            Constraints::with_selector(s_dyn_half, [("half_check", half - composed)])
+        });
+
+        // selected = selected_prev + last * (lo + 2^32 * hi)
+        meta.create_gate("s_dyn_select_first", |meta| {
+            let s_dyn_select_first = meta.query_selector(s_dyn_select_first);
+            let last = meta.query_advice(a_3, Rotation::cur());
+            let lo = meta.query_advice(a_4, Rotation::cur());
+            let hi = meta.query_advice(a_5, Rotation::cur());
+            let selected = meta.query_advice(a_6, Rotation::cur());
+            let select_check = selected - last * compose_word(lo, hi);
+
+            Constraints::with_selector(s_dyn_select_first, [("select_check", select_check)])
+        });
+
+        meta.create_gate("s_dyn_select", |meta| {
+            let s_dyn_select = meta.query_selector(s_dyn_select);
+            let last = meta.query_advice(a_3, Rotation::cur());
+            let lo = meta.query_advice(a_4, Rotation::cur());
+            let hi = meta.query_advice(a_5, Rotation::cur());
+            let selected = meta.query_advice(a_6, Rotation::cur());
+            let selected_prev = meta.query_advice(a_6, Rotation::prev());
+            let select_check = selected - selected_prev - last * compose_word(lo, hi);
+
+            Constraints::with_selector(s_dyn_select, [("select_check", select_check)])
+        });
+
+        DynamicLengthConfig {
+            lookup,
+            message_schedule,
+            extras,
+            s_dyn_header,
+            s_dyn_byte,
+            s_dyn_body,
+            s_dyn_length,
+            s_dyn_tail,
+            s_dyn_length_check,
+            s_dyn_end,
+            s_dyn_half,
+            s_dyn_select,
+            s_dyn_select_first,
+        }
+    }
+
+    /// Pads `message` into `max_blocks` blocks, filling the blocks after the last one
+    /// with zeros, and checks the padding against the message length.
+    ///
+    /// `message` must be at most `max_blocks * 128 - 17` bytes long.
+    pub(super) fn assign_padding(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        max_blocks: usize,
+        message: Value<&Vec<u8>>,
+    ) -> Result<PaddedMessage, Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+        let a_8 = self.extras[4];
+
+        let length = message.map(|message| message.len());
+        let num_blocks = length.map(|length| (length + MIN_PADDING_BYTES + BLOCK_BYTES - 1) / BLOCK_BYTES);
+        let padded = message.map(|message| {
+            let mut padded = message.clone();
+            padded.push(0x80);
+            while padded.len() % BLOCK_BYTES != LENGTH_OFFSET {
+                padded.push(0);
+            }
+            padded.extend_from_slice(&(message.len() as u128 * 8).to_be_bytes());
+            padded.resize(max_blocks * BLOCK_BYTES, 0);
+            padded
+        });
+        let flag = |flag: Value<bool>| flag.map(|flag| bn256::Fr::from(flag as u64));
+
+        self.s_dyn_header.enable(region, 0)?;
+        region.assign_advice(|| "is_msg", a_3, 0, || Value::known(bn256::Fr::one()))?;
+        region.assign_advice(|| "count", a_4, 0, || Value::known(bn256::Fr::zero()))?;
+        region.assign_advice(|| "active", a_5, 0, || Value::known(bn256::Fr::one()))?;
+        region.assign_advice(|| "last", a_6, 0, || Value::known(bn256::Fr::zero()))?;
+
+        let mut words = Vec::with_capacity(max_blocks * BLOCK_SIZE);
+        let mut last = Vec::with_capacity(max_blocks);
+        let mut count = None;
+        let mut hi = None;
+        let mut length_field = Value::known(0u128);
+        for idx in 0..max_blocks * BLOCK_BYTES {
+            let row = BYTE_ROWS * (idx + 1);
+            let block = idx / BLOCK_BYTES;
+            let offset = idx % BLOCK_BYTES;
+            let byte = padded.as_ref().map(|padded| padded[idx]);
+
+            self.s_dyn_byte.enable(region, row)?;
+            SpreadVar::with_lookup(
+                region,
+                &self.lookup,
+                row,
+                byte.map(|byte| SpreadWord::<16, 32>::new(i2lebsp(byte.into()))),
+            )?;
+            SpreadVar::with_lookup(
+                region,
+                &self.lookup,
+                row + 1,
+                byte.map(|byte| SpreadWord::<16, 32>::new(i2lebsp((byte as u128) << 8))),
+            )?;
+            region.assign_advice(|| "is_msg", a_3, row, || flag(length.map(|length| idx < length)))?;
+            let count_cell = region.assign_advice(
+                || "count",
+                a_4,
+                row,
+                || length.map(|length| bn256::Fr::from(length.min(idx + 1) as u64)),
+            )?;
+            region.assign_advice(|| "active", a_5, row, || flag(num_blocks.map(|n| block < n)))?;
+            let is_last = if offset < LENGTH_OFFSET {
+                Value::known(false)
+            } else {
+                num_blocks.map(|n| block + 1 == n)
+            };
+            let last_cell = region.assign_advice(|| "last", a_6, row, || flag(is_last))?;
+
+            if offset < LENGTH_OFFSET {
+                self.s_dyn_body.enable(region, row)?;
+            } else {
+                if offset == LENGTH_OFFSET {
+                    self.s_dyn_length.enable(region, row)?;
+                    last.push(last_cell);
+                    length_field = Value::known(0);
+                } else {
+                    self.s_dyn_tail.enable(region, row)?;
+                }
+                length_field = length_field
+                    .zip(byte)
+                    .map(|(length_field, byte)| (length_field << 8) + byte as u128);
+                region.assign_advice(
+                    || "length",
+                    a_7,
+                    row,
+                    || length_field.map(bn256::Fr::from_u128),
+                )?;
+            }
+
+            if offset == BLOCK_BYTES - 1 {
+                self.s_dyn_length_check.enable(region, row)?;
+                if block + 1 == max_blocks {
+                    self.s_dyn_end.enable(region, row)?;
+                    count = Some(count_cell);
+                }
+            }
+
+            // Each word is its high half followed by its low half.
+            if idx % 4 == 3 {
+                self.s_dyn_half.enable(region, row)?;
+                let half = padded.as_ref().map(|padded| {
+                    u32::from_be_bytes(padded[idx - 3..=idx].try_into().expect("4 bytes"))
+                });
+                let half = AssignedBits::<32>::assign(region, || "half", a_8, row, half)?;
+                match hi.take() {
+                    None => hi = Some(half),
+                    Some(hi) => words.push((half, hi)),
+                }
+            }
+        }
+
+        #[cfg(feature = "secrets")]
+        {
+            use zeroize::Zeroize;
+            let mut padded = padded;
+            padded.as_mut().map(|padded| padded.zeroize());
+        }
+
+        Ok(PaddedMessage {
+            length: count.expect("max_blocks > 0"),
+            words,
+            last,
+        })
+    }
+
+    /// Selects the state after the last block from `states`, the states after each
+    /// block, using the flags of [`PaddedMessage::last`].
+    pub(super) fn assign_select(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        last: &[AssignedCell<bn256::Fr, bn256::Fr>],
+        states: &[DigestHalves],
+    ) -> Result<[AssignedBits<64>; DIGEST_SIZE], Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+
+        let mut digest = Vec::with_capacity(DIGEST_SIZE);
+        for word_idx in 0..DIGEST_SIZE {
+            let mut selected = Value::known(0u64);
+            let mut selected_cell = None;
+            for (block, (last, state)) in last.iter().zip(states.iter()).enumerate() {
+                let row = word_idx * states.len() + block;
+                if block == 0 {
+                    self.s_dyn_select_first.enable(region, row)?;
+                } else {
+                    self.s_dyn_select.enable(region, row)?;
+                }
+
+                let (lo, hi) = &state[word_idx];
+                last.copy_advice(|| "last", region, a_3, row)?;
+                lo.copy_advice(|| "lo", region, a_4, row)?;
+                hi.copy_advice(|| "hi", region, a_5, row)?;
+
+                let word = lo
+                    .value_u32()
+                    .zip(hi.value_u32())
+                    .map(|(lo, hi)| lo as u64 + (1 << 32) * hi as u64);
+                selected = selected
+                    .zip(last.value())
+                    .zip(word)
+                    .map(|((selected, last), word)| {
+                        if *last == bn256::Fr::one() {
+                            selected.wrapping_add(word)
+                        } else {
+                            selected
+                        }
+                    });
+                selected_cell = Some(AssignedBits::<64>::assign(
+                    region,
+                    || "selected",
+                    a_6,
+                    row,
+                    selected,
+                )?);
+            }
+            digest.push(selected_cell.expect("states is not empty"));
+        }
+
+        Ok(digest.try_into().unwrap())
+    }
+}
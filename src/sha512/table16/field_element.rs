@@ -0,0 +1,324 @@
+use super::{util::i2lebsp, AssignedBits, Gate, SpreadInputs, SpreadVar, SpreadWord};
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{AssignedCell, Region},
+    halo2curves::bn256,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+/// The upper 128 bits of `p - 1`, where `p` is the modulus of [`bn256::Fr`].
+const MODULUS_MINUS_ONE_HI: u128 = 0x30644e72e131a029b85045b68181585d;
+/// The lower 128 bits of `p - 1`, where `p` is the modulus of [`bn256::Fr`].
+const MODULUS_MINUS_ONE_LO: u128 = 0x2833e84879b9709143e1f593f0000000;
+
+/// The number of 32-bit halves in the 256-bit encoding of a field element.
+const HALVES: usize = 8;
+
+/// The four 64-bit words of the 256-bit big-endian encoding of a field element, most
+/// significant word first, as `(lo, hi)` pairs of 32-bit halves.
+pub(super) type ElementWords = [(AssignedBits<32>, AssignedBits<32>); HALVES / 2];
+
+#[derive(Clone, Debug)]
+pub(super) struct FieldElementConfig {
+    lookup: SpreadInputs,
+    message_schedule: Column<Advice>,
+    extras: [Column<Advice>; 6],
+
+    /// Decomposes a 32-bit half into two 16-bit chunks on this row and the next.
+    s_field_half: Selector,
+    /// Recombines the halves into the field element and checks they encode it
+    /// canonically, that is, as an integer smaller than the modulus.
+    s_field_element: Selector,
+}
+
+/// Returns `terms[0] + 2^bits * terms[1] + 2^(2 * bits) * terms[2] + ...`.
+fn compose(terms: &[Expression<bn256::Fr>], bits: usize) -> Expression<bn256::Fr> {
+    let radix = bn256::Fr::from_u128(1 << bits);
+    let mut coeff = bn256::Fr::one();
+    let mut sum = Expression::Constant(bn256::Fr::zero());
+    for term in terms {
+        sum = sum + term.clone() * coeff;
+        coeff *= radix;
+    }
+    sum
+}
+
+/// Returns $2^{128}$.
+fn two_pow_128() -> bn256::Fr {
+    bn256::Fr::from_u128(u128::MAX) + bn256::Fr::one()
+}
+
+impl FieldElementConfig {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        lookup: SpreadInputs,
+        message_schedule: Column<Advice>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_field_half = meta.selector();
+        let s_field_element = meta.selector();
+
+        // Rename these here for ease of matching the gates to the specification.
+        let a_1 = lookup.dense;
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+        let a_5 = message_schedule;
+
+        // half = chunk_lo + 2^16 * chunk_hi, with both chunks looked up in the
+        // spread table.
+        meta.create_gate("s_field_half", |meta| {
+            let s_field_half = meta.query_selector(s_field_half);
+            let half = meta.query_advice(a_3, Rotation::cur());
+            let chunk_lo = meta.query_advice(a_1, Rotation::cur());
+            let chunk_hi = meta.query_advice(a_1, Rotation::next());
+
+            Constraints::with_selector(
+                s_field_half,
+                [("half_check", half - compose(&[chunk_lo, chunk_hi], 16))],
+            )
+        });
+
+        // element = lo + 2^128 * hi, where lo and hi are 128-bit limbs of the halves.
+        // The encoding is canonical if (hi, lo) <= p - 1, which holds if and only if
+        // there is a borrow bit such that
+        //   t_lo = (p - 1)_lo - lo + 2^128 * borrow
+        //   t_hi = (p - 1)_hi - hi - borrow
+        // both fit in 128 bits.
+        meta.create_gate("s_field_element", |meta| {
+            let s_field_element = meta.query_selector(s_field_element);
+            let halves: Vec<_> = (0..2 * HALVES)
+                .map(|idx| meta.query_advice(a_3, Rotation(2 * idx as i32)))
+                .collect();
+            let element = meta.query_advice(a_4, Rotation::cur());
+            let borrow = meta.query_advice(a_5, Rotation::cur());
+
+            let lo = compose(&halves[..HALVES / 2], 32);
+            let hi = compose(&halves[HALVES / 2..HALVES], 32);
+            let t_lo = compose(&halves[HALVES..HALVES * 3 / 2], 32);
+            let t_hi = compose(&halves[HALVES * 3 / 2..], 32);
+
+            let element_check = element - compose(&halves[..HALVES], 32);
+            let lo_check = t_lo
+                - (Expression::Constant(bn256::Fr::from_u128(MODULUS_MINUS_ONE_LO)) - lo
+                    + borrow.clone() * two_pow_128());
+            let hi_check = t_hi
+                - (Expression::Constant(bn256::Fr::from_u128(MODULUS_MINUS_ONE_HI))
+                    - hi
+                    - borrow.clone());
+
+            Constraints::with_selector(
+                s_field_element,
+                [
+                    ("element_check", element_check),
+                    ("borrow_range_check", Gate::range_check(borrow, 0, 1)),
+                    ("lo_check", lo_check),
+                    ("hi_check", hi_check),
+                ],
+            )
+        });
+
+        FieldElementConfig {
+            lookup,
+            message_schedule,
+            extras,
+            s_field_half,
+            s_field_element,
+        }
+    }
+
+    /// Decomposes `element` into the four 64-bit words of its 256-bit big-endian
+    /// encoding, copy-constraining the decomposition to `element`.
+    pub(super) fn assign_element(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        element: &AssignedCell<bn256::Fr, bn256::Fr>,
+    ) -> Result<ElementWords, Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+
+        let limbs = element.value().map(|element| {
+            let lo = element.get_lower_128();
+            let hi = ((*element - bn256::Fr::from_u128(lo)) * two_pow_128().invert().unwrap())
+                .get_lower_128();
+            (lo, hi)
+        });
+        let borrow = limbs.map(|(lo, _)| lo > MODULUS_MINUS_ONE_LO);
+        let t_limbs = limbs.zip(borrow).map(|((lo, hi), borrow)| {
+            (
+                MODULUS_MINUS_ONE_LO.wrapping_sub(lo),
+                MODULUS_MINUS_ONE_HI - hi - borrow as u128,
+            )
+        });
+
+        self.s_field_element.enable(region, 0)?;
+        element.copy_advice(|| "element", region, a_4, 0)?;
+        region.assign_advice(
+            || "borrow",
+            a_5,
+            0,
+            || borrow.map(|borrow| bn256::Fr::from(borrow as u64)),
+        )?;
+
+        // The halves of (lo, hi, t_lo, t_hi), least significant first, on even rows.
+        let mut halves = Vec::with_capacity(2 * HALVES);
+        for idx in 0..2 * HALVES {
+            let limb = if idx < HALVES { limbs } else { t_limbs };
+            let shift = 32 * (idx % HALVES);
+            let half = limb.map(|(lo, hi)| {
+                if shift < 128 {
+                    (lo >> shift) as u32
+                } else {
+                    (hi >> (shift - 128)) as u32
+                }
+            });
+
+            let row = 2 * idx;
+            self.s_field_half.enable(region, row)?;
+            for (offset, chunk) in [half.map(|half| half as u16), half.map(|half| (half >> 16) as u16)]
+                .into_iter()
+                .enumerate()
+            {
+                let chunk = chunk.map(|chunk| SpreadWord::<16, 32>::new(i2lebsp(chunk.into())));
+                SpreadVar::with_lookup(region, &self.lookup, row + offset, chunk)?;
+            }
+            halves.push(AssignedBits::<32>::assign(region, || "half", a_3, row, half)?);
+        }
+
+        // Words of the big-endian encoding, most significant first.
+        Ok([
+            (halves[6].clone(), halves[7].clone()),
+            (halves[4].clone(), halves[5].clone()),
+            (halves[2].clone(), halves[3].clone()),
+            (halves[0].clone(), halves[1].clone()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        super::{ShaGadget, BLOCK_SIZE},
+        BlockWord, InputCells, InputWord, Sha512Instructions, Table16Chip, Table16Config,
+        ROUNDS,
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{MockProver, VerifyFailure},
+        halo2curves::bn256,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use std::convert::TryInto;
+
+    #[test]
+    fn absorb_and_squeeze_binds_padding() {
+        struct MyCircuit {
+            /// Whether to witness another length word than the padding constant.
+            tamper: bool,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = (Table16Config, Column<Advice>);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { tamper: self.tamper }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                let elements = meta.advice_column();
+                meta.enable_equality(elements);
+                (Table16Chip::configure(meta), elements)
+            }
+
+            fn synthesize(
+                &self,
+                (config, column): Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let chip = Table16Chip::construct(config.clone());
+
+                let elements = layouter.assign_region(
+                    || "elements",
+                    |mut region| {
+                        [1u64, 2]
+                            .iter()
+                            .enumerate()
+                            .map(|(row, element)| {
+                                region.assign_advice(
+                                    || "element",
+                                    column,
+                                    row,
+                                    || Value::known(bn256::Fr::from(*element)),
+                                )
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    },
+                )?;
+                if !self.tamper {
+                    chip.absorb_and_squeeze(&mut layouter, &elements)?;
+                    return Ok(());
+                }
+
+                // Lays out `absorb_and_squeeze` as a prover grinding the challenge would,
+                // witnessing another padding in the message schedule.
+                let mut words = Vec::new();
+                for element in elements.iter() {
+                    let halves = layouter.assign_region(
+                        || "decompose field element",
+                        |mut region| config.field_element.assign_element(&mut region, element),
+                    )?;
+                    words.extend(halves.into_iter().map(|(lo, hi)| InputWord::Halves(lo, hi)));
+                }
+                let padding = ShaGadget::padding(&chip, &mut layouter, words.len() * 8)?;
+                words.extend(padding.into_iter().map(InputWord::Word));
+                let mut input: Vec<_> =
+                    words.iter().map(|word| BlockWord(word.value_u64())).collect();
+                let length = input.len() - 1;
+                input[length] = BlockWord(input[length].0.map(|word| word ^ 1));
+
+                let mut state = chip.initialization_vector(&mut layouter)?;
+                let blocks = words.chunks_exact(BLOCK_SIZE);
+                for (block, input) in blocks.zip(input.chunks_exact(BLOCK_SIZE)) {
+                    let initialized = config
+                        .compression
+                        .initialize_with_state(&mut layouter, state.clone())?;
+                    let w_halves = config
+                        .message_schedule
+                        .process_block(
+                            &mut layouter,
+                            input.try_into().unwrap(),
+                            Some(InputCells::Mixed(block.try_into().unwrap())),
+                        )?
+                        .halves;
+                    let compressed =
+                        config
+                            .compression
+                            .compress(&mut layouter, initialized, &w_halves, ROUNDS)?;
+                    state = chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+                }
+                chip.digest_words(&mut layouter, &state, 4)?;
+                Ok(())
+            }
+        }
+
+        let circuit = MyCircuit { tamper: false };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+
+        let circuit = MyCircuit { tamper: true };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        let failures = prover.verify().expect_err("tampered padding was accepted");
+        assert!(failures
+            .iter()
+            .any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+}
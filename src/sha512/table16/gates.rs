@@ -1,5 +1,5 @@
 use halo2_proofs::arithmetic::FieldExt;
-use halo2_proofs::plonk::Expression;
+use halo2_proofs::plonk::{Constraints, Expression};
 
 pub struct Gate<F: FieldExt>(pub Expression<F>);
 
@@ -122,4 +122,26 @@ impl<F: FieldExt> Gate<F> {
                 three_bit_spread(dense, spread),
             )))
     }
+
+    /// Ties each 32-bit dense output of a spread gate to its two looked-up 16-bit halves.
+    ///
+    /// `halves` and `words` are ordered R_0^{even}, R_0^{odd}, R_1^{even}, R_1^{odd}.
+    pub fn s_spread_outputs(
+        s_spread_outputs: Expression<F>,
+        halves: [(Expression<F>, Expression<F>); 4],
+        words: [Expression<F>; 4],
+    ) -> Constraints<
+        F,
+        (&'static str, Expression<F>),
+        impl Iterator<Item = (&'static str, Expression<F>)>,
+    > {
+        let names = ["r_0_even", "r_0_odd", "r_1_even", "r_1_odd"];
+        let checks = names
+            .into_iter()
+            .zip(halves)
+            .zip(words)
+            .map(|((name, (lo, hi)), word)| (name, lo + hi * F::from(1 << 16) - word));
+
+        Constraints::with_selector(s_spread_outputs, checks)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,106 @@
+use std::convert::TryInto;
+
+use super::{BlockWord, Sha512Instructions, State, Table16Chip, Table16Config};
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    halo2curves::bn256,
+    plonk::Error,
+};
+
+/// The size of a SHA-512 block, in bytes.
+const BLOCK_BYTES: usize = super::super::BLOCK_SIZE * 8;
+
+/// A byte-oriented SHA-512 hasher over [`Table16Chip`], which keeps the running
+/// [`State`] and the bytes of the current partial block between calls to
+/// [`Sha512Hasher::update`], and pads the message in [`Sha512Hasher::finalize`].
+///
+/// Unlike [`Sha512`](super::super::Sha512), which takes pre-padded 64-bit words, the
+/// message may be split at any byte, and the digest is that of the concatenation of
+/// every update.
+#[derive(Debug)]
+pub struct Sha512Hasher {
+    chip: Table16Chip,
+    /// The chaining value, or `None` until the IV is assigned with the first block.
+    state: Option<State>,
+    /// The bytes of the current block, always fewer than [`BLOCK_BYTES`].
+    buffer: Vec<u8>,
+    /// The number of bytes hashed so far.
+    length: u128,
+}
+
+impl Sha512Hasher {
+    /// Creates a hasher for the empty message. Nothing is assigned until a block is
+    /// complete or the hasher is finalized.
+    pub fn new(config: Table16Config) -> Self {
+        Sha512Hasher {
+            chip: Table16Chip::construct(config),
+            state: None,
+            buffer: Vec::with_capacity(BLOCK_BYTES),
+            length: 0,
+        }
+    }
+
+    /// Appends `data` to the message, compressing every block it completes.
+    pub fn update(
+        &mut self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        self.length += data.len() as u128;
+        self.buffer.extend_from_slice(data);
+
+        let full = self.buffer.len() - self.buffer.len() % BLOCK_BYTES;
+        let blocks: Vec<u8> = self.buffer.drain(..full).collect();
+        for block in blocks.chunks_exact(BLOCK_BYTES) {
+            self.compress(layouter, block)?;
+        }
+        Ok(())
+    }
+
+    /// Pads the message, compresses the remaining one or two blocks, and returns the
+    /// digest of every byte passed to [`Sha512Hasher::update`].
+    pub fn finalize(
+        mut self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+    ) -> Result<[BlockWord; super::super::DIGEST_SIZE], Error> {
+        let mut padded = std::mem::take(&mut self.buffer);
+        padded.push(0x80);
+        while padded.len() % BLOCK_BYTES != BLOCK_BYTES - 16 {
+            padded.push(0);
+        }
+        padded.extend_from_slice(&(self.length * 8).to_be_bytes());
+
+        for block in padded.chunks_exact(BLOCK_BYTES) {
+            self.compress(layouter, block)?;
+        }
+        let state = self.state.as_ref().expect("padding fills at least one block");
+        self.chip.digest(layouter, state)
+    }
+
+    /// Compresses one block of [`BLOCK_BYTES`] bytes into the running state, assigning the
+    /// IV first if this is the first block.
+    fn compress(
+        &mut self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        block: &[u8],
+    ) -> Result<(), Error> {
+        let prev = match self.state.take() {
+            Some(state) => state,
+            None => self.chip.initialization_vector(layouter)?,
+        };
+
+        let words: Vec<BlockWord> = block
+            .chunks_exact(8)
+            .map(|word| {
+                BlockWord(Value::known(u64::from_be_bytes(
+                    word.try_into().expect("word.len() == 8"),
+                )))
+            })
+            .collect();
+        let input = words[..].try_into().expect("block.len() == BLOCK_BYTES");
+
+        let compressed = self.chip.compress_block(layouter, &prev, input)?;
+        self.state = Some(self.chip.add_feed_forward(layouter, &prev, &compressed)?);
+        Ok(())
+    }
+}
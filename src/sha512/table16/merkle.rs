@@ -0,0 +1,297 @@
+use super::{
+    super::{BLOCK_SIZE, DIGEST_SIZE},
+    AssignedBits, BlockWord, Gate,
+};
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Region, Value},
+    halo2curves::bn256,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+use std::convert::TryInto;
+
+/// The 32-bit halves of the eight words of a digest, as `(lo, hi)` pairs.
+pub(super) type DigestHalves = [(AssignedBits<32>, AssignedBits<32>); DIGEST_SIZE];
+
+#[derive(Clone, Debug)]
+pub(super) struct MerkleConfig {
+    message_schedule: Column<Advice>,
+    extras: [Column<Advice>; 6],
+
+    /// Orders a node and its sibling, one 64-bit word per row.
+    s_swap: Selector,
+    /// Selects one of two digests, one 64-bit word per row.
+    s_select: Selector,
+}
+
+impl MerkleConfig {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<bn256::Fr>,
+        message_schedule: Column<Advice>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_swap = meta.selector();
+        let s_select = meta.selector();
+
+        // Rename these here for ease of matching the gates to the specification.
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+        let a_5 = message_schedule;
+        let a_6 = extras[2];
+        let a_7 = extras[3];
+
+        // (left, right) = swap ? (sibling, cur) : (cur, sibling)
+        meta.create_gate("s_swap", |meta| {
+            let s_swap = meta.query_selector(s_swap);
+            let cur = meta.query_advice(a_3, Rotation::cur());
+            let sibling = meta.query_advice(a_4, Rotation::cur());
+            let swap = meta.query_advice(a_5, Rotation::cur());
+            let left = meta.query_advice(a_6, Rotation::cur());
+            let right = meta.query_advice(a_7, Rotation::cur());
+
+            let left_check = left - cur.clone() - swap.clone() * (sibling.clone() - cur.clone());
+            let right_check = right - sibling.clone() - swap.clone() * (cur - sibling);
+
+            Constraints::with_selector(
+                s_swap,
+                [
+                    ("swap_range_check", Gate::range_check(swap, 0, 1)),
+                    ("left_check", left_check),
+                    ("right_check", right_check),
+                ],
+            )
+        });
+
+        // selected = cond ? a : b
+        meta.create_gate("s_select", |meta| {
+            let s_select = meta.query_selector(s_select);
+            let a = meta.query_advice(a_3, Rotation::cur());
+            let b = meta.query_advice(a_4, Rotation::cur());
+            let cond = meta.query_advice(a_5, Rotation::cur());
+            let selected = meta.query_advice(a_6, Rotation::cur());
+
+            let select_check = selected - b.clone() - cond.clone() * (a - b);
+
+            Constraints::with_selector(
+                s_select,
+                [
+                    ("cond_range_check", Gate::range_check(cond, 0, 1)),
+                    ("select_check", select_check),
+                ],
+            )
+        });
+
+        MerkleConfig {
+            message_schedule,
+            extras,
+            s_swap,
+            s_select,
+        }
+    }
+
+    /// Orders the current node `cur` and its `sibling` into the message block
+    /// `left || right` hashed at the next level. The sibling goes on the left if
+    /// `sibling_is_left`.
+    pub(super) fn assign_swap(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        cur: &[AssignedBits<64>; DIGEST_SIZE],
+        sibling: [BlockWord; DIGEST_SIZE],
+        sibling_is_left: Value<bool>,
+    ) -> Result<[AssignedBits<64>; BLOCK_SIZE], Error> {
+        let mut swap = None;
+        let mut left = Vec::with_capacity(DIGEST_SIZE);
+        let mut right = Vec::with_capacity(DIGEST_SIZE);
+        for (row, (cur, sibling)) in cur.iter().zip(sibling.iter()).enumerate() {
+            let (left_word, right_word) =
+                self.assign_swap_word(region, row, cur, sibling.0, sibling_is_left, &mut swap)?;
+            left.push(left_word);
+            right.push(right_word);
+        }
+
+        left.extend(right);
+        Ok(left.try_into().unwrap())
+    }
+
+    /// Selects the words of `a` if `cond` is one, or those of `b` if it is zero. The
+    /// gate rejects any other `cond`.
+    pub(super) fn assign_select(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        cond: &AssignedCell<bn256::Fr, bn256::Fr>,
+        a: &[AssignedBits<64>; DIGEST_SIZE],
+        b: &[AssignedBits<64>; DIGEST_SIZE],
+    ) -> Result<[AssignedBits<64>; DIGEST_SIZE], Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+
+        let mut selected = Vec::with_capacity(DIGEST_SIZE);
+        for (row, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+            self.s_select.enable(region, row)?;
+
+            a.copy_advice(|| "a", region, a_3, row)?;
+            b.copy_advice(|| "b", region, a_4, row)?;
+            cond.copy_advice(|| "cond", region, a_5, row)?;
+
+            let word = a
+                .value_u64()
+                .zip(b.value_u64())
+                .zip(cond.value())
+                .map(|((a, b), cond)| if *cond == bn256::Fr::one() { a } else { b });
+            selected.push(AssignedBits::<64>::assign(region, || "selected", a_6, row, word)?);
+        }
+
+        Ok(selected.try_into().unwrap())
+    }
+
+    fn assign_swap_word(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        row: usize,
+        cur: &AssignedBits<64>,
+        sibling: Value<u64>,
+        sibling_is_left: Value<bool>,
+        swap: &mut Option<AssignedCell<bn256::Fr, bn256::Fr>>,
+    ) -> Result<(AssignedBits<64>, AssignedBits<64>), Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+
+        self.s_swap.enable(region, row)?;
+
+        cur.copy_advice(|| "cur", region, a_3, row)?;
+        AssignedBits::<64>::assign(region, || "sibling", a_4, row, sibling)?;
+
+        // Every word of a node is swapped alike.
+        let swap_cell = region.assign_advice(
+            || "swap",
+            a_5,
+            row,
+            || sibling_is_left.map(|swap| bn256::Fr::from(swap as u64)),
+        )?;
+        match swap {
+            Some(swap) => region.constrain_equal(swap.cell(), swap_cell.cell())?,
+            None => *swap = Some(swap_cell),
+        }
+
+        let (left, right) = cur
+            .value_u64()
+            .zip(sibling)
+            .zip(sibling_is_left)
+            .map(|((cur, sibling), swap)| if swap { (sibling, cur) } else { (cur, sibling) })
+            .unzip();
+        let left = AssignedBits::<64>::assign(region, || "left", a_6, row, left)?;
+        let right = AssignedBits::<64>::assign(region, || "right", a_7, row, right)?;
+
+        Ok((left, right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        super::{ShaGadget, BLOCK_SIZE},
+        BlockWord, InputCells, InputWord, Sha512Instructions, Table16Chip, Table16Config,
+        ROUNDS,
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        halo2curves::bn256,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use std::convert::TryInto;
+
+    #[test]
+    fn hash_two_binds_padding() {
+        struct MyCircuit {
+            /// Whether to witness another first padding word than the `0x80` constant.
+            tamper: bool,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { tamper: self.tamper }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let chip = Table16Chip::construct(config.clone());
+
+                let left = chip.hash_constant(&mut layouter, b"left")?;
+                let right = chip.hash_constant(&mut layouter, b"right")?;
+                if !self.tamper {
+                    chip.hash_two(&mut layouter, &left, &right)?;
+                    return Ok(());
+                }
+
+                // Lays out `hash_two` as a prover choosing the padding would, witnessing
+                // another padding block in the message schedule.
+                let mut words: Vec<_> =
+                    left.iter().chain(right.iter()).cloned().map(InputWord::Word).collect();
+                let padding = ShaGadget::padding(&chip, &mut layouter, words.len() * 8)?;
+                words.extend(padding.into_iter().map(InputWord::Word));
+                let mut input: Vec<_> =
+                    words.iter().map(|word| BlockWord(word.value_u64())).collect();
+                input[BLOCK_SIZE] = BlockWord(input[BLOCK_SIZE].0.map(|word| word ^ 1));
+
+                let mut state = chip.initialization_vector(&mut layouter)?;
+                let blocks = words.chunks_exact(BLOCK_SIZE);
+                for (block, input) in blocks.zip(input.chunks_exact(BLOCK_SIZE)) {
+                    let initialized = config
+                        .compression
+                        .initialize_with_state(&mut layouter, state.clone())?;
+                    let w_halves = config
+                        .message_schedule
+                        .process_block(
+                            &mut layouter,
+                            input.try_into().unwrap(),
+                            Some(InputCells::Mixed(block.try_into().unwrap())),
+                        )?
+                        .halves;
+                    let compressed =
+                        config
+                            .compression
+                            .compress(&mut layouter, initialized, &w_halves, ROUNDS)?;
+                    state = chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+                }
+                chip.digest_cells(&mut layouter, &state)?;
+                Ok(())
+            }
+        }
+
+        let k = Table16Config::minimum_k(2);
+        let circuit = MyCircuit { tamper: false };
+        let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+
+        let circuit = MyCircuit { tamper: true };
+        let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        let failures = prover.verify().expect_err("tampered padding was accepted");
+        assert!(failures
+            .iter()
+            .any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+}
@@ -1,8 +1,11 @@
 use std::convert::TryInto;
 
-use super::{super::BLOCK_SIZE, AssignedBits, BlockWord, SpreadInputs, Table16Assignment, ROUNDS};
+use super::{
+    super::BLOCK_SIZE, configure_spread_outputs, AssignedBits, BlockWord, SpreadInputs,
+    Table16Assignment, ROUNDS,
+};
 use halo2_proofs::{
-    circuit::Layouter,
+    circuit::{Layouter, Value},
     halo2curves::bn256,
     plonk::{Advice, Column, ConstraintSystem, Error, Selector},
     poly::Rotation,
@@ -16,6 +19,7 @@ mod subregion3;
 
 use schedule_gates::ScheduleGate;
 use schedule_util::*;
+pub(super) use schedule_util::{INPUT_RANGE_ROWS, SCHEDULE_ROWS};
 
 #[cfg(test)]
 pub use schedule_util::msg_schedule_test_input;
@@ -31,6 +35,50 @@ impl std::ops::Deref for MessageWord {
     }
 }
 
+/// The message schedule `W_[0..80]` of a block, as returned by
+/// [`MessageScheduleConfig::process`]. `W_[0..16]` are the words of the block.
+#[derive(Clone, Debug)]
+pub(super) struct MessageSchedule {
+    /// Each word as a 64-bit cell.
+    pub(super) dense: [MessageWord; ROUNDS],
+    /// The `(lo, hi)` 32-bit halves of each word, the form the compression rounds
+    /// consume.
+    pub(super) halves: [(AssignedBits<32>, AssignedBits<32>); ROUNDS],
+}
+
+/// Cells assigned elsewhere in the circuit that `W_[0..16]` are copy-constrained to.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum InputCells<'a> {
+    /// The 64-bit words.
+    Words(&'a [AssignedBits<64>; BLOCK_SIZE]),
+    /// The `(lo, hi)` 32-bit halves of the words.
+    Halves(&'a [(AssignedBits<32>, AssignedBits<32>); BLOCK_SIZE]),
+    /// Each word in either form.
+    Mixed(&'a [InputWord; BLOCK_SIZE]),
+}
+
+/// A word of a block assigned elsewhere in the circuit, in either of the forms
+/// `W_[0..16]` can be copy-constrained to.
+#[derive(Clone, Debug)]
+pub(super) enum InputWord {
+    /// A 64-bit word.
+    Word(AssignedBits<64>),
+    /// The `(lo, hi)` 32-bit halves of a word.
+    Halves(AssignedBits<32>, AssignedBits<32>),
+}
+
+impl InputWord {
+    pub(super) fn value_u64(&self) -> Value<u64> {
+        match self {
+            InputWord::Word(word) => word.value_u64(),
+            InputWord::Halves(lo, hi) => lo
+                .value_u32()
+                .zip(hi.value_u32())
+                .map(|(lo, hi)| lo as u64 + ((hi as u64) << 32)),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) struct MessageScheduleConfig {
     lookup: SpreadInputs,
@@ -55,6 +103,10 @@ pub(super) struct MessageScheduleConfig {
     s_lower_sigma_0_v2: Selector,
     /// sigma_1_v2 gate for W_[14..65]
     s_lower_sigma_1_v2: Selector,
+    /// Range check for the halves of W_[0..16]
+    s_input_range: Selector,
+    /// Ties the dense outputs of the sigma gates to their lookups
+    s_spread_outputs: Selector,
 }
 
 impl Table16Assignment for MessageScheduleConfig {}
@@ -86,6 +138,7 @@ impl MessageScheduleConfig {
         let s_lower_sigma_1 = meta.selector();
         let s_lower_sigma_0_v2 = meta.selector();
         let s_lower_sigma_1_v2 = meta.selector();
+        let s_input_range = meta.selector();
 
         // Rename these here for ease of matching the gates to the specification.
         let a_0 = lookup.tag;
@@ -99,6 +152,8 @@ impl MessageScheduleConfig {
         let a_8 = extras[4];
         let a_9 = extras[5];
 
+        let s_spread_outputs = configure_spread_outputs(meta, &lookup, a_3);
+
         // s_word for W_[16..80]
         meta.create_gate("s_word for W_[16..80]", |meta| {
             let s_word = meta.query_selector(s_word);
@@ -143,6 +198,17 @@ impl MessageScheduleConfig {
             ScheduleGate::s_decompose_0(s_decompose_0, lo, hi, word)
         });
 
+        // s_input_range for W_[0..16]
+        // (16, 16)-bit chunks of each 32-bit half
+        meta.create_gate("s_input_range", |meta| {
+            let s_input_range = meta.query_selector(s_input_range);
+            let half = meta.query_advice(a_3, Rotation::cur());
+            let chunk_lo = meta.query_advice(a_1, Rotation::cur());
+            let chunk_hi = meta.query_advice(a_1, Rotation::next());
+
+            ScheduleGate::s_input_range(s_input_range, half, chunk_lo, chunk_hi)
+        });
+
         // s_decompose_1 for W_[1..14]
         // (1, 6, 1, 56)-bit chunks
         meta.create_gate("s_decompose_1", |meta| {
@@ -350,21 +416,75 @@ impl MessageScheduleConfig {
             s_lower_sigma_1,
             s_lower_sigma_0_v2,
             s_lower_sigma_1_v2,
+            s_input_range,
+            s_spread_outputs,
         }
     }
 
-    #[allow(clippy::type_complexity)]
+    /// Expands `input` into its message schedule `W_[0..80]`.
     pub(super) fn process(
         &self,
         layouter: &mut impl Layouter<bn256::Fr>,
         input: [BlockWord; BLOCK_SIZE],
-    ) -> Result<
-        (
-            [MessageWord; ROUNDS],
-            [(AssignedBits<32>, AssignedBits<32>); ROUNDS],
-        ),
-        Error,
-    > {
+    ) -> Result<MessageSchedule, Error> {
+        self.process_block(layouter, input, None)
+    }
+
+    /// Like [`MessageScheduleConfig::process`], but takes the block from cells assigned
+    /// elsewhere in the circuit, and copy-constrains them to `W_[0..16]`.
+    pub(super) fn process_from_cells(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        words: &[AssignedBits<64>; BLOCK_SIZE],
+    ) -> Result<MessageSchedule, Error> {
+        let mut input = [BlockWord::default(); BLOCK_SIZE];
+        for (input, word) in input.iter_mut().zip(words.iter()) {
+            *input = BlockWord(word.value_u64());
+        }
+        self.process_block(layouter, input, Some(InputCells::Words(words)))
+    }
+
+    /// Like [`MessageScheduleConfig::process_from_cells`], but takes the `(lo, hi)`
+    /// 32-bit halves of the words, as the schedule itself stores them, and
+    /// copy-constrains them to the halves of `W_[0..16]`.
+    pub(super) fn process_from_halves(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        halves: &[(AssignedBits<32>, AssignedBits<32>); BLOCK_SIZE],
+    ) -> Result<MessageSchedule, Error> {
+        let mut input = [BlockWord::default(); BLOCK_SIZE];
+        for (input, (lo, hi)) in input.iter_mut().zip(halves.iter()) {
+            *input = BlockWord(
+                lo.value_u32()
+                    .zip(hi.value_u32())
+                    .map(|(lo, hi)| lo as u64 + ((hi as u64) << 32)),
+            );
+        }
+        self.process_block(layouter, input, Some(InputCells::Halves(halves)))
+    }
+
+    /// Like [`MessageScheduleConfig::process_from_cells`], but each word of the block may
+    /// be given as a 64-bit cell or as its 32-bit halves, such as message words held as
+    /// halves followed by padding words assigned as constants.
+    pub(super) fn process_from_input_words(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        words: &[InputWord; BLOCK_SIZE],
+    ) -> Result<MessageSchedule, Error> {
+        let mut input = [BlockWord::default(); BLOCK_SIZE];
+        for (input, word) in input.iter_mut().zip(words.iter()) {
+            *input = BlockWord(word.value_u64());
+        }
+        self.process_block(layouter, input, Some(InputCells::Mixed(words)))
+    }
+
+    /// Expands `input`, copy-constraining `W_[0..16]` to `cells` if given.
+    pub(super) fn process_block(
+        &self,
+        layouter: &mut impl Layouter<bn256::Fr>,
+        input: [BlockWord; BLOCK_SIZE],
+        cells: Option<InputCells<'_>>,
+    ) -> Result<MessageSchedule, Error> {
         let mut w = Vec::<MessageWord>::with_capacity(ROUNDS);
         let mut w_halves = Vec::<(AssignedBits<32>, AssignedBits<32>)>::with_capacity(ROUNDS);
 
@@ -411,6 +531,25 @@ impl MessageScheduleConfig {
                 // Assign W[0..16]
                 for (i, word) in input.iter().enumerate() {
                     let (word, halves) = self.assign_word_and_halves(&mut region, word.0, i)?;
+                    match cells {
+                        Some(InputCells::Words(words)) => {
+                            region.constrain_equal(words[i].cell(), word.cell())?;
+                        }
+                        Some(InputCells::Halves(cells)) => {
+                            region.constrain_equal(cells[i].0.cell(), halves.0.cell())?;
+                            region.constrain_equal(cells[i].1.cell(), halves.1.cell())?;
+                        }
+                        Some(InputCells::Mixed(words)) => match &words[i] {
+                            InputWord::Word(cell) => {
+                                region.constrain_equal(cell.cell(), word.cell())?;
+                            }
+                            InputWord::Halves(lo, hi) => {
+                                region.constrain_equal(lo.cell(), halves.0.cell())?;
+                                region.constrain_equal(hi.cell(), halves.1.cell())?;
+                            }
+                        },
+                        None => {}
+                    }
                     w.push(MessageWord(word));
                     w_halves.push(halves);
                 }
@@ -440,19 +579,31 @@ impl MessageScheduleConfig {
             },
         )?;
 
-        Ok((w.try_into().unwrap(), w_halves.try_into().unwrap()))
+        // The halves of W_[0..16] are otherwise only bound by s_decompose_0, which
+        // does not constrain them to 32 bits.
+        layouter.assign_region(
+            || "range check message block",
+            |mut region| self.assign_input_range(&mut region, &w_halves[..BLOCK_SIZE]),
+        )?;
+
+        Ok(MessageSchedule {
+            dense: w.try_into().unwrap(),
+            halves: w_halves.try_into().unwrap(),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::{
-        super::BLOCK_SIZE, util::lebs2ip, BlockWord, SpreadTableChip, Table16Chip, Table16Config,
+        super::BLOCK_SIZE,
+        util::{lebs2ip, reference_message_schedule},
+        AssignedBits, BlockWord, SpreadTableChip, Table16Chip, Table16Config, Visibility,
     };
-    use super::schedule_util::*;
+    use super::{schedule_util::*, InputCells, InputWord, MessageSchedule};
     use halo2_proofs::{
-        circuit::{Layouter, SimpleFloorPlanner},
-        dev::MockProver,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{MockProver, VerifyFailure},
         halo2curves::bn256,
         plonk::{Circuit, ConstraintSystem, Error},
     };
@@ -486,7 +637,7 @@ mod tests {
                 let inputs: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
 
                 // Run message_scheduler to get W_[0..80]
-                let (w, _) = config.message_schedule.process(&mut layouter, inputs)?;
+                let w = config.message_schedule.process(&mut layouter, inputs)?.dense;
             
                 for (word, test_word) in w.iter().zip(MSG_SCHEDULE_TEST_OUTPUT.iter()) {
                     word.value().assert_if_known(|bits| {
@@ -506,4 +657,476 @@ mod tests {
         };
         prover.assert_satisfied();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn message_schedule_block_words() {
+        // W_[0..16] are the words of the block, in both forms of the schedule.
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
+
+                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+                let MessageSchedule { dense, halves } =
+                    config.message_schedule.process(&mut layouter, input)?;
+
+                for ((word, (lo, hi)), input) in dense.iter().zip(halves.iter()).zip(input.iter())
+                {
+                    word.value_u64()
+                        .zip(input.0)
+                        .assert_if_known(|(word, input)| word == input);
+                    lo.value_u32()
+                        .zip(hi.value_u32())
+                        .zip(input.0)
+                        .assert_if_known(|((lo, hi), input)| {
+                            *lo as u64 + ((*hi as u64) << 32) == *input
+                        });
+                }
+                Ok(())
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn message_schedule_expansion() {
+        struct MyCircuit {
+            block: [u64; BLOCK_SIZE],
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { block: self.block }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                Table16Chip::load(config.clone(), &mut layouter)?;
+                let table16_chip = Table16Chip::construct(config);
+
+                let input = self.block.map(|word| BlockWord(Value::known(word)));
+
+                let w_halves = table16_chip.message_schedule(&mut layouter, input)?;
+
+                // W_[16..80] are produced by the σ_0 and σ_1 gates.
+                let expected = reference_message_schedule(self.block);
+                for (idx, (lo, hi)) in w_halves.iter().enumerate().skip(BLOCK_SIZE) {
+                    lo.value_u32()
+                        .zip(hi.value_u32())
+                        .assert_if_known(|&(lo, hi)| {
+                            (lo as u64) + ((hi as u64) << 32) == expected[idx]
+                        });
+                }
+                Ok(())
+            }
+        }
+
+        // Test vector: "abc"
+        let mut block = [0; BLOCK_SIZE];
+        block[0] = 0x6162638000000000;
+        block[BLOCK_SIZE - 1] = 24;
+        assert_eq!(reference_message_schedule(block), MSG_SCHEDULE_TEST_OUTPUT);
+
+        let circuit: MyCircuit = MyCircuit { block };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn input_range_rejects_wide_half() {
+        // A `BlockWord` cannot hold a value of 2^64 or more, so inject the high half
+        // of such a word directly: 2^32 = 0 + 2^16 * 2^16 satisfies s_input_range,
+        // but the chunk 2^16 is not in the spread table.
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
+
+                let schedule = config.message_schedule;
+                let lookup = schedule.lookup.clone();
+                let a_3 = schedule.extras[0];
+
+                layouter.assign_region(
+                    || "wide half",
+                    |mut region| {
+                        schedule.s_input_range.enable(&mut region, 0)?;
+                        region.assign_advice(
+                            || "half",
+                            a_3,
+                            0,
+                            || Value::known(bn256::Fr::from(1u64 << 32)),
+                        )?;
+                        for (row, chunk) in [0u64, 1 << 16].into_iter().enumerate() {
+                            region.assign_advice(
+                                || "tag",
+                                lookup.tag,
+                                row,
+                                || Value::known(bn256::Fr::from(4u64)),
+                            )?;
+                            region.assign_advice(
+                                || "dense",
+                                lookup.dense,
+                                row,
+                                || Value::known(bn256::Fr::from(chunk)),
+                            )?;
+                            region.assign_advice(
+                                || "spread",
+                                lookup.spread,
+                                row,
+                                || Value::known(bn256::Fr::from(0u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn process_from_cells() {
+        struct MyCircuit {
+            /// Flips the low bit of this word in the upstream cells only.
+            tamper: Option<usize>,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { tamper: self.tamper }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
+
+                // Test vector: "abc"
+                let mut block = [0; BLOCK_SIZE];
+                block[0] = 0x6162638000000000;
+                block[BLOCK_SIZE - 1] = 24;
+
+                let schedule = config.message_schedule;
+                let mut upstream = block;
+                if let Some(idx) = self.tamper {
+                    upstream[idx] ^= 1;
+                }
+                let cells = layouter.assign_region(
+                    || "upstream words",
+                    |mut region| {
+                        let mut cells = Vec::with_capacity(BLOCK_SIZE);
+                        for (row, word) in upstream.iter().enumerate() {
+                            cells.push(AssignedBits::<64>::assign(
+                                &mut region,
+                                || "upstream word",
+                                schedule.message_schedule,
+                                row,
+                                Value::known(*word),
+                            )?);
+                        }
+                        Ok(cells)
+                    },
+                )?;
+                let cells: [AssignedBits<64>; BLOCK_SIZE] = cells.try_into().unwrap();
+
+                let w = if self.tamper.is_none() {
+                    schedule.process_from_cells(&mut layouter, &cells)?.dense
+                } else {
+                    // Witness the honest block, as a prover ignoring the cells would.
+                    let input = block.map(|word| BlockWord(Value::known(word)));
+                    schedule
+                        .process_block(&mut layouter, input, Some(InputCells::Words(&cells)))?
+                        .dense
+                };
+
+                for (word, test_word) in w.iter().zip(MSG_SCHEDULE_TEST_OUTPUT.iter()) {
+                    word.value()
+                        .assert_if_known(|bits| lebs2ip(bits) as u64 == *test_word);
+                }
+                Ok(())
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit { tamper: None };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+
+        // The schedule no longer matches an upstream cell, so the copy constraint fails.
+        let circuit: MyCircuit = MyCircuit { tamper: Some(3) };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn process_from_input_words() {
+        // Two message words held as halves, then the padding assigned as constants.
+        const MESSAGE: [u64; 2] = [0x0123456789abcdef, 0xfedcba9876543210];
+
+        struct MyCircuit {
+            /// Flips the low bit of this padding word in the witnessed schedule only.
+            tamper: Option<usize>,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { tamper: self.tamper }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
+
+                let mut block = [0; BLOCK_SIZE];
+                block[..2].copy_from_slice(&MESSAGE);
+                block[2] = 1 << 63;
+                block[BLOCK_SIZE - 1] = 128;
+
+                let schedule = config.message_schedule;
+                let halves = layouter.assign_region(
+                    || "message halves",
+                    |mut region| {
+                        let mut halves = Vec::with_capacity(MESSAGE.len());
+                        for (row, word) in MESSAGE.iter().enumerate() {
+                            let lo = AssignedBits::<32>::assign(
+                                &mut region,
+                                || "message lo",
+                                schedule.extras[0],
+                                row,
+                                Value::known(*word as u32),
+                            )?;
+                            let hi = AssignedBits::<32>::assign(
+                                &mut region,
+                                || "message hi",
+                                schedule.extras[1],
+                                row,
+                                Value::known((*word >> 32) as u32),
+                            )?;
+                            halves.push(InputWord::Halves(lo, hi));
+                        }
+                        Ok(halves)
+                    },
+                )?;
+                let padding: Vec<_> = block[MESSAGE.len()..]
+                    .iter()
+                    .map(|word| (BlockWord(Value::known(*word)), Visibility::Public))
+                    .collect();
+                let padding = config.compression.assign_message_words(&mut layouter, &padding)?;
+                let words: Vec<InputWord> =
+                    halves.into_iter().chain(padding.into_iter().map(InputWord::Word)).collect();
+                let words: [InputWord; BLOCK_SIZE] = words.try_into().unwrap();
+
+                if let Some(idx) = self.tamper {
+                    // Witness other padding, as a prover ignoring the constants would.
+                    let mut input = block.map(|word| BlockWord(Value::known(word)));
+                    input[idx] = BlockWord(Value::known(block[idx] ^ 1));
+                    schedule.process_block(&mut layouter, input, Some(InputCells::Mixed(&words)))?;
+                } else {
+                    let w = schedule.process_from_input_words(&mut layouter, &words)?.dense;
+                    for (word, expected) in w.iter().zip(block.iter()) {
+                        word.value().assert_if_known(|bits| lebs2ip(bits) as u64 == *expected);
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        let circuit = MyCircuit { tamper: None };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+
+        // The schedule no longer matches a padding constant, so the copy constraint fails.
+        for idx in [2, 9, BLOCK_SIZE - 1] {
+            let circuit = MyCircuit { tamper: Some(idx) };
+            let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:?}", e),
+            };
+            let failures = prover.verify().expect_err("tampered padding was accepted");
+            assert!(
+                failures
+                    .iter()
+                    .any(|failure| matches!(failure, VerifyFailure::Permutation { .. })),
+                "padding word {} is not bound to its constant",
+                idx
+            );
+        }
+    }
+
+    #[test]
+    fn process_from_halves() {
+        struct MyCircuit {}
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = Table16Config;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit {}
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                Table16Chip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
+
+                // Test vector: "abc"
+                let mut block = [0; BLOCK_SIZE];
+                block[0] = 0x6162638000000000;
+                block[BLOCK_SIZE - 1] = 24;
+
+                let schedule = config.message_schedule;
+                let halves = layouter.assign_region(
+                    || "upstream halves",
+                    |mut region| {
+                        let mut halves = Vec::with_capacity(BLOCK_SIZE);
+                        for (row, word) in block.iter().enumerate() {
+                            let lo = AssignedBits::<32>::assign(
+                                &mut region,
+                                || "upstream lo",
+                                schedule.extras[0],
+                                row,
+                                Value::known(*word as u32),
+                            )?;
+                            let hi = AssignedBits::<32>::assign(
+                                &mut region,
+                                || "upstream hi",
+                                schedule.extras[1],
+                                row,
+                                Value::known((*word >> 32) as u32),
+                            )?;
+                            halves.push((lo, hi));
+                        }
+                        Ok(halves)
+                    },
+                )?;
+                let halves: [(AssignedBits<32>, AssignedBits<32>); BLOCK_SIZE] =
+                    halves.try_into().unwrap();
+
+                let MessageSchedule {
+                    dense: w,
+                    halves: w_halves,
+                } = schedule.process_from_halves(&mut layouter, &halves)?;
+                let input = block.map(|word| BlockWord(Value::known(word)));
+                let MessageSchedule {
+                    dense: expected_w,
+                    halves: expected_halves,
+                } = schedule.process(&mut layouter, input)?;
+
+                for ((word, expected), test_word) in
+                    w.iter().zip(expected_w.iter()).zip(MSG_SCHEDULE_TEST_OUTPUT.iter())
+                {
+                    word.value_u64()
+                        .zip(expected.value_u64())
+                        .assert_if_known(|(word, expected)| word == expected && word == test_word);
+                }
+                for ((lo, hi), (expected_lo, expected_hi)) in
+                    w_halves.iter().zip(expected_halves.iter())
+                {
+                    lo.value_u32()
+                        .zip(hi.value_u32())
+                        .zip(expected_lo.value_u32().zip(expected_hi.value_u32()))
+                        .assert_if_known(|(halves, expected)| halves == expected);
+                }
+                Ok(())
+            }
+        }
+
+        let circuit: MyCircuit = MyCircuit {};
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+}
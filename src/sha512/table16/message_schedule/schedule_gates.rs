@@ -47,6 +47,17 @@ impl<F: FieldExt> ScheduleGate<F> {
         Some(("s_decompose_0", s_decompose_0 * check))
     }
 
+    /// s_input_range for the 32-bit halves of W_0 to W_15
+    pub fn s_input_range(
+        s_input_range: Expression<F>,
+        half: Expression<F>,
+        chunk_lo: Expression<F>,
+        chunk_hi: Expression<F>,
+    ) -> Option<(&'static str, Expression<F>)> {
+        let check = chunk_lo + chunk_hi * F::from(1 << 16) - half;
+        Some(("s_input_range", s_input_range * check))
+    }
+
     /// s_decompose_1 for W_1 to W_13
     /// (1, 6, 1, 56)-bit chunks
     #[allow(clippy::too_many_arguments)]
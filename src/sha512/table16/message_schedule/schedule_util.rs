@@ -1,4 +1,4 @@
-use super::super::AssignedBits;
+use super::super::{super::BLOCK_SIZE, util::i2lebsp, AssignedBits, SpreadVar, SpreadWord};
 use super::MessageScheduleConfig;
 use halo2_proofs::{
     circuit::{Region, Value},
@@ -7,7 +7,7 @@ use halo2_proofs::{
 };
 
 #[cfg(test)]
-use super::super::{super::BLOCK_SIZE, BlockWord, ROUNDS};
+use super::super::{BlockWord, ROUNDS};
 
 // Rows needed for each gate
 pub const DECOMPOSE_0_ROWS: usize = 6;
@@ -31,8 +31,13 @@ pub const SUBREGION_2_ROWS: usize = SUBREGION_2_LEN * SUBREGION_2_WORD;
 pub const SUBREGION_3_WORD: usize = DECOMPOSE_3_ROWS + SIGMA_1_V1_ROWS;
 pub const SUBREGION_3_LEN: usize = 13; // W[65..78]
 pub const SUBREGION_3_ROWS: usize = SUBREGION_3_LEN * SUBREGION_3_WORD;
-// pub const SUBREGION_4_LEN: usize = 2; // W_[78..80]
-// pub const SUBREGION_4_ROWS: usize = SUBREGION_4_LEN * DECOMPOSE_0_ROWS;
+pub const SUBREGION_4_LEN: usize = 2; // W_[78..80]
+pub const SUBREGION_4_ROWS: usize = SUBREGION_4_LEN * DECOMPOSE_0_ROWS;
+pub const SCHEDULE_ROWS: usize =
+    SUBREGION_0_ROWS + SUBREGION_1_ROWS + SUBREGION_2_ROWS + SUBREGION_3_ROWS + SUBREGION_4_ROWS;
+
+// Rows needed to range-check the input words: two 16-bit chunks per 32-bit half
+pub const INPUT_RANGE_ROWS: usize = BLOCK_SIZE * 2 * 2;
 
 /// Returns row number of a word
 pub fn get_word_row(word_idx: usize) -> usize {
@@ -195,4 +200,32 @@ impl MessageScheduleConfig {
 
         Ok((word, (w_lo, w_hi)))
     }
+
+    // Range-check the halves of the input words W_[0..16], each as two 16-bit chunks
+    pub fn assign_input_range(
+        &self,
+        region: &mut Region<'_, bn256::Fr>,
+        input_halves: &[(AssignedBits<32>, AssignedBits<32>)],
+    ) -> Result<(), Error> {
+        // Rename these here for ease of matching the gates to the specification.
+        let a_3 = self.extras[0];
+
+        let halves = input_halves.iter().flat_map(|(lo, hi)| [lo, hi]);
+        for (idx, half) in halves.enumerate() {
+            let row = 2 * idx;
+            self.s_input_range.enable(region, row)?;
+            half.copy_advice(|| format!("half_{}", idx), region, a_3, row)?;
+
+            let half = half.value_u32();
+            for (offset, chunk) in [half.map(|half| half as u16), half.map(|half| (half >> 16) as u16)]
+                .into_iter()
+                .enumerate()
+            {
+                let chunk = chunk.map(|chunk| SpreadWord::<16, 32>::new(i2lebsp(chunk.into())));
+                SpreadVar::with_lookup(region, &self.lookup, row + offset, chunk)?;
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -514,6 +514,7 @@ impl MessageScheduleConfig {
         self.assign_sigma_outputs(
             region,
             &self.lookup,
+            self.s_spread_outputs,
             a_3,
             row,
             r_0_even,
@@ -550,6 +551,7 @@ impl MessageScheduleConfig {
         self.assign_sigma_outputs(
             region,
             &self.lookup,
+            self.s_spread_outputs,
             a_3,
             row,
             r_0_even,
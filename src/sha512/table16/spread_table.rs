@@ -1,4 +1,16 @@
-use super::{util::*, AssignedBits};
+//! The spread table, and the variables that look up into it.
+//!
+//! The spread form of a word interleaves its bits with zeros, bit `i` going to bit `2i`.
+//! Adding the spread forms of two or three words keeps the bits of each position apart,
+//! so the even and odd bits of the sum give their XOR and AND, or their majority. The
+//! table holds every 16-bit dense word with its spread form, and a tag for the range of
+//! the dense word, so a lookup of `(tag, dense, spread)` also range-checks `dense`.
+//!
+//! Gadgets outside [`Table16Chip`](super::Table16Chip) can reuse the chip's table
+//! through [`Table16Config::spread_table`](super::Table16Config::spread_table), whose
+//! inputs are looked up on every row.
+
+use super::{util::*, witness_debug, AssignedBits};
 
 use halo2_proofs::{
     arithmetic::FieldExt,
@@ -7,6 +19,7 @@ use halo2_proofs::{
     plonk::{Advice, Column, ConstraintSystem, Error, TableColumn},
     poly::Rotation,
 };
+use lazy_static::lazy_static;
 use std::convert::TryInto;
 use std::marker::PhantomData;
 
@@ -15,9 +28,16 @@ const BITS_11: usize = 1 << 11;
 const BITS_13: usize = 1 << 13;
 const BITS_14: usize = 1 << 14;
 
+lazy_static! {
+    /// The `(tag, dense, spread)` rows of the spread table, computed on first use and
+    /// shared by every later [`SpreadTableChip::load`]. They are kept as integers, as the
+    /// chip is generic over the field.
+    static ref SPREAD_ROWS: Vec<(u8, u16, u32)> = SpreadTableConfig::rows();
+}
+
 /// An input word into a lookup, containing (tag, dense, spread)
 #[derive(Copy, Clone, Debug)]
-pub(super) struct SpreadWord<const DENSE: usize, const SPREAD: usize> {
+pub struct SpreadWord<const DENSE: usize, const SPREAD: usize> {
     pub tag: u8,
     pub dense: [bool; DENSE],
     pub spread: [bool; SPREAD],
@@ -40,7 +60,12 @@ pub fn get_tag(input: u16) -> u8 {
 }
 
 impl<const DENSE: usize, const SPREAD: usize> SpreadWord<DENSE, SPREAD> {
-    pub(super) fn new(dense: [bool; DENSE]) -> Self {
+    /// Computes the tag and spread form of `dense`, given as little-endian bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `DENSE` is more than 16, the width of the table.
+    pub fn new(dense: [bool; DENSE]) -> Self {
         assert!(DENSE <= 16);
         SpreadWord {
             tag: get_tag(lebs2ip(&dense) as u16),
@@ -49,7 +74,12 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadWord<DENSE, SPREAD> {
         }
     }
 
-    pub(super) fn try_new<T: TryInto<[bool; DENSE]> + std::fmt::Debug>(dense: T) -> Self
+    /// Like [`SpreadWord::new`], converting `dense` first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dense` does not convert to `DENSE` bits, or if `DENSE` is more than 16.
+    pub fn try_new<T: TryInto<[bool; DENSE]> + std::fmt::Debug>(dense: T) -> Self
     where
         <T as TryInto<[bool; DENSE]>>::Error: std::fmt::Debug,
     {
@@ -64,15 +94,81 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadWord<DENSE, SPREAD> {
 }
 
 /// A variable stored in advice columns corresponding to a row of [`SpreadTableConfig`].
-#[derive(Clone, Debug)]
-pub(super) struct SpreadVar<const DENSE: usize, const SPREAD: usize> {
+///
+/// ```
+/// use halo2_proofs::{
+///     circuit::{Layouter, SimpleFloorPlanner, Value},
+///     dev::MockProver,
+///     halo2curves::bn256,
+///     plonk::{Circuit, ConstraintSystem, Error},
+/// };
+/// use sha512_halo2::sha512::spread_table::{
+///     SpreadTableChip, SpreadTableConfig, SpreadVar, SpreadWord,
+/// };
+///
+/// struct TwoBits;
+///
+/// impl Circuit<bn256::Fr> for TwoBits {
+///     type Config = SpreadTableConfig;
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         TwoBits
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+///         let tag = meta.advice_column();
+///         let dense = meta.advice_column();
+///         let spread = meta.advice_column();
+///         SpreadTableChip::configure(meta, tag, dense, spread)
+///     }
+///
+///     fn synthesize(
+///         &self,
+///         config: Self::Config,
+///         mut layouter: impl Layouter<bn256::Fr>,
+///     ) -> Result<(), Error> {
+///         SpreadTableChip::load(config.clone(), &mut layouter)?;
+///         layouter.assign_region(
+///             || "two bits",
+///             |mut region| {
+///                 // 0b11 spreads to 0b0101.
+///                 let word = Value::known(SpreadWord::<2, 4>::new([true, true]));
+///                 let var = SpreadVar::with_lookup(&mut region, &config.input, 0, word)?;
+///                 var.spread
+///                     .value()
+///                     .assert_if_known(|spread| spread[..] == [true, false, true, false]);
+///                 Ok(())
+///             },
+///         )
+///     }
+/// }
+///
+/// let prover = MockProver::run(17, &TwoBits, vec![]).unwrap();
+/// prover.assert_satisfied();
+/// ```
+#[derive(Clone)]
+pub struct SpreadVar<const DENSE: usize, const SPREAD: usize> {
     pub _tag: Value<u8>,
     pub dense: AssignedBits<DENSE>,
     pub spread: AssignedBits<SPREAD>,
 }
 
+// The tag bounds the dense value, so it is as secret as the value.
+impl<const DENSE: usize, const SPREAD: usize> std::fmt::Debug for SpreadVar<DENSE, SPREAD> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpreadVar")
+            .field("_tag", witness_debug(&self._tag))
+            .field("dense", &self.dense)
+            .field("spread", &self.spread)
+            .finish()
+    }
+}
+
 impl<const DENSE: usize, const SPREAD: usize> SpreadVar<DENSE, SPREAD> {
-    pub(super) fn with_lookup(
+    /// Assigns `word` to a row of the lookup inputs `cols`, so that the lookup checks
+    /// its tag, dense and spread forms against the table.
+    pub fn with_lookup(
         region: &mut Region<'_, bn256::Fr>,
         cols: &SpreadInputs,
         row: usize,
@@ -102,7 +198,10 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadVar<DENSE, SPREAD> {
         })
     }
 
-    pub(super) fn without_lookup(
+    /// Assigns the dense and spread forms of `word` to the given cells, outside the
+    /// lookup inputs. Nothing checks them against each other; the caller must constrain
+    /// them, typically by copying them from or to looked-up cells.
+    pub fn without_lookup(
         region: &mut Region<'_, bn256::Fr>,
         dense_col: Column<Advice>,
         dense_row: usize,
@@ -138,28 +237,32 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadVar<DENSE, SPREAD> {
     }
 }
 
+/// The advice columns looked up in the spread table, on every row.
 #[derive(Clone, Debug)]
-pub(super) struct SpreadInputs {
-    pub(super) tag: Column<Advice>,
-    pub(super) dense: Column<Advice>,
-    pub(super) spread: Column<Advice>,
+pub struct SpreadInputs {
+    pub tag: Column<Advice>,
+    pub dense: Column<Advice>,
+    pub spread: Column<Advice>,
 }
 
-#[derive(Clone, Debug)]
-pub(super) struct SpreadTable {
-    pub(super) tag: TableColumn,
-    pub(super) dense: TableColumn,
-    pub(super) spread: TableColumn,
+/// The table columns of the spread table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpreadTable {
+    pub tag: TableColumn,
+    pub dense: TableColumn,
+    pub spread: TableColumn,
 }
 
+/// A lookup from [`SpreadInputs`] into a [`SpreadTable`].
 #[derive(Clone, Debug)]
-pub(super) struct SpreadTableConfig {
+pub struct SpreadTableConfig {
     pub input: SpreadInputs,
     pub table: SpreadTable,
 }
 
+/// Configures and loads the spread table.
 #[derive(Clone, Debug)]
-pub(super) struct SpreadTableChip<F: FieldExt> {
+pub struct SpreadTableChip<F: FieldExt> {
     config: SpreadTableConfig,
     _marker: PhantomData<F>,
 }
@@ -178,16 +281,32 @@ impl<F: FieldExt> Chip<F> for SpreadTableChip<F> {
 }
 
 impl<F: FieldExt> SpreadTableChip<F> {
+    /// Allocates a spread table and looks up `(input_tag, input_dense, input_spread)`
+    /// into it on every row.
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         input_tag: Column<Advice>,
         input_dense: Column<Advice>,
         input_spread: Column<Advice>,
     ) -> <Self as Chip<F>>::Config {
-        let table_tag = meta.lookup_table_column();
-        let table_dense = meta.lookup_table_column();
-        let table_spread = meta.lookup_table_column();
+        let table = SpreadTable {
+            tag: meta.lookup_table_column(),
+            dense: meta.lookup_table_column(),
+            spread: meta.lookup_table_column(),
+        };
+
+        Self::configure_with_table(meta, input_tag, input_dense, input_spread, table)
+    }
 
+    /// Configures a lookup from the given inputs into an existing spread table. The
+    /// table must be loaded exactly once, through the config that allocated it.
+    pub fn configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        input_tag: Column<Advice>,
+        input_dense: Column<Advice>,
+        input_spread: Column<Advice>,
+        table: SpreadTable,
+    ) -> <Self as Chip<F>>::Config {
         //TODO: Add proper name for lookup
         meta.lookup("",|meta| {
             let tag_cur = meta.query_advice(input_tag, Rotation::cur());
@@ -195,9 +314,9 @@ impl<F: FieldExt> SpreadTableChip<F> {
             let spread_cur = meta.query_advice(input_spread, Rotation::cur());
 
             vec![
-                (tag_cur, table_tag),
-                (dense_cur, table_dense),
-                (spread_cur, table_spread),
+                (tag_cur, table.tag),
+                (dense_cur, table.dense),
+                (spread_cur, table.spread),
             ]
         });
 
@@ -207,14 +326,11 @@ impl<F: FieldExt> SpreadTableChip<F> {
                 dense: input_dense,
                 spread: input_spread,
             },
-            table: SpreadTable {
-                tag: table_tag,
-                dense: table_dense,
-                spread: table_spread,
-            },
+            table,
         }
     }
 
+    /// Loads the table of `config`. Each table must be loaded exactly once.
     pub fn load(
         config: SpreadTableConfig,
         layouter: &mut impl Layouter<F>,
@@ -257,39 +373,27 @@ impl<F: FieldExt> SpreadTableChip<F> {
 }
 
 impl SpreadTableConfig {
-    fn generate<F: FieldExt>() -> impl Iterator<Item = (F, F, F)> {
-        (1..=(1 << 16)).scan((F::zero(), F::zero(), F::zero()), |(tag, dense, spread), i| {
-            // We computed this table row in the previous iteration.
-            let res = (*tag, *dense, *spread);
-
-            // i holds the zero-indexed row number for the next table row.
-            match i {
-                BITS_10 | BITS_11 | BITS_13 | BITS_14  => *tag += F::one(),
-                _ => (),
-            }
-            *dense += F::one();
-            if i & 1 == 0 {
-                // On even-numbered rows we recompute the spread.
-                *spread = F::zero();
-                for b in 0..16 {
-                    if (i >> b) & 1 != 0 {
-                        *spread += F::from(1 << (2 * b));
-                    }
-                }
-            } else {
-                // On odd-numbered rows we add one.
-                *spread += F::one();
-            }
+    fn rows() -> Vec<(u8, u16, u32)> {
+        (0..=u16::MAX)
+            .map(|dense| {
+                let spread = spread_bits::<16, 32>(i2lebsp::<16>(dense.into()));
+                (get_tag(dense), dense, lebs2ip(&spread) as u32)
+            })
+            .collect()
+    }
 
-            Some(res)
+    fn generate<F: FieldExt>() -> impl Iterator<Item = (F, F, F)> {
+        SPREAD_ROWS.iter().map(|&(tag, dense, spread)| {
+            (F::from(tag as u64), F::from(dense as u64), F::from(spread as u64))
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{get_tag, SpreadTableChip, SpreadTableConfig};
+    use super::{get_tag, SpreadTableChip, SpreadTableConfig, SPREAD_ROWS};
     use rand::Rng;
+    use sha2::{Digest, Sha512};
 
     use halo2_proofs::{
         arithmetic::FieldExt,
@@ -446,4 +550,119 @@ mod tests {
         };
         assert_eq!(prover.verify(), Ok(()));
     }
+    /// The `(tag, dense, spread)` row for `dense`, computed bit by bit rather than with
+    /// the helpers the table is built from.
+    fn expected_row(dense: u16) -> (u8, u16, u32) {
+        let tag = match 16 - dense.leading_zeros() {
+            0..=10 => 0,
+            11 => 1,
+            12 | 13 => 2,
+            14 => 3,
+            _ => 4,
+        };
+        let spread = (0..16).fold(0u32, |spread, bit| {
+            spread | (u32::from((dense >> bit) & 1) << (2 * bit))
+        });
+        (tag, dense, spread)
+    }
+
+    #[test]
+    fn table_rows() {
+        // Row `d` holds the dense value `d`.
+        assert_eq!(SPREAD_ROWS.len(), 1 << 16);
+        for (row, &triple) in SPREAD_ROWS.iter().enumerate() {
+            assert_eq!(triple, expected_row(row as u16), "row {}", row);
+        }
+
+        // Pins the whole table: SHA-512 of every row as a tag byte, then the dense and
+        // spread values big-endian.
+        let mut hasher = Sha512::new();
+        for &(tag, dense, spread) in SPREAD_ROWS.iter() {
+            hasher.update([tag]);
+            hasher.update(dense.to_be_bytes());
+            hasher.update(spread.to_be_bytes());
+        }
+        assert_eq!(
+            format!("{:x}", hasher.finalize()),
+            concat!(
+                "cb9eee9406f90a33dbd90737e25b63378d0811b695de02a199285531d9c93cfc",
+                "a2a7367e10db3f29f845fa97cbb6cc0cbc4dd60098e6fabbb04de74b9f891ad7",
+            )
+        );
+    }
+
+    #[test]
+    fn loaded_table() {
+        // Looks up every expected row in the table as `SpreadTableChip::load` assigns it.
+        // The 2^16 expected rows are distinct and the loader assigns 2^16 rows, so if all
+        // of them are found the loaded table holds exactly the expected rows.
+        struct MyCircuit {
+            rows: Vec<(u8, u16, u32)>,
+        }
+
+        impl Circuit<bn256::Fr> for MyCircuit {
+            type Config = SpreadTableConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit { rows: self.rows.clone() }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+                let input_tag = meta.advice_column();
+                let input_dense = meta.advice_column();
+                let input_spread = meta.advice_column();
+
+                SpreadTableChip::configure(meta, input_tag, input_dense, input_spread)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<bn256::Fr>,
+            ) -> Result<(), Error> {
+                SpreadTableChip::load(config.clone(), &mut layouter)?;
+
+                layouter.assign_region(
+                    || "expected rows",
+                    |mut region| {
+                        for (row, &(tag, dense, spread)) in self.rows.iter().enumerate() {
+                            let cells = [
+                                (config.input.tag, u64::from(tag)),
+                                (config.input.dense, u64::from(dense)),
+                                (config.input.spread, u64::from(spread)),
+                            ];
+                            for (column, value) in cells {
+                                region.assign_advice(
+                                    || "expected",
+                                    column,
+                                    row,
+                                    || Value::known(bn256::Fr::from(value)),
+                                )?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let rows: Vec<_> = (0..=u16::MAX).map(expected_row).collect();
+        let circuit = MyCircuit { rows };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+
+        // A row with the wrong tag is not in the table.
+        let mut rows: Vec<_> = (0..16).map(expected_row).collect();
+        rows.push((1, 0b11_1111_1111, 0b0101_0101_0101_0101_0101));
+        let circuit = MyCircuit { rows };
+        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert!(prover.verify().is_err());
+    }
 }
\ No newline at end of file
@@ -1,46 +1,14 @@
 
 use halo2_proofs::circuit::Value;
 
-pub const MASK_EVEN_64: u64= 0x5555555555555555;
-
-/// The sequence of bits representing a u64 in little-endian order.
-///
-/// # Panics
-///
-/// Panics if the expected length of the sequence `NUM_BITS` exceeds
-/// 128.
-pub fn i2lebsp<const NUM_BITS: usize>(int: u128) -> [bool; NUM_BITS] {
-    /// Takes in an FnMut closure and returns a constant-length array with elements of
-    /// type `Output`.
-    fn gen_const_array<Output: Copy + Default, const LEN: usize>(
-        closure: impl FnMut(usize) -> Output,
-    ) -> [Output; LEN] {
-        gen_const_array_with_default(Default::default(), closure)
-    }
-
-    fn gen_const_array_with_default<Output: Copy, const LEN: usize>(
-        default_value: Output,
-        closure: impl FnMut(usize) -> Output,
-    ) -> [Output; LEN] {
-        let mut ret: [Output; LEN] = [default_value; LEN];
-        for (bit, val) in ret.iter_mut().zip((0..LEN).map(closure)) {
-            *bit = val;
-        }
-        ret
-    }
+pub use crate::reference::{
+    i2lebsp, lebs2ip, reference_compress_rounds, reference_message_schedule, reference_sha512,
+};
 
-    assert!(NUM_BITS <= 128);
-    gen_const_array(|mask: usize| (int & (1 << mask)) != 0)
-}
-
-/// Returns the integer representation of a little-endian bit-array.
-/// Panics if the number of bits exceeds 128.
-pub fn lebs2ip<const K: usize>(bits: &[bool; K]) -> u128 {
-    assert!(K <= 128);
-    bits.iter()
-        .enumerate()
-        .fold(0u128, |acc, (i, b)| acc + if *b { 1 << i } else { 0 })
-}
+/// The spread form of `u32::MAX`: the even bits of a 64-bit spread half. `s_ch_neg`
+/// derives it from the word size; this pins the SHA-512 value.
+#[cfg(test)]
+pub const MASK_EVEN_64: u64 = 0x5555555555555555;
 
 /// Helper function that interleaves a little-endian bit-array with zeros
 /// in the odd indices. That is, it takes the array
@@ -115,4 +83,4 @@ pub fn sum_with_carry(words: Vec<(Value<u32>, Value<u32>)>) -> (Value<u64>, Valu
     let sum = sum.map(|sum| sum as u64);
 
     (sum, carry)
-}
\ No newline at end of file
+}
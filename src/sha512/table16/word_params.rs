@@ -0,0 +1,55 @@
+//! The parameters of a SHA-2 variant, as far as the word-size-independent gates use them.
+//!
+//! The compression gates that only add, choose or take the majority of words are the
+//! same for every SHA-2 variant up to the word size, so [`CompressionGate`] is generic
+//! over these parameters. The decompositions and the `Σ` gates depend on the rotation
+//! amounts and stay specific to SHA-512.
+//!
+//! [`CompressionGate`]: super::compression
+
+use super::{Sha512Error, IV, ROUNDS, ROUND_CONSTANTS};
+
+/// The word size, chunk layout and constants of a SHA-2 variant.
+///
+/// Words are handled as two halves of [`HALF_BITS`](WordParams::HALF_BITS) each, which
+/// the lookup table range-checks in quarters.
+pub trait WordParams {
+    /// The number of bits in a word.
+    const WORD_BITS: usize;
+    /// The number of bits in each half of a word.
+    const HALF_BITS: usize = Self::WORD_BITS / 2;
+    /// The number of compression rounds per block.
+    const ROUNDS: usize;
+    /// The bit widths of the pieces `A, B, C, D` are split into for `Σ_0` and `Maj`, from
+    /// the least significant.
+    const ABCD_CHUNKS: &'static [usize];
+    /// The bit widths of the pieces `E, F, G, H` are split into for `Σ_1` and `Ch`, from
+    /// the least significant.
+    const EFGH_CHUNKS: &'static [usize];
+    /// The initial hash value.
+    const IV: &'static [u64];
+    /// The round constants `K`.
+    const ROUND_CONSTANTS: &'static [u64];
+
+    /// The round constant `K_round`. Returns [`Sha512Error::RoundOutOfRange`] rather than
+    /// panicking or wrapping around when `round` is past the last round.
+    fn round_constant(round: usize) -> Result<u64, Sha512Error> {
+        Self::ROUND_CONSTANTS.get(round).copied().ok_or(Sha512Error::RoundOutOfRange {
+            round,
+            rounds: Self::ROUND_CONSTANTS.len(),
+        })
+    }
+}
+
+/// The parameters of SHA-512.
+#[derive(Clone, Copy, Debug)]
+pub struct Sha512Params;
+
+impl WordParams for Sha512Params {
+    const WORD_BITS: usize = 64;
+    const ROUNDS: usize = ROUNDS;
+    const ABCD_CHUNKS: &'static [usize] = &[14, 14, 3, 3, 2, 3, 14, 11];
+    const EFGH_CHUNKS: &'static [usize] = &[14, 2, 2, 13, 10, 13, 10];
+    const IV: &'static [u64] = &IV;
+    const ROUND_CONSTANTS: &'static [u64] = &ROUND_CONSTANTS;
+}
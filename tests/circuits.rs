@@ -0,0 +1,263 @@
+#![cfg(feature = "std")]
+
+use halo2_proofs::{
+    circuit::{floor_planner::V1, Value},
+    dev::MockProver,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit},
+    poly::{
+        commitment::ParamsProver,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
+};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sha2::{Digest, Sha512};
+use sha512_halo2::reference::{pad_message, reference_compress};
+use sha512_halo2::sha512::{
+    blocks_from_bytes,
+    circuits::{
+        digest_instance, packed_digest_instance, BoundedPreimageCircuit, PreimageCircuit,
+        SingleBlockHashCircuit, TwoToOneCircuit,
+    },
+    BlockWord, Table16Config, BLOCK_SIZE, DIGEST_SIZE, IV,
+};
+
+fn rng() -> XorShiftRng {
+    XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+        0xbc, 0xe5,
+    ])
+}
+
+fn instance(message: &[u8]) -> Vec<Fr> {
+    digest_instance(Sha512::digest(message).as_slice().try_into().unwrap())
+}
+
+/// Runs `circuit` on the mock prover against the digest of `message`.
+fn check<C: Circuit<Fr>>(circuit: &C, num_blocks: usize, message: &[u8]) {
+    let prover = match MockProver::run(Table16Config::minimum_k(num_blocks), circuit, vec![instance(message)]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+/// Returns the instance a prover choosing the padding of `message` could claim: the
+/// digest of its blocks with another length field.
+fn tampered_instance(message: &[u8]) -> Vec<Fr> {
+    let mut blocks = pad_message(message);
+    let last = blocks.len() - 1;
+    blocks[last][BLOCK_SIZE - 1] ^= 8;
+    let state = blocks.into_iter().fold(IV, reference_compress);
+    let digest: Vec<u8> = state.iter().flat_map(|word| word.to_be_bytes()).collect();
+    digest_instance(digest.as_slice().try_into().unwrap())
+}
+
+/// Runs `circuit` on the mock prover against `instance`, and checks it is rejected.
+fn check_rejected<C: Circuit<Fr>>(circuit: &C, num_blocks: usize, instance: Vec<Fr>) {
+    let prover = match MockProver::run(Table16Config::minimum_k(num_blocks), circuit, vec![instance]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn single_block_hash_circuit() {
+    let circuit = SingleBlockHashCircuit::new(blocks_from_bytes(b"abc")[0]);
+    check(&circuit, 1, b"abc");
+
+    // A block with another length field is rejected, even against the digest its
+    // compression gives.
+    let mut tampered = circuit.clone();
+    tampered.block[BLOCK_SIZE - 1] = BlockWord(tampered.block[BLOCK_SIZE - 1].0.map(|word| word ^ 8));
+    check_rejected(&tampered, 1, tampered_instance(b"abc"));
+
+    let params = ParamsKZG::<Bn256>::setup(Table16Config::minimum_k(1), rng());
+    keygen_vk(&params, &circuit.without_witnesses()).expect("keygen_vk should not fail");
+}
+
+#[test]
+fn layout_independent_of_input() {
+    // The verifying key commits to the selectors, fixed columns and copy constraints the
+    // floor planner laid out, so it differs if the layout depends on the message.
+    let params = ParamsKZG::<Bn256>::setup(Table16Config::minimum_k(1), rng());
+    let pinned = |circuit: &SingleBlockHashCircuit| {
+        let vk = keygen_vk(&params, circuit).expect("keygen_vk should not fail");
+        format!("{:?}", vk.pinned())
+    };
+
+    let messages: [&[u8]; 3] = [b"", b"abc", &[0xff; 111]];
+    let circuits = messages.map(|message| SingleBlockHashCircuit::new(blocks_from_bytes(message)[0]));
+    for (circuit, message) in circuits.iter().zip(messages.iter()) {
+        check(circuit, 1, message);
+    }
+
+    let expected = pinned(&circuits[0].without_witnesses());
+    for (circuit, message) in circuits.iter().zip(messages.iter()) {
+        assert!(pinned(circuit) == expected, "the layout for {:?} depends on it", message);
+    }
+}
+
+#[test]
+fn floor_planners() {
+    // The circuits give the same verdicts whichever planner lays them out, for the
+    // right digest and for a wrong one.
+    fn verdict<C: Circuit<Fr>>(circuit: &C, digest_of: &[u8]) -> bool {
+        let prover = match MockProver::run(Table16Config::minimum_k(1), circuit, vec![instance(digest_of)]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.verify().is_ok()
+    }
+
+    let simple = SingleBlockHashCircuit::new(blocks_from_bytes(b"abc")[0]);
+    let v1 = simple.clone().with_floor_planner::<V1>();
+    let preimage = PreimageCircuit::new(b"abc");
+    let preimage_v1 = PreimageCircuit::new(b"abc").with_floor_planner::<V1>();
+    for digest_of in [&b"abc"[..], b"abd"] {
+        let expected = digest_of == b"abc";
+        assert_eq!(verdict(&simple, digest_of), expected);
+        assert_eq!(verdict(&v1, digest_of), expected);
+        assert_eq!(verdict(&preimage, digest_of), expected);
+        assert_eq!(verdict(&preimage_v1, digest_of), expected);
+    }
+}
+
+#[test]
+fn two_to_one_circuit() {
+    let left: Vec<u64> = (0..DIGEST_SIZE as u64).collect();
+    let right: Vec<u64> = (0..DIGEST_SIZE as u64).map(|word| !word).collect();
+    let mut circuit = TwoToOneCircuit::default();
+    for (word, value) in circuit.left.iter_mut().zip(left.iter()) {
+        *word = BlockWord(Value::known(*value));
+    }
+    for (word, value) in circuit.right.iter_mut().zip(right.iter()) {
+        *word = BlockWord(Value::known(*value));
+    }
+    let message: Vec<u8> = left.iter().chain(right.iter()).flat_map(|word| word.to_be_bytes()).collect();
+    check(&circuit, 2, &message);
+
+    // The padding block is fixed, so the digest over another one is rejected.
+    check_rejected(&circuit, 2, tampered_instance(&message));
+
+    let params = ParamsKZG::<Bn256>::setup(Table16Config::minimum_k(2), rng());
+    keygen_vk(&params, &circuit.without_witnesses()).expect("keygen_vk should not fail");
+}
+
+#[test]
+fn preimage_circuit() {
+    let message = b"abc";
+    let circuit = PreimageCircuit::new(message);
+    assert_eq!(circuit.num_blocks(), 1);
+    check(&circuit, circuit.num_blocks(), message);
+
+    // The padding is checked in the circuit, so the digest over another one is rejected.
+    check_rejected(&circuit, circuit.num_blocks(), tampered_instance(message));
+
+    let mut rng = rng();
+    let params = ParamsKZG::<Bn256>::setup(Table16Config::minimum_k(circuit.num_blocks()), &mut rng);
+    let vk = keygen_vk(&params, &circuit.without_witnesses()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &circuit.without_witnesses()).expect("keygen_pk should not fail");
+
+    let public = instance(message);
+    let instances: &[&[Fr]] = &[&public];
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        _,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        PreimageCircuit,
+    >(&params, &pk, &[circuit], &[instances], &mut rng, &mut transcript)
+    .expect("proof generation should not fail");
+    let proof = transcript.finalize();
+
+    let verify = |public: &[Fr]| {
+        let instances: &[&[Fr]] = &[public];
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        let strategy = SingleStrategy::new(&params);
+        verify_proof::<
+            KZGCommitmentScheme<Bn256>,
+            VerifierSHPLONK<'_, Bn256>,
+            Challenge255<G1Affine>,
+            Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+            SingleStrategy<'_, Bn256>,
+        >(params.verifier_params(), pk.get_vk(), strategy, &[instances], &mut transcript)
+    };
+    verify(&public).expect("proof verification should not fail");
+
+    // The proof is bound to the digest.
+    assert!(verify(&instance(b"abd")).is_err());
+}
+
+#[test]
+fn bounded_preimage_circuit() {
+    type MyCircuit = BoundedPreimageCircuit<2>;
+    let k = MyCircuit::minimum_k();
+    let packed = |message: &[u8]| {
+        packed_digest_instance(Sha512::digest(message).as_slice().try_into().unwrap())
+    };
+
+    // Messages of one and two blocks share the circuit.
+    let short = b"abc".to_vec();
+    let long: Vec<u8> = (0..200).map(|idx| idx as u8).collect();
+    for message in [&short[..], &long[..]] {
+        let prover = match MockProver::run(k, &MyCircuit::new(message), vec![packed(message)]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+
+    let mut rng = rng();
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+    let vk = keygen_vk(&params, &MyCircuit::default()).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &MyCircuit::default()).expect("keygen_pk should not fail");
+
+    let public = packed(&long[..]);
+    let instances: &[&[Fr]] = &[&public];
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        _,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        MyCircuit,
+    >(&params, &pk, &[MyCircuit::new(&long)], &[instances], &mut rng, &mut transcript)
+    .expect("proof generation should not fail");
+    let proof = transcript.finalize();
+
+    let verify = |public: &[Fr]| {
+        let instances: &[&[Fr]] = &[public];
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        let strategy = SingleStrategy::new(&params);
+        verify_proof::<
+            KZGCommitmentScheme<Bn256>,
+            VerifierSHPLONK<'_, Bn256>,
+            Challenge255<G1Affine>,
+            Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+            SingleStrategy<'_, Bn256>,
+        >(params.verifier_params(), pk.get_vk(), strategy, &[instances], &mut transcript)
+    };
+    verify(&public).expect("proof verification should not fail");
+
+    // The proof is bound to the committed digest.
+    assert!(verify(&packed(&short[..])).is_err());
+}
+
+#[test]
+#[cfg(feature = "self-test")]
+fn self_test() {
+    sha512_halo2::sha512::circuits::self_test().expect("the self-test should pass");
+}
@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests.
+
+use std::fmt::Write;
+
+use sha512_halo2::sha512::{digest_from_hex, digest_hex, BlockWord, DIGEST_SIZE};
+
+/// Asserts that `actual` is the digest given by the 128 hex digits of `expected_hex`.
+///
+/// On a mismatch, panics with both digests side by side, one word per line, pointing at
+/// the first word that differs. Like `Value::assert_if_known`, it does not check a
+/// digest with unknown words, as during key generation.
+pub fn assert_digest_eq(actual: &[BlockWord; DIGEST_SIZE], expected_hex: &str) {
+    let expected = digest_from_hex(expected_hex).expect("expected_hex is not 128 hex digits");
+    let expected = digest_hex(&expected).expect("expected is known");
+    let actual = match digest_hex(actual) {
+        Ok(actual) => actual,
+        Err(_) => return,
+    };
+    if actual == expected {
+        return;
+    }
+
+    let word = |digest: &str, idx: usize| digest[16 * idx..16 * idx + 16].to_string();
+    let first = (0..DIGEST_SIZE)
+        .find(|idx| word(&actual, *idx) != word(&expected, *idx))
+        .expect("the digests differ");
+
+    let mut report = format!("digests differ, first difference at word {}\n", first);
+    writeln!(report, "word  {:<16}  {:<16}", "actual", "expected").unwrap();
+    for idx in 0..DIGEST_SIZE {
+        let (actual, expected) = (word(&actual, idx), word(&expected, idx));
+        let marker = match (idx == first, actual == expected) {
+            (true, _) => "  <- first difference",
+            (false, false) => "  <-",
+            (false, true) => "",
+        };
+        writeln!(report, "{:>4}  {}  {}{}", idx, actual, expected, marker).unwrap();
+    }
+    panic!("{}", report);
+}
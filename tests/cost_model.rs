@@ -0,0 +1,37 @@
+#![cfg(all(feature = "std", feature = "cost_model"))]
+
+use sha512_halo2::sha512::{CostModel, Table16Config};
+
+/// A snapshot of the chip's footprint. A change here should be deliberate: update the
+/// numbers in the same commit as the gates that changed them.
+#[test]
+fn cost_model() {
+    let cost = Table16Config::cost_model();
+
+    // The number of constraints is not pinned, but every gate has at least one.
+    assert!(cost.constraints >= cost.gates);
+    assert_eq!(
+        CostModel {
+            constraints: 0,
+            ..cost
+        },
+        CostModel {
+            gates: 40,
+            constraints: 0,
+            lookups: 1,
+            advice_columns: 10,
+            fixed_columns: 4,
+            instance_columns: 0,
+            selectors: 39,
+            min_rotation: -6,
+            max_rotation: 30,
+            // The 3-bit range checks are degree 8, and the selector adds one.
+            degree: 9,
+        }
+    );
+
+    // The report shows the same counts.
+    let report = cost.to_string();
+    assert!(report.starts_with(&format!("gates: {} ({} constraints)\n", cost.gates, cost.constraints)));
+    assert!(report.ends_with(&format!("degree: {}", cost.degree)));
+}
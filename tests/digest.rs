@@ -1,12 +1,36 @@
+#![cfg(feature = "std")]
+
 use hex_literal::hex;
 use sha2::{Sha512, Digest, digest::{generic_array::GenericArray, typenum::{U64, U8, U2}}};
-use sha512_halo2::sha512::{BlockWord, Sha512 as OtherSha512, Table16Chip, Table16Config,IV,BLOCK_SIZE};
+use sha512_halo2::sha512::{blocks_from_bytes, Block, circuits, digest_from_hex, digest_hex, BlockWord, Endianness, Sha512 as OtherSha512, Sha512Error, Sha512Hasher, Sha512Instructions, Sha512Variant, Table16Chip, Table16Config, BLOCK_SIZE, IV};
 use halo2_proofs::{
     circuit::{AssignedCell, Chip, Layouter, Region, Value, SimpleFloorPlanner},
     halo2curves::bn256,
-    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error, Circuit}, dev::MockProver,
+    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Fixed, Error, Circuit, Instance}, dev::{MockProver, VerifyFailure},
+};
+use halo2_proofs::arithmetic::{Field, FieldExt};
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, G1Affine},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof},
+    poly::{
+        commitment::ParamsProver,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
 };
-use halo2_proofs::arithmetic::FieldExt;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+mod common;
+
+use common::assert_digest_eq;
+
+/// The digest of "abc", from FIPS 180-2.
+const ABC_DIGEST: &str = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
 
 fn preprocess_message(message: &str) -> Vec<Vec<u8>> {
     // translate message into bits
@@ -146,21 +170,2870 @@ fn sha512_test(){
                 s.push(u64::from_str_radix(&string[2..], 2).unwrap());
             }
             for (idx, digest_word) in digest.0.iter().enumerate() {
-                digest_word.0.assert_if_known(|digest_word| {
-                    (*digest_word as u128 + IV[idx] as u128) as u64
-                        == s[idx] as u64
-                    });
+                digest_word.0.assert_if_known(|digest_word| *digest_word == s[idx]);
                 }
             Ok(())
         }
     }
     let circuit: MyCircuit = MyCircuit {};
-    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![]) {
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn sha512_update_across_blocks() {
+    // The first update leaves a partial block, the second completes it and leaves
+    // another, and the last completes that one, so every block is compressed from the
+    // state left by an earlier update.
+    const SPLITS: [usize; 3] = [5, 20, 7];
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let message: Vec<u8> = (0..200).map(|idx| idx as u8).collect();
+            let words: Vec<BlockWord> = blocks_from_bytes(&message).iter().flatten().copied().collect();
+            assert_eq!(words.len(), SPLITS.iter().sum::<usize>());
+
+            let mut hasher = OtherSha512::new(table16_chip, layouter.namespace(|| "init"))?;
+            let mut rest = &words[..];
+            for len in SPLITS {
+                let (chunk, tail) = rest.split_at(len);
+                hasher.update(layouter.namespace(|| "update"), chunk)?;
+                rest = tail;
+            }
+            let digest = hasher.finalize(layouter.namespace(|| "finalize"))?;
+
+            let expected_digest = Sha512::digest(&message);
+            for (idx, digest_word) in digest.0.iter().enumerate() {
+                let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                digest_word.0.assert_if_known(|digest_word| *digest_word == expected);
+            }
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn blocks_from_bytes_padding_boundary() {
+    // 111 bytes leave exactly enough room for the 0x80 byte and the 128-bit length,
+    // 112 bytes spill the length into an extra block.
+    for (len, num_blocks) in [(0, 1), (3, 1), (111, 1), (112, 2), (128, 2), (239, 2), (240, 3)] {
+        let msg = vec![0x61u8; len];
+        let blocks = blocks_from_bytes(&msg);
+        assert_eq!(blocks.len(), num_blocks, "message of {} bytes", len);
+
+        let words: Vec<BlockWord> = blocks.iter().flatten().copied().collect();
+        // The 0x80 marker directly follows the message.
+        words[len / 8].0.assert_if_known(|word| (word >> (56 - 8 * (len % 8))) & 0xff == 0x80);
+        // The message length in bits closes the final block.
+        words[words.len() - 2].0.assert_if_known(|word| *word == 0);
+        words[words.len() - 1].0.assert_if_known(|word| *word == len as u64 * 8);
+    }
+}
+
+#[test]
+fn block_conversions() {
+    // 128 bytes fill a block exactly, and are packed as they are.
+    let bytes: [u8; 128] = core::array::from_fn(|idx| idx as u8);
+    let words: [BlockWord; BLOCK_SIZE] = Block::from(bytes).into();
+    for (word, chunk) in words.iter().zip(bytes.chunks_exact(8)) {
+        let expected = u64::from_be_bytes(chunk.try_into().unwrap());
+        word.0.assert_if_known(|word| *word == expected);
+    }
+
+    // A message is padded as by blocks_from_bytes, as long as it fits in one block.
+    for len in [0, 3, 111] {
+        let msg = vec![0x61u8; len];
+        let block = Block::try_from(&msg[..]).unwrap();
+        for (word, expected) in block.0.iter().zip(blocks_from_bytes(&msg)[0].iter()) {
+            word.0.zip(expected.0).assert_if_known(|(word, expected)| word == expected);
+        }
+    }
+
+    // 120 bytes leave 8 bytes in the block, too few for the 0x80 byte and the 16-byte
+    // length.
+    for len in [112, 120] {
+        let msg = vec![0x61u8; len];
+        assert!(matches!(
+            Block::try_from(&msg[..]),
+            Err(Sha512Error::MessageTooLong { len: l, max: 111 }) if l == len
+        ));
+    }
+}
+
+/// Hashes `message`, padded with `blocks_from_bytes`, and checks the digest against `sha2`.
+///
+/// The number of blocks is kept apart from the message, so that the circuit returned by
+/// `without_witnesses` has the same shape as the one it came from.
+struct MessageCircuit {
+    num_blocks: usize,
+    message: Value<Vec<u8>>,
+}
+
+impl MessageCircuit {
+    fn new(message: &[u8]) -> Self {
+        Self {
+            num_blocks: blocks_from_bytes(message).len(),
+            message: Value::known(message.to_vec()),
+        }
+    }
+}
+
+impl Circuit<bn256::Fr> for MessageCircuit {
+    type Config = Table16Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            num_blocks: self.num_blocks,
+            message: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let padded = self.message.as_ref().map(|message| blocks_from_bytes(message));
+        padded.as_ref().assert_if_known(|padded| padded.len() == self.num_blocks);
+        let blocks: Vec<[BlockWord; BLOCK_SIZE]> = (0..self.num_blocks)
+            .map(|block| {
+                let mut words = [BlockWord::default(); BLOCK_SIZE];
+                for (idx, word) in words.iter_mut().enumerate() {
+                    *word = BlockWord(padded.as_ref().and_then(|padded| padded[block][idx].0));
+                }
+                words
+            })
+            .collect();
+        let digest = OtherSha512::hash_blocks(table16_chip, layouter.namespace(|| "hash_blocks"), &blocks)?;
+
+        let expected_digest = self.message.as_ref().map(Sha512::digest);
+        for (idx, digest_word) in digest.0.iter().enumerate() {
+            let expected = expected_digest
+                .as_ref()
+                .map(|digest| u64::from_be_bytes(digest[8 * idx..8 * idx + 8].try_into().unwrap()));
+            digest_word.0.zip(expected).assert_if_known(|(digest_word, expected)| digest_word == expected);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn blocks_from_bytes_hash_blocks() {
+    // Longest message that still fits in a single padded block.
+    let circuit = MessageCircuit::new(&[0x61u8; 111]);
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn keygen_without_witnesses() {
+    // 112 bytes pad to two blocks, so an empty witness-less message would not match.
+    let circuit = MessageCircuit::new(&[0x61u8; 112]);
+    let k = Table16Config::minimum_k(circuit.num_blocks);
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+        0xbc, 0xe5,
+    ]);
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+
+    let empty_circuit = circuit.without_witnesses();
+    let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        _,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        MessageCircuit,
+    >(&params, &pk, &[circuit], &[], &mut rng, &mut transcript)
+    .expect("proof generation should not fail");
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        SingleStrategy<'_, Bn256>,
+    >(params.verifier_params(), pk.get_vk(), strategy, &[], &mut transcript)
+    .expect("proof verification should not fail");
+}
+
+#[test]
+fn minimum_k() {
+    let k = Table16Config::minimum_k(1);
+    assert!(Table16Config::minimum_rows(1) < 1 << k);
+    // The 2^16 rows of the spread table, not the 6000 rows of the hash, set k.
+    assert_eq!(k, 17);
+
+    let circuit = MessageCircuit::new(b"abc");
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn constraint_degree() {
+    let mut meta = ConstraintSystem::<bn256::Fr>::default();
+    Table16Chip::configure(&mut meta);
+    assert_eq!(meta.degree(), Table16Config::MAX_DEGREE);
+
+    // The additions are linear checks under their selector.
+    for name in ["s_h_prime", "s_a_new", "s_e_new"] {
+        let gate = meta.gates().iter().find(|gate| gate.name() == name).unwrap();
+        for poly in gate.polynomials() {
+            assert_eq!(poly.degree(), 2, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn field_size() {
+    assert!(Table16Config::check_field::<bn256::Fr>().is_ok());
+    assert!(Table16Config::check_field_bits(Table16Config::MAX_CONSTRAINT_BITS + 1).is_ok());
+
+    // A 64-bit field such as Goldilocks, and a field just too small for the spread sums.
+    for num_bits in [64, Table16Config::MAX_CONSTRAINT_BITS] {
+        let err = Table16Config::check_field_bits(num_bits).unwrap_err();
+        assert!(matches!(
+            err,
+            Sha512Error::FieldTooSmall { num_bits: n, required: 131 } if n == num_bits
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!("the gates need a field of at least 131 bits, the field has {}", num_bits)
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "distinct advice columns")]
+fn configure_with_columns_rejects_shared_columns() {
+    let mut meta = ConstraintSystem::<bn256::Fr>::default();
+    let message_schedule = meta.advice_column();
+    let extras = [(); 6].map(|_| meta.advice_column());
+    let tag = meta.advice_column();
+    let dense = meta.advice_column();
+
+    // The spread lookup input reuses the message schedule column.
+    Table16Chip::configure_with_columns(&mut meta, message_schedule, extras, [tag, dense, message_schedule]);
+}
+
+#[test]
+fn try_configure_with_columns() {
+    let mut meta = ConstraintSystem::<bn256::Fr>::default();
+    let message_schedule = meta.advice_column();
+    let extras = [(); 6].map(|_| meta.advice_column());
+    let lookup_inputs = [(); 3].map(|_| meta.advice_column());
+
+    // Too few extra columns.
+    let result =
+        Table16Chip::try_configure_with_columns(&mut meta, message_schedule, &extras[..5], lookup_inputs);
+    assert!(matches!(result, Err(Sha512Error::WrongColumnCount { expected: 6, given: 5 })));
+
+    // A lookup column reused as an extra column.
+    let mut shared = extras;
+    shared[5] = lookup_inputs[1];
+    let result = Table16Chip::try_configure_with_columns(&mut meta, message_schedule, &shared, lookup_inputs);
+    assert!(matches!(result, Err(Sha512Error::DuplicateColumn(column)) if column == lookup_inputs[1]));
+
+    // Nothing was configured by the rejected calls.
+    assert_eq!(meta.gates().len(), 0);
+    Table16Chip::try_configure_with_columns(&mut meta, message_schedule, &extras, lookup_inputs)
+        .expect("distinct columns should be accepted");
+    assert!(!meta.gates().is_empty());
+}
+
+#[test]
+fn shared_spread_table() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Table16Config);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let first = Table16Chip::configure(meta);
+            let second = Table16Chip::configure_with_table(meta, &first);
+            (first, second)
+        }
+
+        fn synthesize(
+            &self,
+            (first, second): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            // The second chip looks up into the table loaded here.
+            Table16Chip::load(first.clone(), &mut layouter)?;
+
+            let blocks = blocks_from_bytes(b"abc");
+            for (name, config) in [("first", first), ("second", second)] {
+                let chip = Table16Chip::construct(config);
+                let digest = OtherSha512::hash_blocks(chip, layouter.namespace(|| name), &blocks)?;
+                assert_digest_eq(&digest.0, ABC_DIGEST);
+            }
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(18, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn load_with_table() {
+    use sha512_halo2::sha512::spread_table::{SpreadTable, SpreadTableChip, SpreadTableConfig};
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        // A sibling gadget owning the spread table, and the chip looking up into it.
+        type Config = (SpreadTableConfig, Table16Config);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let [tag, dense, spread] = [(); 3].map(|_| meta.advice_column());
+            let sibling = SpreadTableChip::configure(meta, tag, dense, spread);
+            let table16 = Table16Chip::configure_with_spread_table(meta, sibling.table.clone());
+            (sibling, table16)
+        }
+
+        fn synthesize(
+            &self,
+            (sibling, table16): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            SpreadTableChip::load(sibling.clone(), &mut layouter)?;
+            Table16Chip::load_with_table(table16.clone(), &mut layouter, &sibling.table)?;
+
+            // The sibling gadget looks up 0b11, whose spread form is 0b101.
+            layouter.assign_region(
+                || "sibling lookup",
+                |mut region| {
+                    for (column, value) in [(sibling.input.tag, 0), (sibling.input.dense, 3), (sibling.input.spread, 5)] {
+                        region.assign_advice(|| "sibling", column, 0, || Value::known(bn256::Fr::from(value)))?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            // Any other table is rejected.
+            let other = SpreadTable {
+                tag: sibling.table.dense,
+                dense: sibling.table.tag,
+                spread: sibling.table.spread,
+            };
+            let result = Table16Chip::load_with_table(table16.clone(), &mut layouter, &other);
+            assert!(matches!(result, Err(Sha512Error::SpreadTableMismatch)));
+
+            let chip = Table16Chip::construct(table16);
+            let digest = OtherSha512::hash_blocks(chip, layouter.namespace(|| "abc"), &blocks_from_bytes(b"abc"))?;
+            assert_digest_eq(&digest.0, ABC_DIGEST);
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn compress_block_chaining() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            // 112 bytes pad to two blocks.
+            let message = [0x61u8; 112];
+            let blocks = blocks_from_bytes(&message);
+            assert_eq!(blocks.len(), 2);
+
+            let mut state = table16_chip.initialization_vector(&mut layouter)?;
+            for block in blocks.iter() {
+                let compressed = table16_chip.compress_block(&mut layouter, &state, *block)?;
+                state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+            }
+            let chained = table16_chip.digest(&mut layouter, &state)?;
+
+            let direct = OtherSha512::hash_blocks(table16_chip, layouter.namespace(|| "direct"), &blocks)?;
+
+            let expected_digest = Sha512::digest(message);
+            for (idx, (chained, direct)) in chained.iter().zip(direct.0.iter()).enumerate() {
+                let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                chained.0.assert_if_known(|word| *word == expected);
+                direct.0.assert_if_known(|word| *word == expected);
+            }
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(4), &circuit, vec![]) {
         Ok(prover) => prover,
         Err(e) => panic!("{:?}", e),
     };
     prover.assert_satisfied();
 }
 
+#[test]
+fn hash_two() {
+    struct MyCircuit;
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Table16Chip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let left = table16_chip.hash_constant(&mut layouter, b"left")?;
+            let right = table16_chip.hash_constant(&mut layouter, b"right")?;
+            let digest = table16_chip.hash_two(&mut layouter, &left, &right)?;
+            for (row, word) in digest.iter().enumerate() {
+                layouter.constrain_instance(word.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    let message = [Sha512::digest(b"left"), Sha512::digest(b"right")].concat();
+    let digest: [u8; 64] = Sha512::digest(&message).as_slice().try_into().unwrap();
+    let instance = circuits::digest_instance(&digest);
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &MyCircuit, vec![instance]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
 
+#[test]
+fn verify_merkle_path() {
+    #[derive(Clone)]
+    struct MyCircuit {
+        leaf_idx: u8,
+        path: Vec<([u8; 64], bool)>,
+        // The two children of the expected root, which is fixed in the circuit as their
+        // digest.
+        root_children: Vec<u8>,
+    }
 
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let words = |bytes: &[u8; 64]| -> [BlockWord; 8] {
+                let mut words = [BlockWord::default(); 8];
+                for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+                    *word = BlockWord(Value::known(u64::from_be_bytes(chunk.try_into().unwrap())));
+                }
+                words
+            };
+            let path: Vec<_> = self
+                .path
+                .iter()
+                .map(|(sibling, sibling_is_left)| (words(sibling), Value::known(*sibling_is_left)))
+                .collect();
+            let leaf = table16_chip.hash_constant(&mut layouter, &[self.leaf_idx])?;
+            let root = table16_chip.hash_constant(&mut layouter, &self.root_children)?;
+            table16_chip.verify_merkle_path(&mut layouter, &leaf, &path, &root)
+        }
+    }
+
+    fn hash_two(left: &[u8; 64], right: &[u8; 64]) -> [u8; 64] {
+        Sha512::digest([*left, *right].concat()).as_slice().try_into().unwrap()
+    }
+
+    // Depth-3 tree over eight leaves.
+    let mut levels: Vec<Vec<[u8; 64]>> = vec![(0u8..8)
+        .map(|i| Sha512::digest([i]).as_slice().try_into().unwrap())
+        .collect()];
+    while levels.last().unwrap().len() > 1 {
+        let level = levels.last().unwrap();
+        let next = level.chunks_exact(2).map(|pair| hash_two(&pair[0], &pair[1])).collect();
+        levels.push(next);
+    }
+    let root_children = levels[2].concat();
+    assert_eq!(Sha512::digest(&root_children).as_slice(), &levels[3][0][..]);
+
+    let leaf_idx = 5;
+    let path: Vec<_> = (0..3)
+        .map(|depth| {
+            let idx = leaf_idx >> depth;
+            (levels[depth][idx ^ 1], idx & 1 == 1)
+        })
+        .collect();
+
+    // Each level hashes a block and its padding block.
+    let k = Table16Config::minimum_k(6);
+    let circuit = MyCircuit { leaf_idx: leaf_idx as u8, path, root_children };
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // Another root is rejected.
+    let mut wrong_root = circuit.clone();
+    wrong_root.root_children[127] ^= 1;
+    let prover = match MockProver::<bn256::Fr>::run(k, &wrong_root, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    let failures = prover.verify().expect_err("a wrong root was accepted");
+    assert!(failures.iter().any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+
+    // So is another leaf, whose path leads to another root.
+    let mut circuit = circuit;
+    circuit.leaf_idx = 4;
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(prover.verify().is_err());
+
+    // Putting the first sibling on the wrong side changes the computed root.
+    circuit.leaf_idx = leaf_idx as u8;
+    circuit.path[0].1 = !circuit.path[0].1;
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn assign_round_manual_loop() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let blocks = blocks_from_bytes(b"abc");
+            let iv = table16_chip.initialization_vector(&mut layouter)?;
+            let initialized_state = table16_chip.initialization(&mut layouter, &iv)?;
+            let w_halves = table16_chip.message_schedule(&mut layouter, blocks[0])?;
+            let compressed = layouter.assign_region(
+                || "compress",
+                |mut region| {
+                    let mut state = initialized_state.clone();
+                    for (round_idx, w_halves) in w_halves.iter().enumerate() {
+                        state = table16_chip.assign_round(&mut region, round_idx, state, w_halves)?;
+                    }
+                    Ok(state)
+                },
+            )?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &iv, &compressed)?;
+            let digest = table16_chip.digest(&mut layouter, &state)?;
+
+            let expected_digest = Sha512::digest(b"abc");
+            for (idx, digest_word) in digest.iter().enumerate() {
+                let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                digest_word.0.assert_if_known(|word| *word == expected);
+            }
+
+            // Round 80, one past the last, is an error rather than a panic.
+            let past_end = layouter.assign_region(
+                || "round 80",
+                |mut region| table16_chip.assign_round(&mut region, 80, initialized_state.clone(), &w_halves[0]),
+            );
+            assert!(matches!(past_end, Err(Error::Synthesis)));
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn digest_cells() {
+    struct MyCircuit {
+        expected: [u64; 8],
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Fixed>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { expected: self.expected }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+            (Table16Chip::configure(meta), constants)
+        }
+
+        fn synthesize(
+            &self,
+            (config, _): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let blocks = blocks_from_bytes(b"abc");
+            let state = table16_chip.initialization_vector(&mut layouter)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+            let digest = table16_chip.digest_cells(&mut layouter, &state)?;
+
+            layouter.assign_region(
+                || "constrain digest",
+                |mut region| {
+                    for (cell, expected) in digest.iter().zip(self.expected.iter()) {
+                        region.constrain_constant(cell.cell(), bn256::Fr::from(*expected))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let digest = Sha512::digest(b"abc");
+    let mut expected = [0u64; 8];
+    for (word, chunk) in expected.iter_mut().zip(digest.chunks_exact(8)) {
+        *word = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+
+    let circuit = MyCircuit { expected };
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // A wrong digest word must not satisfy the copy constraints.
+    expected[7] ^= 1;
+    let circuit = MyCircuit { expected };
+    let prover = MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn digest_bytes() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let blocks = blocks_from_bytes(b"abc");
+            let state = table16_chip.initialization_vector(&mut layouter)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+
+            let expected = hex!("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f");
+            let big = table16_chip.digest_bytes(&mut layouter, &state, Endianness::Big)?;
+            big.assert_if_known(|bytes| *bytes == expected);
+
+            let little = table16_chip.digest_bytes(&mut layouter, &state, Endianness::Little)?;
+            little.assert_if_known(|bytes| {
+                bytes
+                    .chunks_exact(8)
+                    .zip(expected.chunks_exact(8))
+                    .all(|(le, be)| le.iter().eq(be.iter().rev()))
+            });
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn initialize_with_iv() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            assert!(matches!(
+                table16_chip.initialize_with_iv(&mut layouter, &[0; 7]),
+                Err(Sha512Error::BadIvLength(7))
+            ));
+
+            // The digest of "abc" is the chaining value after its single padded block.
+            let abc_digest = Sha512::digest(b"abc");
+            let abc_state: Vec<u64> = abc_digest
+                .chunks_exact(8)
+                .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()))
+                .collect();
+
+            // Resuming from it hashes a message that starts with the padded "abc" block.
+            let mut message = b"abc".to_vec();
+            message.push(0x80);
+            message.resize(112, 0);
+            message.extend_from_slice(&24u128.to_be_bytes());
+            message.extend_from_slice(b"def");
+            let blocks = blocks_from_bytes(&message);
+            assert_eq!(blocks.len(), 2);
+
+            let state = table16_chip.initialize_with_iv(&mut layouter, &abc_state)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[1])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+            let digest = table16_chip.digest(&mut layouter, &state)?;
+
+            let expected_digest = Sha512::digest(&message);
+            for (idx, digest_word) in digest.iter().enumerate() {
+                let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                digest_word.0.assert_if_known(|word| *word == expected);
+            }
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn sha512_t() {
+    use sha512_halo2::reference::sha512_t_iv;
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            // SHA-512/t is SHA-512 from a derived IV, truncated to its first t bits.
+            let blocks = blocks_from_bytes(b"abc");
+            for (t, expected) in [
+                (224, sha2::Sha512_224::digest(b"abc").to_vec()),
+                (256, sha2::Sha512_256::digest(b"abc").to_vec()),
+            ] {
+                let state = table16_chip.initialize_with_iv(&mut layouter, &sha512_t_iv(t))?;
+                let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+                let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+                let digest = table16_chip.digest_bytes(&mut layouter, &state, Endianness::Big)?;
+                digest.assert_if_known(|digest| digest[..expected.len()] == expected[..]);
+            }
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+/// Hashes "abc" with `variant` and checks the truncated digest against `expected`, in the
+/// circuit: the cells returned are constrained to an instance column holding it.
+fn check_variant(variant: Sha512Variant, expected: &[u8]) {
+    struct MyCircuit {
+        variant: Sha512Variant,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { variant: self.variant }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Table16Chip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let blocks = blocks_from_bytes(b"abc");
+            let digest = table16_chip.hash_variant(&mut layouter, self.variant, &blocks)?;
+            assert_eq!(digest.words.len(), self.variant.output_bits() / 64);
+            assert_eq!(digest.last_half.is_some(), self.variant == Sha512Variant::Sha512_224);
+            for (row, cell) in digest.cells().into_iter().enumerate() {
+                layouter.constrain_instance(cell, instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    // The last word of SHA-512/224 only contributes its upper half, as a 32-bit cell.
+    assert_eq!(expected.len() * 8, variant.output_bits());
+    let instance: Vec<bn256::Fr> = expected
+        .chunks(8)
+        .map(|chunk| {
+            let mut bytes = [0; 8];
+            bytes[8 - chunk.len()..].copy_from_slice(chunk);
+            bn256::Fr::from(u64::from_be_bytes(bytes))
+        })
+        .collect();
+
+    let circuit = MyCircuit { variant };
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![instance.clone()]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // The truncated digest is bound: another last word, or half, is rejected.
+    let mut wrong = instance;
+    let last = wrong.len() - 1;
+    wrong[last] += bn256::Fr::one();
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![wrong]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn variant_sha512() {
+    check_variant(Sha512Variant::Sha512, &Sha512::digest(b"abc"));
+}
+
+#[test]
+fn variant_sha384() {
+    check_variant(Sha512Variant::Sha384, &sha2::Sha384::digest(b"abc"));
+}
+
+#[test]
+fn variant_sha512_256() {
+    check_variant(Sha512Variant::Sha512_256, &sha2::Sha512_256::digest(b"abc"));
+}
+
+#[test]
+fn variant_sha512_224() {
+    check_variant(Sha512Variant::Sha512_224, &sha2::Sha512_224::digest(b"abc"));
+}
+
+#[test]
+fn batch_hash() {
+    const MESSAGES: [&[u8]; 3] = [b"abc", b"", &[0x62; 200]];
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let inputs: Vec<_> = MESSAGES.iter().map(|message| blocks_from_bytes(message)).collect();
+            let digests = table16_chip.batch_hash(&mut layouter, &inputs)?;
+            assert_eq!(digests.len(), MESSAGES.len());
+
+            for (digest, message) in digests.iter().zip(MESSAGES.iter()) {
+                let expected_digest = Sha512::digest(message);
+                for (idx, digest_word) in digest.iter().enumerate() {
+                    let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                    digest_word.0.assert_if_known(|word| *word == expected);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let num_blocks = MESSAGES.iter().map(|message| blocks_from_bytes(message).len()).sum();
+    assert_eq!(num_blocks, 4);
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(num_blocks), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn hash_field_elements() {
+    struct MyCircuit {
+        elements: Vec<bn256::Fr>,
+        expected: [u8; 64],
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { elements: self.elements.clone(), expected: self.expected }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let elements = meta.advice_column();
+            meta.enable_equality(elements);
+            (Table16Chip::configure(meta), elements)
+        }
+
+        fn synthesize(
+            &self,
+            (config, column): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let elements = layouter.assign_region(
+                || "elements",
+                |mut region| {
+                    self.elements
+                        .iter()
+                        .enumerate()
+                        .map(|(row, element)| {
+                            region.assign_advice(|| "element", column, row, || Value::known(*element))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let digest = table16_chip.hash_field_elements(&mut layouter, &elements)?;
+
+            for (idx, digest_word) in digest.iter().enumerate() {
+                let expected = u64::from_be_bytes(self.expected[8 * idx..8 * idx + 8].try_into().unwrap());
+                digest_word.0.assert_if_known(|word| *word == expected);
+            }
+            Ok(())
+        }
+    }
+
+    // p - 1 is the largest canonical element and needs the borrow in the canonicity
+    // check; the small element has an all-zero upper half.
+    let max = -bn256::Fr::one();
+    let max_bytes = hex!("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000");
+    let small = 0x0123456789abcdeffedcba9876543210u128;
+    let mut small_bytes = [0u8; 32];
+    small_bytes[16..].copy_from_slice(&small.to_be_bytes());
+
+    for (elements, message) in [
+        (vec![max], max_bytes.to_vec()),
+        (vec![bn256::Fr::from_u128(small), max], [small_bytes, max_bytes].concat()),
+    ] {
+        let circuit = MyCircuit {
+            elements,
+            expected: Sha512::digest(&message).as_slice().try_into().unwrap(),
+        };
+        let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+}
+
+#[test]
+fn absorb_and_squeeze() {
+    use halo2_proofs::halo2curves::group::ff::PrimeField;
+
+    struct MyCircuit {
+        elements: Vec<bn256::Fr>,
+        expected: [u8; 32],
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { elements: self.elements.clone(), expected: self.expected }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let elements = meta.advice_column();
+            meta.enable_equality(elements);
+            (Table16Chip::configure(meta), elements)
+        }
+
+        fn synthesize(
+            &self,
+            (config, column): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let elements = layouter.assign_region(
+                || "elements",
+                |mut region| {
+                    self.elements
+                        .iter()
+                        .enumerate()
+                        .map(|(row, element)| {
+                            region.assign_advice(|| "element", column, row, || Value::known(*element))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let challenge = table16_chip.absorb_and_squeeze(&mut layouter, &elements)?;
+
+            for (word, expected) in challenge.iter().zip(self.expected.chunks(8)) {
+                let expected = u64::from_be_bytes(expected.try_into().unwrap());
+                word.value().map(|bits| u64::from(bits)).assert_if_known(|word| *word == expected);
+            }
+            Ok(())
+        }
+    }
+
+    // The challenge is the first half of SHA-512 over the big-endian encodings.
+    let elements = vec![bn256::Fr::one(), bn256::Fr::from(0xdeadbeef), -bn256::Fr::one()];
+    let mut message = Vec::new();
+    for element in elements.iter() {
+        let mut bytes = element.to_repr();
+        bytes.as_mut().reverse();
+        message.extend_from_slice(bytes.as_ref());
+    }
+    let circuit = MyCircuit {
+        elements,
+        expected: Sha512::digest(&message)[..32].try_into().unwrap(),
+    };
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn hash_with_domain() {
+    const MESSAGE: [u64; 3] = [0x6d65737361676521, 0x0123456789abcdef, 0xfedcba9876543210];
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let message = MESSAGE.map(|word| BlockWord(Value::known(word)));
+            let message_bytes: Vec<u8> = MESSAGE.iter().flat_map(|word| word.to_be_bytes()).collect();
+
+            let mut digests = vec![];
+            for domain in [&b"ctxA"[..], &b"ctxB"[..], &b"ctx"[..], &b"ctx\0"[..]] {
+                let digest = table16_chip.hash_with_domain(&mut layouter, domain, &message)?;
+
+                // The tag follows its length and is zero-padded to a whole block.
+                let mut tagged = vec![domain.len() as u8];
+                tagged.extend_from_slice(domain);
+                tagged.resize(BLOCK_SIZE * 8, 0);
+                tagged.extend_from_slice(&message_bytes);
+                let expected_digest = Sha512::digest(&tagged);
+                for (idx, digest_word) in digest.iter().enumerate() {
+                    let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                    digest_word.0.assert_if_known(|word| *word == expected);
+                }
+                digests.push(digest);
+            }
+
+            // Neither different tags nor tags differing in a trailing zero byte collide.
+            for (a, b) in [(&digests[0], &digests[1]), (&digests[2], &digests[3])] {
+                let differ = a
+                    .iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| a.0.zip(b.0).map(|(a, b)| a != b))
+                    .fold(Value::known(false), |acc, differ| acc.zip(differ).map(|(acc, differ)| acc || differ));
+                differ.assert_if_known(|differ| *differ);
+            }
+
+            // Tags that leave no room for their length in the tag block are rejected.
+            assert!(matches!(
+                table16_chip.hash_with_domain(&mut layouter, &[0; BLOCK_SIZE * 8], &message),
+                Err(Sha512Error::DomainTooLong { len: 128, max: 127 })
+            ));
+            Ok(())
+        }
+    }
+
+    // Each hash is the tag block, the message and its padding: two blocks.
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(8), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn length_extension() {
+    // A "MAC" computed as H(secret || message), as in a naive signed request.
+    const SECRET_AND_MESSAGE: [u64; 3] = [0x7365637265742121, 0x616d6f756e743d31, 0x3030202020202020];
+    const EXTENSION: u64 = 0x26616d743d393939;
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let original: Vec<u8> = SECRET_AND_MESSAGE.iter().flat_map(|word| word.to_be_bytes()).collect();
+            let tag = Sha512::digest(&original);
+
+            // The attacker knows the tag and the original length, but not the secret. The
+            // glue padding is that of the original message, and the length in the forged
+            // block's padding counts everything before it.
+            let mut extended = original.clone();
+            extended.push(0x80);
+            extended.resize(112, 0);
+            extended.extend_from_slice(&(original.len() as u128 * 8).to_be_bytes());
+            extended.extend_from_slice(&EXTENSION.to_be_bytes());
+            let blocks = blocks_from_bytes(&extended);
+            assert_eq!(blocks.len(), 2);
+
+            let tag_words: Vec<u64> = tag
+                .chunks_exact(8)
+                .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()))
+                .collect();
+            let state = table16_chip.initialize_with_iv(&mut layouter, &tag_words)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[1])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+            let forged = table16_chip.digest(&mut layouter, &state)?;
+
+            // The forgery is the valid tag of the extended message.
+            let expected_digest = Sha512::digest(&extended);
+            for (idx, digest_word) in forged.iter().enumerate() {
+                let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                digest_word.0.assert_if_known(|word| *word == expected);
+            }
+
+            // With the length at the end, the extended message is hashed with its own
+            // length appended, and the forgery no longer verifies.
+            let extended_words: Vec<BlockWord> = extended
+                .chunks_exact(8)
+                .map(|chunk| BlockWord(Value::known(u64::from_be_bytes(chunk.try_into().unwrap()))))
+                .collect();
+            let suffixed = table16_chip.hash_with_length_suffix(&mut layouter, &extended_words)?;
+
+            let mut suffixed_message = extended.clone();
+            suffixed_message.extend_from_slice(&(extended.len() as u128 * 8).to_be_bytes());
+            let expected_digest = Sha512::digest(&suffixed_message);
+            for (idx, digest_word) in suffixed.iter().enumerate() {
+                let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                digest_word.0.assert_if_known(|word| *word == expected);
+            }
+
+            let differ = forged
+                .iter()
+                .zip(suffixed.iter())
+                .map(|(a, b)| a.0.zip(b.0).map(|(a, b)| a != b))
+                .fold(Value::known(false), |acc, differ| acc.zip(differ).map(|(acc, differ)| acc || differ));
+            differ.assert_if_known(|differ| *differ);
+            Ok(())
+        }
+    }
+
+    // One forged block, and two blocks for the extended message with its length suffix.
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(3), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn hash_dynamic() {
+    use std::cell::RefCell;
+
+    const MAX_BLOCKS: usize = 3;
+
+    struct MyCircuit {
+        message: Value<Vec<u8>>,
+        expected_length: u64,
+        expected: [u64; 8],
+        error: RefCell<Option<Sha512Error>>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Fixed>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: Value::unknown(),
+                expected_length: self.expected_length,
+                expected: self.expected,
+                error: RefCell::new(None),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+            (Table16Chip::configure(meta), constants)
+        }
+
+        fn synthesize(
+            &self,
+            (config, _): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let (length, digest) = match table16_chip.hash_dynamic::<MAX_BLOCKS>(&mut layouter, self.message.clone()) {
+                Ok(hashed) => hashed,
+                Err(err) => {
+                    *self.error.borrow_mut() = Some(err);
+                    return Err(Error::Synthesis);
+                }
+            };
+
+            layouter.assign_region(
+                || "constrain digest",
+                |mut region| {
+                    region.constrain_constant(length.cell(), bn256::Fr::from(self.expected_length))?;
+                    for (cell, expected) in digest.iter().zip(self.expected.iter()) {
+                        region.constrain_constant(cell.cell(), bn256::Fr::from(*expected))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let circuit = |message: &[u8]| {
+        let digest = Sha512::digest(message);
+        let mut expected = [0u64; 8];
+        for (word, chunk) in expected.iter_mut().zip(digest.chunks_exact(8)) {
+            *word = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        MyCircuit {
+            message: Value::known(message.to_vec()),
+            expected_length: message.len() as u64,
+            expected,
+            error: RefCell::new(None),
+        }
+    };
+    let k = Table16Config::minimum_k(MAX_BLOCKS + 1);
+
+    // One, two and three active blocks, on both sides of the boundaries where the
+    // padding spills into another block, up to the largest message that fits.
+    for length in [0, 111, 112, 240, 367] {
+        let message: Vec<u8> = (0..length).map(|idx| idx as u8).collect();
+        let prover = match MockProver::<bn256::Fr>::run(k, &circuit(&message[..]), vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+
+    // The length is bound to the padding.
+    let mut wrong_length = circuit(&b"abc"[..]);
+    wrong_length.expected_length = 4;
+    let prover = MockProver::<bn256::Fr>::run(k, &wrong_length, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // A message whose padding does not fit is rejected, with its length and the bound.
+    let too_long = circuit(&[0; 368][..]);
+    assert!(MockProver::<bn256::Fr>::run(k, &too_long, vec![]).is_err());
+    match too_long.error.into_inner() {
+        Some(Sha512Error::MessageTooLong { len, max }) => assert_eq!((len, max), (368, 367)),
+        other => panic!("expected MessageTooLong, got {:?}", other),
+    }
+}
+
+#[test]
+fn hash_constant() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Fixed>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+            (Table16Chip::configure(meta), constants)
+        }
+
+        fn synthesize(
+            &self,
+            (config, _): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            // The full circuit.
+            let blocks = blocks_from_bytes(b"abc");
+            let state = table16_chip.initialization_vector(&mut layouter)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+            let digest = table16_chip.digest_cells(&mut layouter, &state)?;
+
+            // The constant-folded digest, in eight rows.
+            let folded = table16_chip.hash_constant(&mut layouter, b"abc")?;
+
+            layouter.assign_region(
+                || "compare digests",
+                |mut region| {
+                    for (word, folded) in digest.iter().zip(folded.iter()) {
+                        region.constrain_equal(word.cell(), folded.cell())?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn hash_rows() {
+    assert_eq!(Table16Config::hash_rows(1), 6000);
+    assert_eq!(Table16Config::hash_rows(3), 3 * 5960 + 40);
+
+    // Fills every advice row left after hashing `message` with a region spanning all
+    // the chip's columns, so the floor planner must place it after the hash.
+    struct MyCircuit {
+        message: Vec<u8>,
+        filler_rows: usize,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Vec<Column<Advice>>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { message: self.message.clone(), filler_rows: self.filler_rows }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let message_schedule = meta.advice_column();
+            let extras = [(); 6].map(|_| meta.advice_column());
+            let lookup_inputs = [(); 3].map(|_| meta.advice_column());
+            let config = Table16Chip::configure_with_columns(meta, message_schedule, extras, lookup_inputs);
+
+            let mut columns = vec![message_schedule];
+            columns.extend(extras);
+            columns.extend(lookup_inputs);
+            (config, columns)
+        }
+
+        fn synthesize(
+            &self,
+            (config, columns): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let blocks = blocks_from_bytes(&self.message);
+            OtherSha512::hash_blocks(table16_chip, layouter.namespace(|| "message"), &blocks)?;
+
+            layouter.assign_region(
+                || "filler",
+                |mut region| {
+                    for column in columns.iter() {
+                        for row in [0, self.filler_rows - 1] {
+                            region.assign_advice(|| "filler", *column, row, || Value::known(bn256::Fr::zero()))?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let mut meta = ConstraintSystem::<bn256::Fr>::default();
+    MyCircuit::configure(&mut meta);
+
+    // The hash fits in its documented number of rows, so the regions of consecutive
+    // blocks are placed without gaps.
+    for (message, num_blocks) in [(b"abc".to_vec(), 1), (vec![0x61; 300], 3)] {
+        assert_eq!(blocks_from_bytes(&message).len(), num_blocks);
+        let k = Table16Config::minimum_k(num_blocks);
+        let usable_rows = (1 << k) - (meta.blinding_factors() + 1);
+
+        let filler_rows = usable_rows - Table16Config::hash_rows(num_blocks);
+        let circuit = MyCircuit { message, filler_rows };
+        let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+}
+
+#[test]
+fn sha512_hasher_streaming() {
+    // Updates that end inside a block, on a block boundary and across several blocks.
+    const LONG: [usize; 4] = [50, 78, 150, 3];
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+
+            let long: Vec<u8> = (0..LONG.iter().sum::<usize>()).map(|idx| idx as u8).collect();
+            let mut long_chunks = vec![];
+            let mut rest = &long[..];
+            for len in LONG {
+                let (chunk, tail) = rest.split_at(len);
+                long_chunks.push(chunk);
+                rest = tail;
+            }
+
+            let cases: [(&[u8], Vec<&[u8]>); 3] = [
+                (&b"abc"[..], vec![&b"ab"[..], &b"c"[..]]),
+                (&b"abc"[..], vec![&b"abc"[..]]),
+                (&long[..], long_chunks),
+            ];
+            for (message, chunks) in cases {
+                let mut hasher = Sha512Hasher::new(config.clone());
+                for chunk in chunks {
+                    hasher.update(&mut layouter, chunk)?;
+                }
+                let digest = hasher.finalize(&mut layouter)?;
+
+                let expected_digest = Sha512::digest(message);
+                for (idx, digest_word) in digest.iter().enumerate() {
+                    let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                    digest_word.0.assert_if_known(|word| *word == expected);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    // One block for each "abc" hash, and three for the 281-byte message.
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(5), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn select_digest() {
+    struct MyCircuit {
+        cond: u64,
+        expected: [u64; 8],
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Advice>, Column<Fixed>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { cond: self.cond, expected: self.expected }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let cond = meta.advice_column();
+            meta.enable_equality(cond);
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+            (Table16Chip::configure(meta), cond, constants)
+        }
+
+        fn synthesize(
+            &self,
+            (config, cond_column, _): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let mut digests = vec![];
+            for message in [&b"abc"[..], &b"ab"[..]] {
+                let blocks = blocks_from_bytes(message);
+                let state = table16_chip.initialization_vector(&mut layouter)?;
+                let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+                let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+                digests.push(table16_chip.digest_cells(&mut layouter, &state)?);
+            }
+            let b = digests.pop().unwrap();
+            let a = digests.pop().unwrap();
+
+            let cond = layouter.assign_region(
+                || "cond",
+                |mut region| {
+                    region.assign_advice(|| "cond", cond_column, 0, || Value::known(bn256::Fr::from(self.cond)))
+                },
+            )?;
+            let selected = table16_chip.select_digest(&mut layouter, cond, a, b)?;
+
+            layouter.assign_region(
+                || "constrain selected",
+                |mut region| {
+                    for (cell, expected) in selected.iter().zip(self.expected.iter()) {
+                        region.constrain_constant(cell.cell(), bn256::Fr::from(*expected))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    let words = |message: &[u8]| {
+        let digest = Sha512::digest(message);
+        let mut words = [0u64; 8];
+        for (word, chunk) in words.iter_mut().zip(digest.chunks_exact(8)) {
+            *word = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        words
+    };
+
+    for (cond, expected) in [(1, words(b"abc")), (0, words(b"ab"))] {
+        let circuit = MyCircuit { cond, expected };
+        let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+    }
+
+    // The other branch, or a condition that is not a bit, must not satisfy the circuit.
+    for (cond, expected) in [(1, words(b"ab")), (2, words(b"ab"))] {
+        let circuit = MyCircuit { cond, expected };
+        let prover = MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
+
+#[test]
+fn constrain_digests_equal() {
+    struct MyCircuit {
+        messages: [&'static [u8]; 2],
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { messages: self.messages }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let mut digests = vec![];
+            for message in self.messages {
+                let blocks = blocks_from_bytes(message);
+                let state = table16_chip.initialization_vector(&mut layouter)?;
+                let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+                let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+                digests.push(table16_chip.digest_cells(&mut layouter, &state)?);
+            }
+
+            table16_chip.constrain_digests_equal(&mut layouter, &digests[0], &digests[1])
+        }
+    }
+
+    let circuit = MyCircuit { messages: [b"abc", b"abc"] };
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // Different digests break the copy constraints.
+    let circuit = MyCircuit { messages: [b"abc", b"ab"] };
+    let prover = MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn digest_hex_round_trip() {
+    const ABC: &str = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+
+    let digest = digest_from_hex(ABC).unwrap();
+    assert_eq!(digest_hex(&digest).unwrap(), ABC);
+    digest[0].0.assert_if_known(|word| *word == 0xddaf35a193617aba);
+    assert_eq!(digest_hex(&digest_from_hex(&ABC.to_uppercase()).unwrap()).unwrap(), ABC);
+
+    // Unknown words have no encoding.
+    let mut unknown = digest;
+    unknown[3] = BlockWord(Value::unknown());
+    assert!(matches!(digest_hex(&unknown), Err(Sha512Error::UnknownDigestValue)));
+
+    // Wrong lengths and non-hex digits are rejected.
+    assert!(digest_from_hex(&ABC[1..]).is_none());
+    assert!(digest_from_hex(&format!("{}0", ABC)).is_none());
+    assert!(digest_from_hex(&format!("+{}", &ABC[1..])).is_none());
+    assert!(digest_from_hex(&format!("g{}", &ABC[1..])).is_none());
+}
+
+#[test]
+fn digest_methods() {
+    use sha512_halo2::sha512::Digest as Sha512Output;
+
+    let words = digest_from_hex(ABC_DIGEST).unwrap();
+    let digest = Sha512Output::from(words);
+    assert_eq!(digest.to_hex().unwrap(), ABC_DIGEST);
+    assert_eq!(digest.to_bytes().unwrap()[..], Sha512::digest(b"abc")[..]);
+    digest.as_words()[7].0.assert_if_known(|word| *word == 0x2a9ac94fa54ca49f);
+    assert!(digest == Sha512Output::from(words));
+
+    // The raw words convert back unchanged.
+    let raw: [BlockWord; 8] = digest.into();
+    assert_eq!(digest_hex(&raw).unwrap(), ABC_DIGEST);
+
+    // Digests with a different or an unknown word are not equal.
+    let mut other = words;
+    other[0] = BlockWord(Value::known(0));
+    assert!(Sha512Output::from(words) != Sha512Output::from(other));
+    let mut unknown = words;
+    unknown[3] = BlockWord(Value::unknown());
+    let unknown = Sha512Output::from(unknown);
+    assert!(matches!(unknown.to_bytes(), Err(Sha512Error::UnknownDigestValue)));
+    assert!(unknown != Sha512Output::from(unknown.0));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_round_trip() {
+    use sha512_halo2::sha512::Sha512Digest;
+
+    const ABC: &str = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+
+    // A digest is a single hex string.
+    let digest = Sha512Digest(digest_from_hex(ABC).unwrap());
+    let json = serde_json::to_string(&digest).unwrap();
+    assert_eq!(json, format!("\"{}\"", ABC));
+    let decoded: Sha512Digest<BlockWord> = serde_json::from_str(&json).unwrap();
+    assert_eq!(digest_hex(&decoded.0).unwrap(), ABC);
+
+    // Words are hex strings of their own.
+    let json = serde_json::to_string(&digest.0).unwrap();
+    assert!(json.starts_with("[\"ddaf35a193617aba\",\"cc417349ae204131\""));
+    let decoded: [BlockWord; 8] = serde_json::from_str(&json).unwrap();
+    assert_eq!(digest_hex(&decoded).unwrap(), ABC);
+
+    // Unknown values cannot be serialized, and malformed strings are rejected.
+    assert!(serde_json::to_string(&BlockWord(Value::unknown())).is_err());
+    assert!(serde_json::from_str::<BlockWord>("\"ddaf35a193617ab\"").is_err());
+    assert!(serde_json::from_str::<BlockWord>("\"+daf35a193617aba\"").is_err());
+    assert!(serde_json::from_str::<Sha512Digest<BlockWord>>(&format!("\"{}\"", &ABC[1..])).is_err());
+}
+
+#[test]
+fn spread_digest_xor() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let iv = table16_chip.initialization_vector(&mut layouter)?;
+            let mut spread_digests = Vec::new();
+            for message in [&b"abc"[..], &b"abd"[..]] {
+                let block = blocks_from_bytes(message)[0];
+                let compressed = table16_chip.compress_block(&mut layouter, &iv, block)?;
+                let (_, spread) =
+                    table16_chip.add_feed_forward_spread(&mut layouter, &iv, &compressed)?;
+                spread_digests.push(spread);
+            }
+
+            // The even bits of the sum of two spread words are their XOR.
+            let even_bits = |spread: u128| {
+                (0..64).fold(0u64, |acc, i| acc | (((spread >> (2 * i)) & 1) as u64) << i)
+            };
+            let expected: Vec<u8> = Sha512::digest(b"abc")
+                .iter()
+                .zip(Sha512::digest(b"abd").iter())
+                .map(|(a, b)| a ^ b)
+                .collect();
+            for (idx, (a, b)) in spread_digests[0].iter().zip(&spread_digests[1]).enumerate() {
+                a.value().zip(b.value()).assert_if_known(|(a, b)| {
+                    even_bits(a + b).to_be_bytes()[..] == expected[8 * idx..8 * idx + 8]
+                });
+            }
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(2), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn hash_no_blocks() {
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {}
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let variant = table16_chip.hash_variant(&mut layouter, Sha512Variant::Sha384, &[]);
+            assert!(matches!(variant, Err(Sha512Error::BadBlockCount(0))));
+
+            let result =
+                OtherSha512::hash_blocks(table16_chip, layouter.namespace(|| "no blocks"), &[]);
+            assert!(matches!(result, Err(Sha512Error::BadBlockCount(0))));
+            // Other errors still surface as a synthesis error inside a circuit.
+            assert!(matches!(Error::from(result.unwrap_err()), Error::Synthesis));
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn hash_blocks_with_intermediates() {
+    // Each message extends the padded bytes of the previous one, so the first i + 1
+    // blocks of the last message are the previous message padded, and the chaining
+    // value after block i is its digest.
+    let mut messages: Vec<Vec<u8>> = Vec::new();
+    let mut message = Vec::new();
+    for tail in [&b"first block"[..], b"second block", b"third block"] {
+        message.extend_from_slice(tail);
+        messages.push(message.clone());
+        message.clear();
+        for word in blocks_from_bytes(messages.last().unwrap()).iter().flatten() {
+            word.0.map(|word| message.extend_from_slice(&word.to_be_bytes()));
+        }
+    }
+    let blocks = blocks_from_bytes(messages.last().unwrap());
+    assert_eq!(blocks.len(), 3);
+
+    struct MyCircuit {
+        blocks: Vec<[BlockWord; BLOCK_SIZE]>,
+        expected: Vec<Vec<u8>>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                blocks: vec![[BlockWord::default(); BLOCK_SIZE]; self.blocks.len()],
+                expected: self.expected.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let intermediates =
+                table16_chip.hash_blocks_with_intermediates(&mut layouter, &self.blocks)?;
+            assert_eq!(intermediates.len(), self.expected.len());
+            for (digest, expected) in intermediates.iter().zip(self.expected.iter()) {
+                for (word, expected) in digest.iter().zip(expected.chunks_exact(8)) {
+                    word.0.assert_if_known(|word| &word.to_be_bytes()[..] == expected);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {
+        blocks,
+        expected: messages.iter().map(|message| Sha512::digest(message).to_vec()).collect(),
+    };
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(3), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn synthesize_digest_only() {
+    struct MyCircuit {
+        expected: [u64; 8],
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { expected: self.expected }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Table16Chip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+
+            let digest = config.synthesize_digest_only(&mut layouter, instance)?;
+            for (word, expected) in digest.iter().zip(self.expected.iter()) {
+                word.0.assert_if_known(|word| word == expected);
+            }
+            Ok(())
+        }
+    }
+
+    // The state is eight public words, here the digest of "abc".
+    let mut expected = [0u64; 8];
+    for (word, chunk) in expected.iter_mut().zip(Sha512::digest(b"abc").chunks_exact(8)) {
+        *word = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    let instance: Vec<_> = expected.iter().map(|word| bn256::Fr::from(*word)).collect();
+
+    let circuit = MyCircuit { expected };
+    let prover = match MockProver::<bn256::Fr>::run(
+        Table16Config::minimum_k(1),
+        &circuit,
+        vec![instance.clone()],
+    ) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // A public word of more than 64 bits cannot be a state word.
+    let mut instance = instance;
+    instance[3] += bn256::Fr::from_u128(1 << 64);
+    let prover =
+        MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![instance]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn feed_forward_wraps() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            // The feed-forward of the second block overflows 2^64 in words D, F and H.
+            let message = [0x61u8; 112];
+            let blocks = blocks_from_bytes(&message);
+            assert_eq!(blocks.len(), 2);
+
+            let state = table16_chip.initialization_vector(&mut layouter)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+            let prev = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &prev, blocks[1])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &prev, &compressed)?;
+
+            // The digest of a state is its words.
+            let prev = table16_chip.digest(&mut layouter, &prev)?;
+            let compressed = table16_chip.digest(&mut layouter, &compressed)?;
+            let chained = table16_chip.digest(&mut layouter, &state)?;
+
+            let expected_digest = Sha512::digest(message);
+            let mut wrapped = Value::known(Vec::new());
+            for (idx, ((prev, compressed), chained)) in
+                prev.iter().zip(compressed.iter()).zip(chained.iter()).enumerate()
+            {
+                let expected = u64::from_be_bytes(expected_digest[8 * idx..8 * idx + 8].try_into().unwrap());
+                chained.0.assert_if_known(|word| *word == expected);
+                prev.0
+                    .zip(compressed.0)
+                    .zip(chained.0)
+                    .assert_if_known(|((prev, compressed), chained)| {
+                        *chained == prev.wrapping_add(*compressed)
+                    });
+
+                let carry = prev.0.zip(compressed.0).map(|(prev, compressed)| {
+                    prev.checked_add(compressed).is_none()
+                });
+                wrapped = wrapped.zip(carry).map(|(mut wrapped, carry)| {
+                    if carry {
+                        wrapped.push(idx);
+                    }
+                    wrapped
+                });
+            }
+            wrapped.assert_if_known(|wrapped| wrapped == &[3, 5, 7]);
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(4), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn expose_digest_packed() {
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Table16Chip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let blocks = blocks_from_bytes(b"abc");
+            let state = table16_chip.initialization_vector(&mut layouter)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+            let digest = table16_chip.digest_cells(&mut layouter, &state)?;
+
+            table16_chip.expose_digest_packed(&mut layouter, &digest, instance)?;
+            Ok(())
+        }
+    }
+
+    let digest: [u8; 64] = Sha512::digest(b"abc").as_slice().try_into().unwrap();
+    let packed = circuits::packed_digest_instance(&digest);
+
+    // Bytes 0..24, 24..48 and 48..64 of the digest, big-endian.
+    let be = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .fold(bn256::Fr::zero(), |sum, byte| sum * bn256::Fr::from(256) + bn256::Fr::from(*byte as u64))
+    };
+    assert_eq!(packed, vec![be(&digest[0..24]), be(&digest[24..48]), be(&digest[48..64])]);
+    assert_eq!(
+        packed[2],
+        bn256::Fr::from_u128(u128::from_be_bytes(digest[48..64].try_into().unwrap()))
+    );
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![packed.clone()]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // The elements are not interchangeable.
+    let mut swapped = packed;
+    swapped.swap(0, 1);
+    let prover = MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![swapped]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn digest_as_field_elements() {
+    use halo2_proofs::halo2curves::group::ff::PrimeField;
+
+    #[derive(Default)]
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Table16Chip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let blocks = blocks_from_bytes(b"abc");
+            let state = table16_chip.initialization_vector(&mut layouter)?;
+            let compressed = table16_chip.compress_block(&mut layouter, &state, blocks[0])?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &state, &compressed)?;
+
+            let elements = table16_chip.digest_as_field_elements(&mut layouter, &state)?;
+            assert_eq!(elements.len(), 3);
+            for (row, element) in elements.iter().enumerate() {
+                layouter.constrain_instance(element.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    let digest: [u8; 64] = Sha512::digest(b"abc").as_slice().try_into().unwrap();
+    let elements = circuits::packed_digest_instance(&digest);
+
+    // The elements are below 2^192, so their little-endian encodings give back the digest
+    // bytes, and no other digest packs to the same elements.
+    let mut bytes = Vec::new();
+    for (element, len) in elements.iter().zip([24, 24, 16]) {
+        let repr = element.to_repr();
+        assert!(repr[len..].iter().all(|byte| *byte == 0));
+        bytes.extend(repr[..len].iter().rev());
+    }
+    assert_eq!(bytes, digest);
+
+    let circuit = MyCircuit {};
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![elements.clone()]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // The elements of a digest differing in its last bit are rejected.
+    let mut flipped = digest;
+    flipped[63] ^= 1;
+    let prover = MockProver::<bn256::Fr>::run(
+        Table16Config::minimum_k(1),
+        &circuit,
+        vec![circuits::packed_digest_instance(&flipped)],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Hashes `message` with the `table16` backend under MockProver and returns the digest
+/// words it computes.
+fn table16_digest(message: &[u8]) -> [u64; 8] {
+    use std::cell::RefCell;
+
+    struct MyCircuit {
+        blocks: Vec<[BlockWord; BLOCK_SIZE]>,
+        digest: RefCell<[u64; 8]>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                blocks: self.blocks.clone(),
+                digest: RefCell::new([0; 8]),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let digest = OtherSha512::hash_blocks(table16_chip, layouter.namespace(|| "hash"), &self.blocks)?;
+            for (word, out) in digest.0.iter().zip(self.digest.borrow_mut().iter_mut()) {
+                word.0.map(|word| *out = word);
+            }
+            Ok(())
+        }
+    }
+
+    let blocks = blocks_from_bytes(message);
+    let k = Table16Config::minimum_k(blocks.len());
+    let circuit = MyCircuit { blocks, digest: RefCell::new([0; 8]) };
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+    circuit.digest.into_inner()
+}
+
+#[test]
+fn backends_agree() {
+    // Every backend built into the crate, checked against each other and against the
+    // reference implementation. Only `table16` exists so far; a further backend is added
+    // to this list once it has a chip.
+    let backends: [(&str, fn(&[u8]) -> [u64; 8]); 1] = [("table16", table16_digest)];
+
+    let message = b"abc";
+    let expected = sha512_halo2::reference::reference_sha512(message);
+    let expected: Vec<u64> = expected
+        .chunks_exact(8)
+        .map(|word| u64::from_be_bytes(word.try_into().unwrap()))
+        .collect();
+
+    let digests: Vec<_> = backends.iter().map(|(name, digest)| (name, digest(message))).collect();
+    for (name, digest) in digests.iter() {
+        assert_eq!(&digest[..], &expected[..], "{} disagrees with the reference", name);
+    }
+    for pair in digests.windows(2) {
+        assert_eq!(pair[0].1, pair[1].1, "{} and {} disagree", pair[0].0, pair[1].0);
+    }
+}
+
+#[test]
+fn hash_partially_public() {
+    use sha512_halo2::sha512::Visibility;
+
+    // A public first block, followed by a secret one.
+    struct MyCircuit {
+        prefix: [u64; BLOCK_SIZE],
+        secret: Value<[u64; BLOCK_SIZE]>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                prefix: self.prefix,
+                secret: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+            (Table16Chip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let public = self
+                .prefix
+                .iter()
+                .map(|word| (BlockWord(Value::known(*word)), Visibility::Public));
+            let private = (0..BLOCK_SIZE)
+                .map(|idx| (BlockWord(self.secret.map(|secret| secret[idx])), Visibility::Private));
+            let message: Vec<_> = public.chain(private).collect();
+
+            let digest = table16_chip.hash_partially_public(&mut layouter, &message)?;
+            table16_chip.expose_digest_packed(&mut layouter, &digest, instance)?;
+            Ok(())
+        }
+    }
+
+    let words = |bytes: &[u8]| -> [u64; BLOCK_SIZE] {
+        let mut words = [0; BLOCK_SIZE];
+        for (word, bytes) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = u64::from_be_bytes(bytes.try_into().unwrap());
+        }
+        words
+    };
+    let prefix = [0x70u8; 128];
+    let secret = [0x73u8; 128];
+    let message = [&prefix[..], &secret[..]].concat();
+    let digest: [u8; 64] = Sha512::digest(&message).as_slice().try_into().unwrap();
+    let instance = circuits::packed_digest_instance(&digest);
+
+    // Two message blocks and a padding block.
+    let k = Table16Config::minimum_k(3);
+    let circuit = MyCircuit {
+        prefix: words(&prefix),
+        secret: Value::known(words(&secret)),
+    };
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![instance.clone()]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // Another secret does not give the same digest.
+    let mut other = secret;
+    other[0] ^= 1;
+    let circuit = MyCircuit {
+        prefix: words(&prefix),
+        secret: Value::known(words(&other)),
+    };
+    let prover = MockProver::<bn256::Fr>::run(k, &circuit, vec![instance.clone()]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // Nor does another public prefix.
+    let mut other = prefix;
+    other[127] ^= 1;
+    let circuit = MyCircuit {
+        prefix: words(&other),
+        secret: Value::known(words(&secret)),
+    };
+    let prover = MockProver::<bn256::Fr>::run(k, &circuit, vec![instance]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn reference_compress_differential() {
+    // Compresses random (state, block) pairs in the circuit and with
+    // `reference_compress`. Set SHA512_DIFFERENTIAL_CASES for a longer run.
+    use rand::Rng;
+    use sha512_halo2::reference::reference_compress;
+    use std::cell::RefCell;
+
+    struct MyCircuit {
+        state: [u64; 8],
+        block: [u64; BLOCK_SIZE],
+        output: RefCell<[u64; 8]>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                state: self.state,
+                block: [0; BLOCK_SIZE],
+                output: RefCell::new([0; 8]),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let prev = table16_chip.initialize_with_iv(&mut layouter, &self.state)?;
+            let block = self.block.map(|word| BlockWord(Value::known(word)));
+            let compressed = table16_chip.compress_block(&mut layouter, &prev, block)?;
+            let state = table16_chip.add_feed_forward(&mut layouter, &prev, &compressed)?;
+
+            let words = table16_chip.digest(&mut layouter, &state)?;
+            for (word, out) in words.iter().zip(self.output.borrow_mut().iter_mut()) {
+                word.0.map(|word| *out = word);
+            }
+            Ok(())
+        }
+    }
+
+    let cases = std::env::var("SHA512_DIFFERENTIAL_CASES")
+        .map(|cases| cases.parse().expect("SHA512_DIFFERENTIAL_CASES is not a number"))
+        .unwrap_or(2);
+    let mut rng = XorShiftRng::from_seed([
+        0x3d, 0x8a, 0x51, 0x0c, 0xe7, 0x29, 0x94, 0x6b, 0x1f, 0xd2, 0x40, 0xb8, 0x75, 0x0e,
+        0xc3, 0x96,
+    ]);
+    let k = Table16Config::minimum_k(1);
+    for case in 0..cases {
+        let state: [u64; 8] = rng.gen();
+        let block: [u64; BLOCK_SIZE] = rng.gen();
+
+        let circuit = MyCircuit { state, block, output: RefCell::new([0; 8]) };
+        let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        prover.assert_satisfied();
+        assert_eq!(
+            circuit.output.into_inner(),
+            reference_compress(state, block),
+            "case {}: state {:x?}, block {:x?}",
+            case,
+            state,
+            block
+        );
+    }
+}
+
+#[test]
+fn sha_gadget_merkle_root() {
+    use sha512_halo2::sha512::{ShaGadget, Visibility};
+
+    /// Hashes each pair of nodes of a level into its parent, up to the root.
+    fn merkle_root<F: FieldExt, H: ShaGadget<F>>(
+        chip: &H,
+        layouter: &mut impl Layouter<F>,
+        leaves: Vec<Vec<H::Word>>,
+    ) -> Result<Vec<H::Word>, Error> {
+        if !leaves.len().is_power_of_two() {
+            return Err(Error::Synthesis);
+        }
+        let mut level = leaves;
+        while level.len() > 1 {
+            level = level
+                .chunks_exact(2)
+                .map(|pair| chip.hash(layouter, &pair.concat()))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(level.pop().expect("level.len() == 1"))
+    }
+
+    struct MyCircuit {
+        leaves: Value<[u64; 4]>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = (Table16Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit { leaves: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Table16Chip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            // Each leaf is the digest of one secret word.
+            let leaves = (0..4)
+                .map(|idx| {
+                    let word = BlockWord(self.leaves.map(|leaves| leaves[idx]));
+                    let digest = table16_chip
+                        .hash_partially_public(&mut layouter, &[(word, Visibility::Private)])?;
+                    Ok(digest.to_vec())
+                })
+                .collect::<Result<_, Error>>()?;
+
+            let root = merkle_root(&table16_chip, &mut layouter, leaves)?;
+            let root = root.try_into().expect("root.len() == DIGEST_SIZE");
+            table16_chip.expose_digest_packed(&mut layouter, &root, instance)?;
+            Ok(())
+        }
+    }
+
+    assert_eq!(<Table16Chip as ShaGadget<bn256::Fr>>::DIGEST_BYTES, 64);
+    assert_eq!(<Table16Chip as ShaGadget<bn256::Fr>>::BLOCK_BYTES, 128);
+
+    let leaves = [1u64, 2, 3, 4];
+    let level: Vec<Vec<u8>> = leaves
+        .iter()
+        .map(|leaf| Sha512::digest(leaf.to_be_bytes()).to_vec())
+        .collect();
+    let level: Vec<Vec<u8>> = level
+        .chunks_exact(2)
+        .map(|pair| Sha512::digest(pair.concat()).to_vec())
+        .collect();
+    let root: [u8; 64] = Sha512::digest(level.concat()).as_slice().try_into().unwrap();
+    let instance = circuits::packed_digest_instance(&root);
+
+    // Four one-block leaves, and two blocks for each of the three inner nodes.
+    let k = Table16Config::minimum_k(10);
+    let circuit = MyCircuit { leaves: Value::known(leaves) };
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![instance.clone()]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    // Swapping two leaves changes the root.
+    let circuit = MyCircuit { leaves: Value::known([2, 1, 3, 4]) };
+    let prover = MockProver::<bn256::Fr>::run(k, &circuit, vec![instance]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+#[should_panic(expected = "first difference at word 3")]
+fn assert_digest_eq_reports_first_difference() {
+    let mut digest = digest_from_hex(ABC_DIGEST).unwrap();
+    assert_digest_eq(&digest, ABC_DIGEST);
+
+    digest[3].0 = digest[3].0.map(|word| word ^ 1);
+    digest[6].0 = digest[6].0.map(|word| word ^ 1);
+    assert_digest_eq(&digest, ABC_DIGEST);
+}
+
+#[test]
+fn hash_stream() {
+    // 1200 bytes pad to ten blocks, streamed one at a time.
+    struct MyCircuit {
+        message: Vec<u8>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit { message: self.message.clone() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+
+            let blocks = blocks_from_bytes(&self.message);
+            assert_eq!(blocks.len(), 10);
+            let expected: String =
+                Sha512::digest(&self.message).iter().map(|byte| format!("{:02x}", byte)).collect();
+
+            let chip = Table16Chip::construct(config.clone());
+            let sliced = OtherSha512::hash_blocks(chip, layouter.namespace(|| "slice"), &blocks)?;
+            assert_digest_eq(&sliced.0, &expected);
+
+            let chip = Table16Chip::construct(config.clone());
+            let stream = (0..blocks.len()).map(|idx| blocks[idx]);
+            let streamed = OtherSha512::hash_stream(chip, layouter.namespace(|| "stream"), stream)?;
+            assert_digest_eq(&streamed.0, &expected);
+
+            // Nothing to stream is not a padded message.
+            let chip = Table16Chip::construct(config);
+            let empty =
+                OtherSha512::hash_stream(chip, layouter.namespace(|| "empty"), std::iter::empty());
+            assert!(matches!(empty, Err(Sha512Error::BadBlockCount(0))));
+            Ok(())
+        }
+    }
+
+    let circuit = MyCircuit { message: (0..1200).map(|idx| (idx * 7 + 3) as u8).collect() };
+    // The message is hashed twice.
+    let k = Table16Config::minimum_k(20);
+    let prover = match MockProver::<bn256::Fr>::run(k, &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+#[cfg(all(feature = "secrets", not(feature = "debug-witness")))]
+fn debug_redacts_witnesses() {
+    use sha512_halo2::reference::{reference_compress_rounds, ROUNDS};
+    use std::cell::RefCell;
+
+    // Compresses a block holding a secret word, keeping the Debug output of the block and
+    // the resulting state.
+    struct MyCircuit {
+        block: [u64; BLOCK_SIZE],
+        debug: RefCell<String>,
+    }
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit { block: self.block, debug: RefCell::new(String::new()) }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            let block = self.block.map(|word| BlockWord(Value::known(word)));
+            let state = table16_chip.initialization_vector(&mut layouter)?;
+            let state = table16_chip.compress(&mut layouter, &state, block)?;
+            *self.debug.borrow_mut() = format!("{:?} {:?}", block, state);
+            Ok(())
+        }
+    }
+
+    let secret = 0x0123_4567_89ab_cdef_u64;
+    let mut block = [0; BLOCK_SIZE];
+    block[0] = secret;
+    let circuit = MyCircuit { block, debug: RefCell::new(String::new()) };
+    let prover = match MockProver::<bn256::Fr>::run(Table16Config::minimum_k(1), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+
+    let debug = circuit.debug.into_inner();
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains("true") && !debug.contains("false"));
+    let words = std::iter::once(secret).chain(reference_compress_rounds(IV, block, ROUNDS));
+    for word in words {
+        for value in [word, word as u32 as u64, word >> 32] {
+            assert!(!debug.contains(&value.to_string()), "{} is in {}", value, debug);
+        }
+    }
+
+    // The message of a preimage circuit, as the decimal bytes of a Vec<u8>.
+    let debug = format!("{:?}", circuits::PreimageCircuit::new(b"hunter2"));
+    assert!(!debug.contains("104, 117"), "{}", debug);
+    assert!(debug.contains("num_blocks: 1"));
+}
@@ -0,0 +1,154 @@
+//! SHA-512 test vectors at the message lengths where padding changes shape.
+//!
+//! The empty and one-byte messages are from the NIST SHAVS `SHA512ShortMsg.rsp` file,
+//! and the 112-byte message is the two-block example of FIPS 180-2. The other lengths
+//! hash a fixed byte pattern, with digests from an independent SHA-512 implementation.
+
+#![cfg(feature = "std")]
+
+use hex_literal::hex;
+use sha512_halo2::sha512::{blocks_from_bytes, Sha512, Table16Chip, Table16Config};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+mod common;
+
+use common::assert_digest_eq;
+
+/// Pads and hashes `message` with the full gadget, checking the digest bytes.
+struct VectorCircuit {
+    message: Vec<u8>,
+    digest: [u8; 64],
+}
+
+impl Circuit<bn256::Fr> for VectorCircuit {
+    type Config = Table16Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: self.message.clone(),
+            digest: self.digest,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let blocks = blocks_from_bytes(&self.message);
+        let digest = Sha512::hash_blocks(table16_chip, layouter.namespace(|| "vector"), &blocks)?;
+
+        let expected: String = self.digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        assert_digest_eq(&digest.0, &expected);
+        Ok(())
+    }
+}
+
+/// Checks that `message` hashes to `digest`, and that it pads to `num_blocks` blocks.
+fn check_vector(message: &[u8], num_blocks: usize, digest: [u8; 64]) {
+    assert_eq!(blocks_from_bytes(message).len(), num_blocks);
+
+    let circuit = VectorCircuit {
+        message: message.to_vec(),
+        digest,
+    };
+    let prover = match MockProver::run(Table16Config::minimum_k(num_blocks), &circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+/// The bytes `3, 10, 17, ...`, wrapping modulo 256.
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|idx| (idx * 7 + 3) as u8).collect()
+}
+
+#[test]
+fn empty_message() {
+    // The only block is all padding: the 0x80 byte, zeros, and a bit length of zero.
+    let blocks = blocks_from_bytes(b"");
+    assert_eq!(blocks.len(), 1);
+    for (idx, word) in blocks[0].iter().enumerate() {
+        let expected = if idx == 0 { 0x8000_0000_0000_0000 } else { 0 };
+        word.0.assert_if_known(|word| *word == expected);
+    }
+
+    check_vector(
+        b"",
+        1,
+        hex!("cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"),
+    );
+}
+
+#[test]
+fn one_byte() {
+    check_vector(
+        &hex!("21"),
+        1,
+        hex!("3831a6a6155e509dee59a7f451eb35324d8f8f2df6e3708894740f98fdee23889f4de5adb0c5010dfb555cda77c8ab5dc902094c52de3278f35a75ebc25f093a"),
+    );
+}
+
+#[test]
+fn longest_single_block() {
+    // The 0x80 byte and the 16-byte length exactly fill the block.
+    check_vector(
+        &pattern(111),
+        1,
+        hex!("68cffa6d0d76f309c9ce0d35280939f8e25990c43b7b086ccdf709be35b07d4ddba599541ff2b1c19d34ea49aeafb9659adb7ac3c0b078bb30a22d57fc6687ef"),
+    );
+}
+
+#[test]
+fn shortest_two_blocks() {
+    // The length no longer fits after the 0x80 byte, and spills into a second block.
+    check_vector(
+        b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmno\
+          ijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+        2,
+        hex!("8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"),
+    );
+}
+
+#[test]
+fn one_byte_short_of_a_block() {
+    // Only the 0x80 byte fits in the first block.
+    check_vector(
+        &pattern(127),
+        2,
+        hex!("e0b6a20f1c0c88970a9340152cd5a1c1ecf3d3b8de55102741879438079473540133b812706e5dbec322c8c9523b6fc8c6d16ee626e87ad5fe3d2916afedc369"),
+    );
+}
+
+#[test]
+fn whole_block() {
+    // The padding is a block of its own.
+    check_vector(
+        &pattern(128),
+        2,
+        hex!("99b16f17aa0b969a5b8f08f367719d516e330ccd2660b6f0688ec031dbc783de50a1cd185a2568dba75070a2403d17d4741d163578515dfd2ff756ddfe4d47b1"),
+    );
+}
+
+#[test]
+fn multi_block() {
+    check_vector(
+        &pattern(300),
+        3,
+        hex!("46e56ad30db9ef50f8b6762ba55839737f3fba34ab47863c9daff7b3f58f97fe3465a52dd364560db47f802909ced49093322621ea0aebf8e0696b85ca8f81f0"),
+    );
+}
@@ -0,0 +1,47 @@
+//! The software reference, through the only module built without `std`.
+//!
+//! These tests do not touch `sha512_halo2::sha512`, so they also run with
+//! `cargo test --no-default-features --test reference`.
+
+use hex_literal::hex;
+use sha512_halo2::reference::{
+    i2lebsp, lebs2ip, pad_message, reference_compress, reference_sha512, BLOCK_SIZE, IV,
+};
+
+#[test]
+fn reference_sha512_vectors() {
+    // NIST SHAVS `SHA512ShortMsg.rsp`, and FIPS 180-2, Appendix C.1.
+    assert_eq!(
+        reference_sha512(b""),
+        hex!("cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e")
+    );
+    assert_eq!(
+        reference_sha512(b"abc"),
+        hex!("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f")
+    );
+}
+
+#[test]
+fn pad_message_lengths() {
+    // The length fits after 111 bytes, and spills into a second block from 112.
+    for (len, blocks) in [(0, 1), (111, 1), (112, 2), (128, 2), (239, 2), (240, 3)] {
+        let padded = pad_message(&vec![0x61; len]);
+        assert_eq!(padded.len(), blocks, "{} bytes", len);
+
+        let last = padded.last().unwrap();
+        assert_eq!(last[BLOCK_SIZE - 2], 0);
+        assert_eq!(last[BLOCK_SIZE - 1], len as u64 * 8);
+    }
+
+    let padded = pad_message(b"abc");
+    assert_eq!(padded[0][0], 0x6162638000000000);
+    assert_eq!(reference_compress(IV, padded[0])[0], 0xddaf35a193617aba);
+}
+
+#[test]
+fn bit_conversions() {
+    let bits = i2lebsp::<64>(IV[0].into());
+    assert_eq!(lebs2ip(&bits), IV[0].into());
+    assert!(!bits[0]);
+    assert!(!bits[63]);
+}